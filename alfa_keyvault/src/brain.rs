@@ -1,6 +1,7 @@
 //! VaultBrain - Żywy moduł samouczący się
 
 use std::collections::{HashMap, VecDeque};
+use std::path::Path;
 use chrono::{DateTime, Utc, Timelike, Duration};
 use serde::{Deserialize, Serialize};
 use parking_lot::RwLock;
@@ -40,6 +41,14 @@ pub struct UsageProfile {
     /// Typowe dni tygodnia (0=niedziela, 6=sobota)
     pub daily_access: [u32; 7],
 
+    /// EWMA średniej μ aktywności per (dzień tygodnia, godzina)
+    #[serde(default = "default_buckets")]
+    pub bucket_mean: [[f32; 24]; 7],
+
+    /// EWMA średniego odchylenia bezwzględnego d per (dzień tygodnia, godzina)
+    #[serde(default = "default_buckets")]
+    pub bucket_dev: [[f32; 24]; 7],
+
     /// Najczęściej używane klucze
     pub key_usage: HashMap<String, u64>,
 
@@ -53,11 +62,27 @@ pub struct UsageProfile {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Prior dla świeżych koszyków, by zimny start nie wywoływał alarmu od razu.
+const BUCKET_PRIOR: f32 = 0.1;
+
+/// Współczynnik wygładzania EWMA.
+const EWMA_ALPHA: f32 = 0.1;
+
+/// Mały składnik stabilizujący mianownik z-score.
+const ANOMALY_EPSILON: f32 = 1e-3;
+
+/// Domyślna siatka koszyków z jednolitym priorem.
+fn default_buckets() -> [[f32; 24]; 7] {
+    [[BUCKET_PRIOR; 24]; 7]
+}
+
 impl Default for UsageProfile {
     fn default() -> Self {
         Self {
             hourly_access: [0; 24],
             daily_access: [0; 7],
+            bucket_mean: default_buckets(),
+            bucket_dev: default_buckets(),
             key_usage: HashMap::new(),
             avg_session_duration: 0,
             avg_daily_sessions: 0.0,
@@ -91,6 +116,18 @@ pub struct VaultBrain {
 
     /// Czas ostatniego sukcesu
     last_success: RwLock<Option<DateTime<Utc>>>,
+
+    /// Próg z-score, powyżej którego dostęp uznaje się za nietypowy
+    anomaly_threshold: RwLock<f32>,
+
+    /// Ostatnio wyliczony wynik anomalii (robust z-score)
+    last_anomaly_score: RwLock<f32>,
+
+    /// Trwały dziennik zdarzeń (opcjonalny)
+    journal: Option<crate::journal::BrainJournal>,
+
+    /// Zdarzenia dopisane od ostatniej kompaktacji dziennika
+    events_since_compact: RwLock<usize>,
 }
 
 impl VaultBrain {
@@ -104,6 +141,10 @@ impl VaultBrain {
             lockdown_started: RwLock::new(None),
             failed_attempts: RwLock::new(0),
             last_success: RwLock::new(None),
+            anomaly_threshold: RwLock::new(3.0),
+            last_anomaly_score: RwLock::new(0.0),
+            journal: None,
+            events_since_compact: RwLock::new(0),
         }
     }
 
@@ -113,6 +154,74 @@ impl VaultBrain {
         brain
     }
 
+    /// Tworzy mózg z trwałym dziennikiem i odtwarza stan z dysku
+    ///
+    /// Najpierw stosowany jest kompaktowy snapshot, a następnie dopisane po nim
+    /// zdarzenia odbudowują `events`, `profile`, `failed_attempts`,
+    /// `last_success` oraz stan lockdown.
+    pub fn new_with_store(path: impl AsRef<Path>) -> Result<Self> {
+        let (journal, state) = crate::journal::BrainJournal::open(path)
+            .map_err(|e| AlfaKeyVaultError::BrainError(e.to_string()))?;
+
+        let mut brain = Self::new();
+
+        if let Some(snapshot) = state.snapshot {
+            *brain.profile.write() = snapshot.profile;
+            *brain.failed_attempts.write() = snapshot.failed_attempts;
+            *brain.last_success.write() = snapshot.last_success;
+            *brain.lockdown_active.write() = snapshot.lockdown_active;
+            *brain.lockdown_started.write() = snapshot.lockdown_started;
+        }
+
+        for event in state.events {
+            brain.replay_event(event);
+        }
+
+        brain.journal = Some(journal);
+        Ok(brain)
+    }
+
+    /// Odbudowuje stan z pojedynczego zdarzenia bez ponownego zapisu do dziennika
+    fn replay_event(&self, event: AccessEvent) {
+        self.update_profile(&event);
+
+        if event.success {
+            *self.failed_attempts.write() = 0;
+            *self.last_success.write() = Some(event.timestamp);
+        } else if matches!(event.event_type, AccessEventType::Unlock) {
+            *self.failed_attempts.write() += 1;
+        }
+
+        if matches!(event.event_type, AccessEventType::Lockdown) {
+            *self.lockdown_active.write() = true;
+            *self.lockdown_started.write() = Some(event.timestamp);
+        }
+
+        let mut events = self.events.write();
+        if events.len() >= self.max_events {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Buduje kompaktowy snapshot bieżącego stanu
+    fn build_snapshot(&self) -> crate::journal::BrainSnapshot {
+        crate::journal::BrainSnapshot {
+            profile: self.profile.read().clone(),
+            failed_attempts: *self.failed_attempts.read(),
+            last_success: *self.last_success.read(),
+            lockdown_active: *self.lockdown_active.read(),
+            lockdown_started: *self.lockdown_started.read(),
+        }
+    }
+
+    /// Opróżnia zaległe zapisy dziennika, jeśli jest skonfigurowany
+    pub fn flush(&self) {
+        if let Some(journal) = &self.journal {
+            journal.flush();
+        }
+    }
+
     /// Rejestruje zdarzenie dostępu
     pub fn record_event(&self, event: AccessEvent) {
         let mut events = self.events.write();
@@ -129,10 +238,28 @@ impl VaultBrain {
         }
 
         // Dodaj zdarzenie
+        let journaled = if self.journal.is_some() {
+            Some(event.clone())
+        } else {
+            None
+        };
         if events.len() >= self.max_events {
             events.pop_front();
         }
         events.push_back(event);
+        drop(events);
+
+        // Utrwal zdarzenie i okresowo kompaktuj dziennik
+        if let (Some(journal), Some(event)) = (&self.journal, journaled) {
+            journal.append_event(event);
+            let mut since = self.events_since_compact.write();
+            *since += 1;
+            if *since >= self.max_events {
+                *since = 0;
+                drop(since);
+                journal.compact(self.build_snapshot());
+            }
+        }
 
         // Sprawdź czy potrzebna aktualizacja polityki
         self.check_and_update_policy();
@@ -150,6 +277,27 @@ impl VaultBrain {
         let weekday = event.timestamp.weekday().num_days_from_sunday() as usize;
         profile.daily_access[weekday] += 1;
 
+        // Robust z-score bieżącego dostępu względem historii koszyka, liczony
+        // PRZED aktualizacją modelu, tak by porównanie było z przeszłością.
+        let mu = profile.bucket_mean[weekday][hour];
+        let dev = profile.bucket_dev[weekday][hour];
+        let z = (1.0 - mu) / (1.4826 * dev + ANOMALY_EPSILON);
+        *self.last_anomaly_score.write() = z.max(0.0);
+
+        // Aktualizuj EWMA wszystkich koszyków: bieżący dostaje x=1, reszta x=0,
+        // przez co μ przybliża częstość dostępu, a d jej zmienność.
+        for w in 0..7 {
+            for h in 0..24 {
+                let x = if w == weekday && h == hour { 1.0 } else { 0.0 };
+                let m = profile.bucket_mean[w][h];
+                let new_mean = EWMA_ALPHA * x + (1.0 - EWMA_ALPHA) * m;
+                let new_dev =
+                    EWMA_ALPHA * (x - new_mean).abs() + (1.0 - EWMA_ALPHA) * profile.bucket_dev[w][h];
+                profile.bucket_mean[w][h] = new_mean;
+                profile.bucket_dev[w][h] = new_dev;
+            }
+        }
+
         // Aktualizuj użycie kluczy
         if let Some(ref purpose) = event.key_purpose {
             *profile.key_usage.entry(purpose.clone()).or_insert(0) += 1;
@@ -170,19 +318,18 @@ impl VaultBrain {
             return;
         }
 
-        // Sprawdź czy nietypowa godzina
-        let current_hour = Utc::now().hour() as usize;
-        let profile = self.profile.read();
-        let avg_access = profile.hourly_access.iter().sum::<u32>() / 24;
-        let is_unusual = profile.hourly_access[current_hour] < avg_access / 2;
-
-        drop(profile);
         drop(policy);
 
+        // Wynik anomalii czasu dostępu z modelu EWMA + robust z-score
+        let score = *self.last_anomaly_score.read();
+        let threshold = *self.anomaly_threshold.read();
+        let is_unusual = score > threshold;
+
         // Aktualizuj metryki polityki
         let mut policy = self.policy.write();
         policy.metrics.failed_attempts_24h = failed;
         policy.metrics.unusual_hour_access = is_unusual;
+        policy.metrics.anomaly_score = score;
         policy.metrics.last_access = Some(Utc::now());
         policy.update_threat_level();
     }
@@ -266,6 +413,16 @@ impl VaultBrain {
         profile.hourly_access[current_hour] > avg_access
     }
 
+    /// Zwraca ostatni skalibrowany wynik anomalii (robust z-score) zamiast flagi
+    pub fn predict_access_score(&self) -> f32 {
+        *self.last_anomaly_score.read()
+    }
+
+    /// Ustawia próg z-score uznania dostępu za nietypowy
+    pub fn set_anomaly_threshold(&self, threshold: f32) {
+        *self.anomaly_threshold.write() = threshold;
+    }
+
     /// Pobiera aktualne metryki
     pub fn get_metrics(&self) -> PolicyMetrics {
         self.policy.read().metrics.clone()
@@ -281,6 +438,13 @@ impl VaultBrain {
         *self.policy.write() = policy;
     }
 
+    /// Ustawia okno dostępu z czytelnego tokenu (np. `"business-hours"`)
+    pub fn set_access_schedule(&self, spec: &str) -> Result<()> {
+        let hours = crate::schedule::parse_access_schedule(spec)?;
+        self.policy.write().allowed_hours = Some(hours);
+        Ok(())
+    }
+
     /// Pobiera profil użycia
     pub fn get_profile(&self) -> UsageProfile {
         self.profile.read().clone()
@@ -323,6 +487,7 @@ impl VaultBrain {
                 .map(|(k, v)| (k.clone(), *v))
                 .collect(),
             last_success: *self.last_success.read(),
+            anomaly_score: *self.last_anomaly_score.read(),
         }
     }
 
@@ -356,6 +521,7 @@ pub struct BrainStats {
     pub threat_level: ThreatLevel,
     pub top_keys: Vec<(String, u64)>,
     pub last_success: Option<DateTime<Utc>>,
+    pub anomaly_score: f32,
 }
 
 #[cfg(test)]
@@ -405,6 +571,37 @@ mod tests {
         assert!(brain.is_lockdown_active());
     }
 
+    #[test]
+    fn test_anomaly_score_flags_off_pattern_access() {
+        use chrono::TimeZone;
+
+        let brain = VaultBrain::new();
+        let usual = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap(); // poniedziałek 10:00
+        let unusual = Utc.with_ymd_and_hms(2024, 1, 7, 3, 0, 0).unwrap(); // niedziela 03:00
+
+        let event_at = |ts| AccessEvent {
+            timestamp: ts,
+            event_type: AccessEventType::DeriveKey,
+            key_purpose: None,
+            success: true,
+            duration_ms: 10,
+            source: "test".into(),
+        };
+
+        // Wytrenuj wzorzec w jednym koszyku.
+        for _ in 0..50 {
+            brain.record_event(event_at(usual));
+        }
+        let trained = brain.get_stats().anomaly_score;
+
+        // Dostęp w nietrenowanym koszyku powinien dać wyższy z-score.
+        brain.record_event(event_at(unusual));
+        let off = brain.get_stats().anomaly_score;
+
+        assert!(off > trained);
+        assert!(off > 3.0);
+    }
+
     #[test]
     fn test_access_check() {
         let brain = VaultBrain::new();
@@ -21,6 +21,9 @@ pub struct Argon2Config {
     /// Salt (base64)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub salt: Option<String>,
+    /// Zmierzony czas derywacji (ms), jeśli konfiguracja pochodzi z kalibracji
+    #[serde(skip)]
+    pub measured_time_ms: Option<u64>,
 }
 
 impl Default for Argon2Config {
@@ -31,6 +34,7 @@ impl Default for Argon2Config {
             parallelism: 2,
             output_len: 32,
             salt: None,
+            measured_time_ms: None,
         }
     }
 }
@@ -57,6 +61,7 @@ impl Argon2Config {
             parallelism: 1,
             output_len: 32,
             salt: None,
+            measured_time_ms: None,
         }
     }
 
@@ -68,17 +73,95 @@ impl Argon2Config {
             parallelism: 4,
             output_len: 32,
             salt: None,
+            measured_time_ms: None,
         }
     }
 
     /// Oblicz szacowany czas derywacji (ms)
+    ///
+    /// Jeśli konfiguracja pochodzi z [`Argon2Config::calibrate`], zwracany jest
+    /// rzeczywisty zmierzony czas; heurystyka multiplikatywna służy wyłącznie
+    /// jako fallback, gdy pomiar nie istnieje.
     pub fn estimated_time_ms(&self) -> u64 {
+        if let Some(ms) = self.measured_time_ms {
+            return ms;
+        }
         // Przybliżone oszacowanie
         let base = 50u64; // bazowy czas w ms
         let mem_factor = self.memory_cost_kib as u64 / 1024;
         let time_factor = self.time_cost as u64;
         base * time_factor * mem_factor / self.parallelism as u64
     }
+
+    /// Kalibruje parametry Argon2 tak, aby derywacja trafiła w `target_ms`.
+    ///
+    /// Parallelizm ustawiany jest na dostrojoną liczbę rdzeni, a następnie
+    /// wykonywana jest rzeczywista derywacja z jednorazowym saltem: dopóki
+    /// zmierzony czas jest poniżej celu, koszt pamięci rośnie geometrycznie
+    /// (×1.5) aż do `max_memory_kib`; po osiągnięciu limitu pamięci liniowo
+    /// rośnie `time_cost`, dopóki pomiar po raz pierwszy nie osiągnie
+    /// `target_ms`. Liczba prób jest ograniczona, aby kalibracja nie zawiesiła
+    /// słabego urządzenia. Zwrócona konfiguracja niesie zmierzony czas w
+    /// [`Argon2Config::measured_time_ms`].
+    pub fn calibrate(target_ms: u64, max_memory_kib: u32) -> Argon2Config {
+        use std::time::Instant;
+
+        /// Górny limit prób, by kalibracja pozostała ograniczona czasowo.
+        const MAX_TRIALS: usize = 24;
+        /// Skromny punkt startowy kosztu pamięci (16 MiB).
+        const START_MEMORY_KIB: u32 = 16 * 1024;
+
+        let password = SecretBox::new(Box::new("alfa-calibration-probe".to_string()));
+        let salt = generate_salt();
+
+        let mut config = Argon2Config {
+            time_cost: 1,
+            memory_cost_kib: START_MEMORY_KIB.min(max_memory_kib.max(8)),
+            parallelism: tuned_parallelism(),
+            output_len: 32,
+            salt: None,
+            measured_time_ms: None,
+        };
+
+        let measure = |cfg: &Argon2Config| -> u64 {
+            let start = Instant::now();
+            let _ = derive_kek(&password, &salt, cfg);
+            start.elapsed().as_millis() as u64
+        };
+
+        let mut measured = measure(&config);
+        let mut trials = 1usize;
+
+        // Faza 1: geometryczny wzrost pamięci do limitu.
+        while measured < target_ms && config.memory_cost_kib < max_memory_kib && trials < MAX_TRIALS
+        {
+            let next = ((config.memory_cost_kib as u64 * 3 / 2) as u32).min(max_memory_kib);
+            if next == config.memory_cost_kib {
+                break;
+            }
+            config.memory_cost_kib = next;
+            measured = measure(&config);
+            trials += 1;
+        }
+
+        // Faza 2: pamięć wyczerpana, liniowy wzrost time_cost.
+        while measured < target_ms && trials < MAX_TRIALS {
+            config.time_cost += 1;
+            measured = measure(&config);
+            trials += 1;
+        }
+
+        config.measured_time_ms = Some(measured);
+        config
+    }
+}
+
+/// Dostrojona liczba rdzeni do równoległości Argon2 (połowa CPU, 1..=8).
+fn tuned_parallelism() -> u32 {
+    let cores = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    (cores / 2).max(1).min(8) as u32
 }
 
 /// Derywuje Key Encryption Key (KEK) z hasła
@@ -150,4 +233,22 @@ mod tests {
 
         assert_eq!(kek1.expose_secret(), kek2.expose_secret());
     }
+
+    #[test]
+    fn test_estimated_time_prefers_measurement() {
+        let mut config = Argon2Config::default();
+        assert!(config.estimated_time_ms() > 0);
+        config.measured_time_ms = Some(123);
+        assert_eq!(config.estimated_time_ms(), 123);
+    }
+
+    #[test]
+    fn test_calibrate_meets_target() {
+        let config = Argon2Config::calibrate(40, 32 * 1024);
+        let measured = config.measured_time_ms.expect("calibration records a time");
+        // Pomiar musi istnieć, osiągać cel i być spójny z fallbackiem estimated_time_ms.
+        assert!(measured >= 40);
+        assert_eq!(config.estimated_time_ms(), measured);
+        assert!(config.parallelism >= 1);
+    }
 }
@@ -3,11 +3,13 @@
 mod argon2_kdf;
 mod aead;
 mod hkdf_derive;
+mod recovery;
 mod zeroize_utils;
 
-pub use argon2_kdf::{derive_kek, Argon2Config};
+pub use argon2_kdf::{derive_kek, generate_salt, Argon2Config};
 pub use aead::{encrypt_seed, decrypt_seed, AeadCipher};
 pub use hkdf_derive::{derive_subkey, derive_subkey_fixed};
+pub use recovery::{mnemonic_to_seed, seed_to_mnemonic, WordList};
 pub use zeroize_utils::{zeroize_buffer, SecureBuffer};
 
 use secrecy::SecretVec;
@@ -0,0 +1,197 @@
+//! Odzyskiwanie seeda z frazy mnemonicznej (BIP39)
+//!
+//! Koduje 32-bajtowy master seed do listy słów możliwych do zapisania ręcznie
+//! i odtwarza go bezstratnie, dzięki czemu `derive_key_32`/`derive_epoch_key`
+//! mogą zostać ponownie zainicjowane na nowym urządzeniu.
+
+use sha2::{Digest, Sha256};
+use secrecy::{ExposeSecret, SecretBox, SecretString};
+use zeroize::Zeroize;
+
+use crate::error::{AlfaKeyVaultError, Result};
+
+/// Obsługiwane listy słów. Na razie wymagana jest co najmniej angielska.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordList {
+    /// Standardowa angielska lista 2048 słów BIP39.
+    English,
+}
+
+impl Default for WordList {
+    fn default() -> Self {
+        WordList::English
+    }
+}
+
+/// Wbudowana angielska lista słów BIP39 (2048 słów, po jednym w wierszu).
+const ENGLISH_RAW: &str = include_str!("bip39_english.txt");
+
+impl WordList {
+    /// Surowy tekst listy słów dla danego języka.
+    fn raw(self) -> &'static str {
+        match self {
+            WordList::English => ENGLISH_RAW,
+        }
+    }
+
+    /// Słowo o indeksie `index` (0..2048).
+    fn word_at(self, index: usize) -> &'static str {
+        self.raw().lines().nth(index).unwrap_or("")
+    }
+
+    /// Indeks słowa na liście, jeśli występuje.
+    fn index_of(self, word: &str) -> Option<usize> {
+        self.raw().lines().position(|w| w == word)
+    }
+}
+
+/// Koduje 32-bajtowy seed jako 24-słowną frazę odzyskiwania.
+///
+/// Konstrukcja standardowa: do 256 bitów entropii dołączany jest `256/32 = 8`
+/// bitów sumy kontrolnej (pierwsze bity SHA-256 entropii), co daje 264 bity
+/// dzielone na 24 grupy po 11 bitów indeksujące listę słów. Zwracany
+/// [`SecretString`] jest czyszczony z pamięci po zwolnieniu.
+pub fn seed_to_mnemonic(seed: &SecretBox<[u8; 32]>) -> SecretString {
+    seed_to_mnemonic_with(seed, WordList::English)
+}
+
+/// Jak [`seed_to_mnemonic`], ale z wyborem listy słów.
+pub fn seed_to_mnemonic_with(seed: &SecretBox<[u8; 32]>, list: WordList) -> SecretString {
+    let entropy = seed.expose_secret();
+    let entropy_bits = entropy.len() * 8;
+    let checksum_bits = entropy_bits / 32;
+    let digest = Sha256::digest(entropy);
+
+    let total_bits = entropy_bits + checksum_bits;
+    let mut words: Vec<&str> = Vec::with_capacity(total_bits / 11);
+    let mut bit = 0;
+    while bit < total_bits {
+        let mut index = 0usize;
+        for _ in 0..11 {
+            let byte_pos = bit / 8;
+            let bit_pos = 7 - (bit % 8);
+            let source = if byte_pos < entropy.len() {
+                entropy[byte_pos]
+            } else {
+                digest[byte_pos - entropy.len()]
+            };
+            index = (index << 1) | ((source >> bit_pos) & 1) as usize;
+            bit += 1;
+        }
+        words.push(list.word_at(index));
+    }
+
+    SecretString::from(words.join(" "))
+}
+
+/// Odtwarza 32-bajtowy seed z frazy mnemonicznej, weryfikując sumę kontrolną.
+///
+/// Zwraca błąd, gdy któreś słowo nie należy do listy albo gdy suma kontrolna
+/// się nie zgadza. Bufory pośrednie są zerowane przed powrotem.
+pub fn mnemonic_to_seed(phrase: &str) -> Result<SecretBox<[u8; 32]>> {
+    mnemonic_to_seed_with(phrase, WordList::English)
+}
+
+/// Jak [`mnemonic_to_seed`], ale z wyborem listy słów.
+pub fn mnemonic_to_seed_with(phrase: &str, list: WordList) -> Result<SecretBox<[u8; 32]>> {
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    // 32 bajty entropii → 24 słowa (264 bity).
+    if words.len() != 24 {
+        return Err(AlfaKeyVaultError::MnemonicInvalid(format!(
+            "expected 24 words, got {}",
+            words.len()
+        )));
+    }
+
+    let total_bits = words.len() * 11;
+    let entropy_bits = total_bits / 33 * 32;
+    let checksum_bits = total_bits - entropy_bits;
+
+    let mut bits = vec![0u8; total_bits];
+    for (w, word) in words.iter().enumerate() {
+        let index = list
+            .index_of(word)
+            .ok_or_else(|| AlfaKeyVaultError::MnemonicInvalidWord((*word).to_string()))?;
+        for b in 0..11 {
+            bits[w * 11 + b] = ((index >> (10 - b)) & 1) as u8;
+        }
+    }
+
+    let mut entropy = vec![0u8; entropy_bits / 8];
+    for (i, byte) in entropy.iter_mut().enumerate() {
+        let mut v = 0u8;
+        for b in 0..8 {
+            v = (v << 1) | bits[i * 8 + b];
+        }
+        *byte = v;
+    }
+
+    // Przeliczenie i porównanie bitów sumy kontrolnej.
+    let digest = Sha256::digest(&entropy);
+    for b in 0..checksum_bits {
+        let expected = (digest[b / 8] >> (7 - (b % 8))) & 1;
+        if bits[entropy_bits + b] != expected {
+            bits.zeroize();
+            entropy.zeroize();
+            return Err(AlfaKeyVaultError::MnemonicChecksumMismatch);
+        }
+    }
+    bits.zeroize();
+
+    let mut seed = [0u8; 32];
+    if entropy.len() != seed.len() {
+        entropy.zeroize();
+        return Err(AlfaKeyVaultError::MnemonicInvalid(
+            "invalid entropy length".to_string(),
+        ));
+    }
+    seed.copy_from_slice(&entropy);
+    entropy.zeroize();
+
+    Ok(SecretBox::new(Box::new(seed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_round_trip() {
+        let seed = SecretBox::new(Box::new([0x13u8; 32]));
+        let phrase = seed_to_mnemonic(&seed);
+        let restored = mnemonic_to_seed(phrase.expose_secret()).unwrap();
+        assert_eq!(restored.expose_secret(), seed.expose_secret());
+    }
+
+    #[test]
+    fn test_phrase_is_24_words() {
+        let seed = SecretBox::new(Box::new([0x2bu8; 32]));
+        let phrase = seed_to_mnemonic(&seed);
+        assert_eq!(phrase.expose_secret().split_whitespace().count(), 24);
+    }
+
+    #[test]
+    fn test_invalid_word_rejected() {
+        let seed = SecretBox::new(Box::new([0x55u8; 32]));
+        let phrase = seed_to_mnemonic(&seed);
+        let mut words: Vec<&str> = phrase.expose_secret().split(' ').collect();
+        words[0] = "notaword";
+        assert!(matches!(
+            mnemonic_to_seed(&words.join(" ")),
+            Err(AlfaKeyVaultError::MnemonicInvalidWord(_))
+        ));
+    }
+
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let seed = SecretBox::new(Box::new([0x7au8; 32]));
+        let phrase = seed_to_mnemonic(&seed);
+        let mut words: Vec<String> = phrase.expose_secret().split(' ').map(String::from).collect();
+        // Zamiana ostatniego słowa na inne poprawne psuje sumę kontrolną.
+        words[23] = if words[23] == "zoo" { "zero".into() } else { "zoo".into() };
+        assert!(matches!(
+            mnemonic_to_seed(&words.join(" ")),
+            Err(AlfaKeyVaultError::MnemonicChecksumMismatch)
+        ));
+    }
+}
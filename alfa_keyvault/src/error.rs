@@ -57,6 +57,24 @@ pub enum AlfaKeyVaultError {
 
     #[error("Rotation required")]
     RotationRequired,
+
+    #[error("Invalid mnemonic: {0}")]
+    MnemonicInvalid(String),
+
+    #[error("Invalid mnemonic word: {0}")]
+    MnemonicInvalidWord(String),
+
+    #[error("Mnemonic checksum mismatch")]
+    MnemonicChecksumMismatch,
+
+    #[error("Invalid PIN")]
+    InvalidPin,
+
+    #[error("Too many failed PIN attempts - locked out")]
+    TooManyAttempts,
+
+    #[error("Invalid schedule specification: {0}")]
+    InvalidSchedule(String),
 }
 
 pub type Result<T> = std::result::Result<T, AlfaKeyVaultError>;
@@ -69,13 +87,14 @@ impl AlfaKeyVaultError {
                 | Self::MaxAttemptsReached(_)
                 | Self::LockdownActive
                 | Self::PolicyViolation(_)
+                | Self::TooManyAttempts
         )
     }
 
     pub fn requires_lockdown(&self) -> bool {
         matches!(
             self,
-            Self::ThreatDetected(_) | Self::MaxAttemptsReached(_)
+            Self::ThreatDetected(_) | Self::MaxAttemptsReached(_) | Self::TooManyAttempts
         )
     }
 }
@@ -0,0 +1,272 @@
+//! Trwały, odporny na awarie dziennik zdarzeń dla [`crate::brain::VaultBrain`]
+//!
+//! Modelowany na warstwie klucz-wartość opartej o pliki: dopisywany (append-only)
+//! dziennik zdarzeń z nagłówkiem długości oraz okresowo przepisywany, kompaktowy
+//! snapshot [`UsageProfile`] i liczników. Zapisy są przekazywane do wątku
+//! zrzutu w tle przez kanał o ograniczonej pojemności, dzięki czemu
+//! `record_event` pozostaje nieblokujące; [`BrainJournal::flush`] i `Drop`
+//! opróżniają zaległe zapisy.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::brain::{AccessEvent, UsageProfile};
+
+/// Kompaktowy snapshot stanu mózgu zapisywany obok dziennika.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrainSnapshot {
+    pub profile: UsageProfile,
+    pub failed_attempts: u32,
+    pub last_success: Option<DateTime<Utc>>,
+    pub lockdown_active: bool,
+    pub lockdown_started: Option<DateTime<Utc>>,
+}
+
+/// Odtworzony stan po odczytaniu snapshotu i dziennika.
+#[derive(Debug, Default)]
+pub struct ReplayState {
+    pub snapshot: Option<BrainSnapshot>,
+    pub events: Vec<AccessEvent>,
+}
+
+/// Pojemność kanału do wątku zrzutu.
+const CHANNEL_CAPACITY: usize = 1024;
+
+enum JournalMsg {
+    Event(Box<AccessEvent>),
+    Snapshot(Box<BrainSnapshot>),
+    Flush(SyncSender<()>),
+    Shutdown,
+}
+
+/// Dziennik zdarzeń z wątkiem zrzutu w tle.
+pub struct BrainJournal {
+    tx: SyncSender<JournalMsg>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl BrainJournal {
+    /// Otwiera dziennik pod `path`, odtwarza stan i startuje wątek zrzutu.
+    ///
+    /// Snapshot trzymany jest w `<path>.snapshot`; najpierw czytany jest
+    /// snapshot, a potem dopisane po nim zdarzenia z dziennika.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<(Self, ReplayState)> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let snap_path = snapshot_path(&path);
+
+        let snapshot = read_snapshot(&snap_path);
+        let events = read_events(&path);
+
+        let (tx, rx) = sync_channel::<JournalMsg>(CHANNEL_CAPACITY);
+        let writer_path = path.clone();
+        let handle = thread::Builder::new()
+            .name("vault-brain-journal".to_string())
+            .spawn(move || writer_loop(writer_path, snap_path, rx))?;
+
+        Ok((
+            Self {
+                tx,
+                handle: Some(handle),
+            },
+            ReplayState { snapshot, events },
+        ))
+    }
+
+    /// Dopisuje zdarzenie bez blokowania (odrzucane, gdy kanał jest pełny).
+    pub fn append_event(&self, event: AccessEvent) {
+        let _ = self.tx.try_send(JournalMsg::Event(Box::new(event)));
+    }
+
+    /// Zleca kompaktację: zapisuje snapshot i przycina dziennik.
+    pub fn compact(&self, snapshot: BrainSnapshot) {
+        let _ = self.tx.try_send(JournalMsg::Snapshot(Box::new(snapshot)));
+    }
+
+    /// Opróżnia zaległe zapisy i czeka na ich utrwalenie.
+    pub fn flush(&self) {
+        let (ack_tx, ack_rx) = sync_channel::<()>(0);
+        if self.tx.send(JournalMsg::Flush(ack_tx)).is_ok() {
+            let _ = ack_rx.recv();
+        }
+    }
+}
+
+impl Drop for BrainJournal {
+    fn drop(&mut self) {
+        let _ = self.tx.send(JournalMsg::Shutdown);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Ścieżka pliku snapshotu dla danego dziennika.
+fn snapshot_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".snapshot");
+    path.with_file_name(name)
+}
+
+fn writer_loop(path: PathBuf, snap_path: PathBuf, rx: Receiver<JournalMsg>) {
+    let mut file = open_append(&path);
+
+    while let Ok(msg) = rx.recv() {
+        match msg {
+            JournalMsg::Event(event) => {
+                if let Some(f) = file.as_mut() {
+                    let _ = write_record(f, &event);
+                }
+            }
+            JournalMsg::Snapshot(snapshot) => {
+                let _ = write_snapshot(&snap_path, &snapshot);
+                // Kompaktacja: przytnij dziennik po utrwaleniu snapshotu. Awaria
+                // pomiędzy zapisem snapshotu a utrwaleniem tego przycięcia
+                // skutkowałaby powtórnym odtworzeniem już skompaktowanych
+                // zdarzeń przy starcie, więc przycięcie musi zostać
+                // jawnie fsync'owane (plik i katalog nadrzędny) zanim
+                // wznowimy zwykłe dopisywanie.
+                let _ = truncate_journal(&path);
+                file = open_append(&path);
+            }
+            JournalMsg::Flush(ack) => {
+                if let Some(f) = file.as_mut() {
+                    let _ = f.sync_all();
+                }
+                let _ = ack.send(());
+            }
+            JournalMsg::Shutdown => break,
+        }
+    }
+
+    if let Some(mut f) = file {
+        let _ = f.sync_all();
+    }
+}
+
+fn open_append(path: &Path) -> Option<File> {
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+/// Przycina dziennik do pustego pliku i fsync'uje zarówno plik, jak
+/// i katalog nadrzędny, tak by przycięcie przetrwało awarię zaraz po
+/// kompaktacji.
+fn truncate_journal(path: &Path) -> std::io::Result<()> {
+    let f = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    f.sync_all()?;
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+/// Zapisuje jedno zdarzenie jako `[u32 BE długość][JSON]`.
+fn write_record(file: &mut File, event: &AccessEvent) -> std::io::Result<()> {
+    let body = serde_json::to_vec(event).map_err(std::io::Error::other)?;
+    let len = body.len() as u32;
+    file.write_all(&len.to_be_bytes())?;
+    file.write_all(&body)?;
+    Ok(())
+}
+
+/// Atomowo zapisuje snapshot (zapis do `.tmp` i `rename`).
+fn write_snapshot(path: &Path, snapshot: &BrainSnapshot) -> std::io::Result<()> {
+    let body = serde_json::to_vec(snapshot).map_err(std::io::Error::other)?;
+    let tmp = path.with_extension("tmp");
+    let mut f = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&tmp)?;
+    f.write_all(&body)?;
+    f.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn read_snapshot(path: &Path) -> Option<BrainSnapshot> {
+    let bytes = fs::read(path).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Odczytuje wszystkie poprawne, długościowo-prefiksowane zdarzenia dziennika.
+fn read_events(path: &Path) -> Vec<AccessEvent> {
+    let mut events = Vec::new();
+    let mut file = match File::open(path) {
+        Ok(f) => f,
+        Err(_) => return events,
+    };
+    let mut buf = Vec::new();
+    if file.read_to_end(&mut buf).is_err() {
+        return events;
+    }
+
+    let mut pos = 0usize;
+    while pos + 4 <= buf.len() {
+        let len = u32::from_be_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]]) as usize;
+        pos += 4;
+        // Rekord ucięty przez awarię - przerwij odtwarzanie.
+        if pos + len > buf.len() {
+            break;
+        }
+        if let Ok(event) = serde_json::from_slice::<AccessEvent>(&buf[pos..pos + len]) {
+            events.push(event);
+        }
+        pos += len;
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brain::AccessEventType;
+
+    fn sample_event(source: &str) -> AccessEvent {
+        AccessEvent {
+            timestamp: Utc::now(),
+            event_type: AccessEventType::Unlock,
+            key_purpose: Some("ALFA:test".into()),
+            success: true,
+            duration_ms: 1,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_journal_roundtrip() {
+        let path = std::env::temp_dir().join(format!("alfa_journal_{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(snapshot_path(&path));
+
+        {
+            let (journal, state) = BrainJournal::open(&path).unwrap();
+            assert!(state.events.is_empty());
+            journal.append_event(sample_event("a"));
+            journal.append_event(sample_event("b"));
+            journal.flush();
+        }
+
+        let (_journal, state) = BrainJournal::open(&path).unwrap();
+        assert_eq!(state.events.len(), 2);
+        assert_eq!(state.events[1].source, "b");
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(snapshot_path(&path));
+    }
+}
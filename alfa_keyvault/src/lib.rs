@@ -13,13 +13,17 @@ pub mod error;
 pub mod crypto;
 pub mod vault;
 pub mod policy;
+pub mod pin;
+pub mod schedule;
 pub mod brain;
+pub mod journal;
 pub mod snapshot;
 
 // Re-exports
 pub use error::{AlfaKeyVaultError, Result};
 pub use vault::{AlfaKeyVault, VaultConfig, VaultStatus};
 pub use policy::{AutoPolicy, ThreatLevel};
+pub use pin::PinGuard;
 pub use brain::VaultBrain;
 pub use snapshot::PqxSnapshot;
 
@@ -0,0 +1,335 @@
+//! Autoryzacja PIN-em w stylu karty inteligentnej
+//!
+//! Przechowywany jest wyłącznie solony hash Argon2id PIN-u (poprzez
+//! [`derive_kek`]). Licznik nieudanych prób maleje monotonicznie i jest
+//! utrwalany razem z sejfem - po jego wyzerowaniu wymuszana jest blokada na
+//! `lockout_seconds`, zanim kolejna próba zostanie w ogóle przyjęta. Poprawny
+//! PIN w oknie prób przywraca pełną pulę prób i zwraca derywowany KEK.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use secrecy::{ExposeSecret, SecretBox};
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{derive_kek, generate_salt, Argon2Config};
+use crate::error::{AlfaKeyVaultError, Result};
+use crate::policy::AutoPolicy;
+
+/// Bramka PIN z ograniczaniem prób w stylu smartcard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinGuard {
+    /// Salt Argon2id (base64).
+    salt: String,
+    /// Solony hash PIN-u (base64) - nieczytelny bez wcześniejszej weryfikacji.
+    hash: String,
+    /// Parametry derywacji KEK z PIN-u.
+    argon2: Argon2Config,
+    /// Pozostałe próby przed blokadą (malejący licznik).
+    remaining: u32,
+    /// Maksymalna liczba prób w oknie.
+    max_attempts: u32,
+    /// Czas blokady po wyczerpaniu prób (sekundy).
+    lockout_seconds: u64,
+    /// Moment, do którego bramka pozostaje zablokowana.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locked_until: Option<DateTime<Utc>>,
+    /// Plik, do którego bramka utrwala się po każdej zmianie licznika prób.
+    /// `None` dla bramek tylko-w-pamięci (np. w testach) - bez ścieżki
+    /// [`Self::persist`] jest no-opem.
+    #[serde(skip)]
+    persist_path: Option<PathBuf>,
+}
+
+impl PinGuard {
+    /// Tworzy bramkę dla podanego PIN-u, dziedzicząc limity z polityki.
+    ///
+    /// Bramka pozostaje tylko w pamięci - by licznik prób przeżył restart,
+    /// użyj [`Self::new_persisted`].
+    pub fn new(pin: &str, policy: &AutoPolicy) -> Result<Self> {
+        let salt = generate_salt();
+        let argon2 = Argon2Config {
+            memory_cost_kib: policy.argon2_memory_mib * 1024,
+            time_cost: policy.argon2_time_cost,
+            parallelism: policy.argon2_parallelism,
+            ..Argon2Config::default()
+        };
+
+        let derived = derive_pin(pin, &salt, &argon2)?;
+        Ok(Self {
+            salt: b64(&salt),
+            hash: b64(derived.expose_secret()),
+            argon2,
+            remaining: policy.max_failed_attempts,
+            max_attempts: policy.max_failed_attempts,
+            lockout_seconds: policy.lockout_seconds,
+            locked_until: None,
+            persist_path: None,
+        })
+    }
+
+    /// Jak [`Self::new`], ale utrwala bramkę pod `path` natychmiast i po
+    /// każdej kolejnej zmianie licznika prób, tak by blokada przeżyła awarię
+    /// w trakcie weryfikacji.
+    pub fn new_persisted<P: Into<PathBuf>>(
+        pin: &str,
+        policy: &AutoPolicy,
+        path: P,
+    ) -> Result<Self> {
+        let mut guard = Self::new(pin, policy)?;
+        guard.persist_path = Some(path.into());
+        guard.persist()?;
+        Ok(guard)
+    }
+
+    /// Wczytuje bramkę utrwaloną wcześniej przez [`Self::persist`], wiążąc ją
+    /// z powrotem z tym samym plikiem, tak by kolejne zmiany licznika nadal
+    /// się utrwalały.
+    pub fn load<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let bytes = fs::read(&path)?;
+        let mut guard: Self = serde_json::from_slice(&bytes)?;
+        guard.persist_path = Some(path);
+        Ok(guard)
+    }
+
+    /// Atomowo utrwala bieżący stan bramki (zapis do `.tmp`, fsync, rename),
+    /// albo nic nie robi dla bramek bez [`Self::persist_path`].
+    fn persist(&self) -> Result<()> {
+        let Some(path) = &self.persist_path else {
+            return Ok(());
+        };
+        let body = serde_json::to_vec(self)?;
+        let tmp = path.with_extension("tmp");
+        let mut f = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&tmp)?;
+        f.write_all(&body)?;
+        f.sync_all()?;
+        fs::rename(&tmp, path)?;
+        if let Some(parent) = path.parent() {
+            if let Ok(dir) = fs::File::open(parent) {
+                let _ = dir.sync_all();
+            }
+        }
+        Ok(())
+    }
+
+    /// Pozostała liczba prób (0 gdy trwa blokada).
+    pub fn remaining_attempts(&self) -> u32 {
+        if self.is_locked_out() {
+            0
+        } else {
+            self.remaining
+        }
+    }
+
+    /// Czy bramka jest aktualnie zablokowana czasowo.
+    pub fn is_locked_out(&self) -> bool {
+        match self.locked_until {
+            Some(until) => Utc::now() < until,
+            None => false,
+        }
+    }
+
+    /// Weryfikuje PIN w czasie stałym i - przy sukcesie - zwraca derywowany KEK.
+    ///
+    /// Licznik nieudanych prób jest zmniejszany *przed* porównaniem, tak aby
+    /// awaria w trakcie weryfikacji nie przywróciła budżetu prób. Każda
+    /// nieudana próba podbija [`crate::policy::PolicyMetrics`], dzięki czemu
+    /// `update_threat_level` eskaluje.
+    pub fn verify_pin(
+        &mut self,
+        pin: &str,
+        policy: &mut AutoPolicy,
+    ) -> Result<SecretBox<[u8; 32]>> {
+        // Trwająca blokada odrzuca próbę bez jej zliczania.
+        if self.is_locked_out() {
+            return Err(AlfaKeyVaultError::TooManyAttempts);
+        }
+        // Okno blokady minęło - pozwól na nowy zestaw prób.
+        if self.locked_until.is_some() {
+            self.locked_until = None;
+            self.remaining = self.max_attempts;
+        }
+        if self.remaining == 0 {
+            self.start_lockout()?;
+            return Err(AlfaKeyVaultError::TooManyAttempts);
+        }
+
+        // Zapis licznika przed porównaniem (write-before-compare), utrwalony
+        // na dysku zanim w ogóle porównamy PIN, tak by awaria w trakcie
+        // weryfikacji nie przywróciła budżetu prób.
+        self.remaining -= 1;
+        self.persist()?;
+        policy.metrics.failed_attempts_24h = policy.metrics.failed_attempts_24h.saturating_add(1);
+        if self.remaining < self.max_attempts.saturating_sub(1) {
+            policy.metrics.rapid_access_attempts = true;
+        }
+
+        let salt = unb64(&self.salt)?;
+        let candidate = derive_pin(pin, &salt, &self.argon2)?;
+        let stored = unb64(&self.hash)?;
+
+        if ct_eq(candidate.expose_secret(), &stored) {
+            // Poprawny PIN: zwolnij licznik i wydaj KEK.
+            self.remaining = self.max_attempts;
+            self.persist()?;
+            // Próba okazała się udana - cofnij jej wstępne zliczenie.
+            policy.metrics.failed_attempts_24h = policy.metrics.failed_attempts_24h.saturating_sub(1);
+            policy.metrics.rapid_access_attempts = false;
+            policy.update_threat_level();
+            return Ok(candidate);
+        }
+
+        policy.update_threat_level();
+        if self.remaining == 0 {
+            self.start_lockout()?;
+            return Err(AlfaKeyVaultError::TooManyAttempts);
+        }
+        Err(AlfaKeyVaultError::InvalidPin)
+    }
+
+    /// Rozpoczyna okno blokady i odkłada licznik na następny zestaw prób,
+    /// utrwalając oba natychmiast tak by blokada przeżyła restart.
+    fn start_lockout(&mut self) -> Result<()> {
+        self.locked_until = Some(Utc::now() + Duration::seconds(self.lockout_seconds as i64));
+        self.remaining = self.max_attempts;
+        self.persist()
+    }
+}
+
+/// Derywuje 32-bajtowy klucz z PIN-u przez Argon2id.
+fn derive_pin(pin: &str, salt: &[u8], config: &Argon2Config) -> Result<SecretBox<[u8; 32]>> {
+    let secret = SecretBox::new(Box::new(pin.to_string()));
+    derive_kek(&secret, salt, config)
+}
+
+/// Porównanie bajtów w czasie stałym.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+fn b64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn unb64(s: &str) -> Result<Vec<u8>> {
+    Ok(base64::engine::general_purpose::STANDARD.decode(s)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_policy() -> AutoPolicy {
+        // Lekki Argon2, by testy PIN-u były szybkie.
+        let mut p = AutoPolicy::default();
+        p.argon2_memory_mib = 8;
+        p.argon2_time_cost = 1;
+        p.argon2_parallelism = 1;
+        p.max_failed_attempts = 3;
+        p.lockout_seconds = 300;
+        p
+    }
+
+    #[test]
+    fn test_correct_pin_releases_kek() {
+        let policy = fast_policy();
+        let mut guard = PinGuard::new("1379", &policy).unwrap();
+        let mut policy = policy;
+        let kek = guard.verify_pin("1379", &mut policy).unwrap();
+        assert_eq!(kek.expose_secret().len(), 32);
+        assert_eq!(guard.remaining_attempts(), policy.max_failed_attempts);
+    }
+
+    #[test]
+    fn test_wrong_pin_decrements_and_escalates() {
+        let mut policy = fast_policy();
+        let mut guard = PinGuard::new("1379", &policy).unwrap();
+        assert!(matches!(
+            guard.verify_pin("0000", &mut policy),
+            Err(AlfaKeyVaultError::InvalidPin)
+        ));
+        assert_eq!(guard.remaining_attempts(), policy.max_failed_attempts - 1);
+        assert_eq!(policy.metrics.failed_attempts_24h, 1);
+    }
+
+    #[test]
+    fn test_lockout_after_exhausting_attempts() {
+        let mut policy = fast_policy();
+        let mut guard = PinGuard::new("1379", &policy).unwrap();
+        for _ in 0..policy.max_failed_attempts {
+            let _ = guard.verify_pin("0000", &mut policy);
+        }
+        assert!(guard.is_locked_out());
+        assert!(matches!(
+            guard.verify_pin("1379", &mut policy),
+            Err(AlfaKeyVaultError::TooManyAttempts)
+        ));
+    }
+
+    fn temp_pin_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("alfa_pin_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_persisted_guard_survives_reload() {
+        let path = temp_pin_path("reload");
+        let _ = fs::remove_file(&path);
+
+        let mut policy = fast_policy();
+        {
+            let mut guard = PinGuard::new_persisted("1379", &policy, &path).unwrap();
+            assert!(matches!(
+                guard.verify_pin("0000", &mut policy),
+                Err(AlfaKeyVaultError::InvalidPin)
+            ));
+        }
+
+        // Simulate a restart: reload the guard from disk and confirm the
+        // decremented counter (not a fresh one) came back with it.
+        let mut reloaded = PinGuard::load(&path).unwrap();
+        assert_eq!(reloaded.remaining_attempts(), policy.max_failed_attempts - 1);
+        let kek = reloaded.verify_pin("1379", &mut policy).unwrap();
+        assert_eq!(kek.expose_secret().len(), 32);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("tmp"));
+    }
+
+    #[test]
+    fn test_lockout_persists_across_reload() {
+        let path = temp_pin_path("lockout");
+        let _ = fs::remove_file(&path);
+
+        let mut policy = fast_policy();
+        {
+            let mut guard = PinGuard::new_persisted("1379", &policy, &path).unwrap();
+            for _ in 0..policy.max_failed_attempts {
+                let _ = guard.verify_pin("0000", &mut policy);
+            }
+            assert!(guard.is_locked_out());
+        }
+
+        // A process restart right after the lockout must not hand the caller
+        // a fresh budget of attempts.
+        let reloaded = PinGuard::load(&path).unwrap();
+        assert!(reloaded.is_locked_out());
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("tmp"));
+    }
+}
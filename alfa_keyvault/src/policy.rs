@@ -4,6 +4,9 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, Duration};
 use std::collections::HashMap;
 
+use crate::crypto::Argon2Config;
+use crate::error::AlfaKeyVaultError;
+
 /// Poziom zagrożenia
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -136,6 +139,15 @@ impl AutoPolicy {
         }
     }
 
+    /// Tworzy politykę z czasem blokady podanym czytelnym DSL-em (np. `"15m"`)
+    pub fn with_lockout(spec: &str) -> Result<Self, AlfaKeyVaultError> {
+        let lockout_seconds = crate::schedule::parse_duration(spec)?;
+        Ok(Self {
+            lockout_seconds,
+            ..Default::default()
+        })
+    }
+
     /// Tworzy politykę dla słabych urządzeń
     pub fn low_resource() -> Self {
         Self {
@@ -147,8 +159,13 @@ impl AutoPolicy {
         }
     }
 
-    /// Sprawdza czy hasło spełnia wymagania polityki
-    pub fn validate_password(&self, password: &str) -> Result<(), Vec<String>> {
+    /// Sprawdza siłę hasła i zwraca jego wynik 0-100 lub listę słabości
+    ///
+    /// Twarde bramki (długość, cyfra, znak specjalny) obowiązują nadal, ale
+    /// binarny test zastąpiono estymatorem entropii: wynik to efektywne bity
+    /// odwzorowane na 0-100, a rosnący [`ThreatLevel`] podnosi wymagany próg
+    /// (Normal→40, Elevated→60, High/Critical→80).
+    pub fn validate_password(&self, password: &str) -> Result<u32, Vec<String>> {
         let mut errors = Vec::new();
 
         if password.len() < self.min_password_length {
@@ -166,13 +183,34 @@ impl AutoPolicy {
             errors.push("Password must contain at least one special character".to_string());
         }
 
+        let (score, mut weaknesses) = estimate_password_strength(password);
+        let required = self.min_strength_score();
+        if score < required {
+            errors.push(format!(
+                "Password strength {}/100 below required {} for threat level {}",
+                score,
+                required,
+                self.threat_level.as_str()
+            ));
+            errors.append(&mut weaknesses);
+        }
+
         if errors.is_empty() {
-            Ok(())
+            Ok(score)
         } else {
             Err(errors)
         }
     }
 
+    /// Minimalny wymagany wynik siły hasła dla bieżącego poziomu zagrożenia
+    pub fn min_strength_score(&self) -> u32 {
+        match self.threat_level {
+            ThreatLevel::Normal => 40,
+            ThreatLevel::Elevated => 60,
+            ThreatLevel::High | ThreatLevel::Critical => 80,
+        }
+    }
+
     /// Sprawdza czy aktualna godzina jest dozwolona
     pub fn is_access_allowed_now(&self) -> bool {
         match &self.allowed_hours {
@@ -201,6 +239,13 @@ impl AutoPolicy {
             score += 30;
         }
 
+        // Stopniowa anomalia czasu dostępu: im wyższy z-score, tym więcej punktów
+        // (nasycenie przy z≈6 → 30 punktów), zamiast binarnej flagi.
+        if self.metrics.anomaly_score > 0.0 {
+            let graded = (self.metrics.anomaly_score / 6.0).clamp(0.0, 1.0) * 30.0;
+            score += graded as u32;
+        }
+
         // Nowe urządzenie
         if self.metrics.new_device_detected {
             score += 15;
@@ -231,6 +276,24 @@ impl AutoPolicy {
         self.updated_at = Utc::now();
     }
 
+    /// Empiryczna kalibracja Argon2 pod zadany czas derywacji.
+    ///
+    /// Mierzy rzeczywisty koszt [`derive_kek`] i zapisuje dostrojone parametry
+    /// oraz zaobserwowany czas do [`PolicyMetrics::avg_derivation_time_ms`].
+    pub fn calibrate_argon2(&mut self, target_ms: u64, max_memory_kib: u32) -> Argon2Config {
+        let config = Argon2Config::calibrate(target_ms, max_memory_kib);
+
+        self.argon2_memory_mib = config.memory_cost_kib / 1024;
+        self.argon2_time_cost = config.time_cost;
+        self.argon2_parallelism = config.parallelism;
+        if let Some(ms) = config.measured_time_ms {
+            self.metrics.avg_derivation_time_ms = ms;
+        }
+        self.updated_at = Utc::now();
+
+        config
+    }
+
     /// Sprawdza czy wymagana jest rotacja kluczy
     pub fn is_rotation_required(&self, last_rotation: DateTime<Utc>) -> bool {
         let rotation_interval = Duration::days(self.key_rotation_days as i64);
@@ -256,6 +319,10 @@ pub struct PolicyMetrics {
     /// Średni czas derywacji (ms)
     pub avg_derivation_time_ms: u64,
 
+    /// Stopniowy wynik anomalii czasu dostępu (robust z-score)
+    #[serde(default)]
+    pub anomaly_score: f32,
+
     /// Liczba dostępów dziennie
     pub daily_access_count: u32,
 
@@ -266,6 +333,159 @@ pub struct PolicyMetrics {
     pub last_access: Option<DateTime<Utc>>,
 }
 
+/// Niewielka wbudowana lista najczęstszych haseł (posortowana wg popularności).
+const COMMON_PASSWORDS: &[&str] = &[
+    "password", "123456", "123456789", "qwerty", "12345678", "111111", "abc123",
+    "password1", "1234567", "iloveyou", "admin", "welcome", "monkey", "dragon",
+    "letmein", "login", "princess", "qwerty123", "sunshine", "master",
+];
+
+/// Wiersze klawiatury używane do wykrywania "keyboard walk".
+const KEYBOARD_ROWS: &[&str] = &["qwertyuiop", "asdfghjkl", "zxcvbnm", "1234567890"];
+
+/// Szacuje siłę hasła: zwraca wynik 0-100 oraz listę konkretnych słabości.
+///
+/// Entropię liczy się z przestrzeni wyszukiwania wynikającej z użytych klas
+/// znaków, a następnie karze wykryte wzorce (ciągi rosnące/malejące,
+/// powtórzenia, marsze po klawiaturze, słowa słownikowe), obniżając efektywną
+/// długość w stronę wkładu `log2(rank)` zamiast pełnej losowej entropii.
+pub fn estimate_password_strength(password: &str) -> (u32, Vec<String>) {
+    let mut weaknesses = Vec::new();
+
+    if password.is_empty() {
+        return (0, vec!["Password is empty".to_string()]);
+    }
+
+    let chars: Vec<char> = password.chars().collect();
+    let len = chars.len() as f64;
+
+    // Przestrzeń wyszukiwania na podstawie klas znaków.
+    let mut space = 0u32;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        space += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        space += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        space += 10;
+    }
+    if chars.iter().any(|c| !c.is_alphanumeric()) {
+        space += 33;
+    }
+    let per_char_bits = (space.max(2) as f64).log2();
+
+    // Startujemy od pełnej długości i odejmujemy kary za wzorce.
+    let mut effective_len = len;
+
+    // Ciągi rosnące/malejące oraz marsze po klawiaturze.
+    let seq = longest_run(&chars, &mut weaknesses);
+    if seq >= 3 {
+        effective_len -= (seq - 1) as f64;
+    }
+
+    // Powtórzone znaki (aaaa) i powtórzone bigramy (abab).
+    let rep = repeat_penalty(&chars, &mut weaknesses);
+    effective_len -= rep;
+
+    // Słowa słownikowe / popularne hasła (case-folded).
+    let folded = password.to_lowercase();
+    if let Some(rank) = COMMON_PASSWORDS.iter().position(|w| *w == folded) {
+        weaknesses.push("Password is a well-known common password".to_string());
+        // Wkład log2(rank+2) (rank liczony od zera) zamiast pełnej entropii.
+        effective_len = ((rank + 2) as f64).log2() / per_char_bits;
+    } else if let Some((word, rank)) = COMMON_PASSWORDS
+        .iter()
+        .enumerate()
+        .find(|(_, w)| w.len() >= 4 && folded.contains(**w))
+        .map(|(r, w)| (*w, r))
+    {
+        weaknesses.push(format!("Password contains the common word '{}'", word));
+        let contrib = ((rank + 2) as f64).log2() / per_char_bits;
+        effective_len -= word.len() as f64 - contrib;
+    }
+
+    let effective_len = effective_len.max(1.0);
+    let effective_bits = effective_len * per_char_bits;
+
+    // 80 bitów efektywnych ≈ 100 punktów.
+    let score = ((effective_bits / 80.0) * 100.0).round().clamp(0.0, 100.0) as u32;
+    (score, weaknesses)
+}
+
+/// Najdłuższy ciąg kolejnych znaków (±1 lub sąsiadujących na klawiaturze).
+fn longest_run(chars: &[char], weaknesses: &mut Vec<String>) -> usize {
+    let mut best = 1usize;
+    let mut run = 1usize;
+    for i in 1..chars.len() {
+        if is_adjacent(chars[i - 1], chars[i]) {
+            run += 1;
+            best = best.max(run);
+        } else {
+            run = 1;
+        }
+    }
+    if best >= 3 {
+        weaknesses.push(format!("Contains a sequential or keyboard run of length {}", best));
+    }
+    best
+}
+
+/// Czy dwa znaki sąsiadują (alfabetycznie/numerycznie ±1 lub na klawiaturze).
+fn is_adjacent(a: char, b: char) -> bool {
+    let (a, b) = (a.to_ascii_lowercase(), b.to_ascii_lowercase());
+    if a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric() {
+        let diff = (a as i32 - b as i32).abs();
+        if diff == 1 {
+            return true;
+        }
+    }
+    KEYBOARD_ROWS.iter().any(|row| {
+        row.as_bytes().windows(2).any(|w| {
+            (w[0] as char == a && w[1] as char == b) || (w[0] as char == b && w[1] as char == a)
+        })
+    })
+}
+
+/// Kara za powtórzone znaki i powtórzone bigramy.
+fn repeat_penalty(chars: &[char], weaknesses: &mut Vec<String>) -> f64 {
+    let mut penalty = 0.0;
+
+    // Powtórzone pojedyncze znaki (aaa...).
+    let mut run = 1usize;
+    let mut flagged = false;
+    for i in 1..chars.len() {
+        if chars[i] == chars[i - 1] {
+            run += 1;
+            if run >= 3 {
+                penalty += 1.0;
+                if !flagged {
+                    weaknesses.push("Contains repeated characters".to_string());
+                    flagged = true;
+                }
+            }
+        } else {
+            run = 1;
+        }
+    }
+
+    // Powtórzone bigramy (abab, xyxy).
+    if chars.len() >= 4 {
+        let mut bigram_flagged = false;
+        for i in 0..chars.len().saturating_sub(3) {
+            if chars[i] == chars[i + 2] && chars[i + 1] == chars[i + 3] {
+                penalty += 1.0;
+                if !bigram_flagged {
+                    weaknesses.push("Contains repeated n-grams".to_string());
+                    bigram_flagged = true;
+                }
+            }
+        }
+    }
+
+    penalty
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,8 +507,19 @@ mod tests {
         // Brak cyfr
         assert!(policy.validate_password("abcdefgh").is_err());
 
-        // OK
-        assert!(policy.validate_password("abcdefgh1").is_ok());
+        // Zgodne z twardymi bramkami, ale słabe (ciąg sekwencyjny) - odrzucone
+        assert!(policy.validate_password("abcdefgh1").is_err());
+
+        // Silne hasło - zwraca wynik
+        let score = policy.validate_password("Tr0ub4!Xq7zK").unwrap();
+        assert!(score >= policy.min_strength_score());
+    }
+
+    #[test]
+    fn test_common_password_scored_weak() {
+        let (score, weaknesses) = estimate_password_strength("password");
+        assert!(score < 40);
+        assert!(!weaknesses.is_empty());
     }
 
     #[test]
@@ -0,0 +1,107 @@
+//! Czytelny DSL harmonogramów dla polityk dostępu
+//!
+//! Zamiast surowych sekund operatorzy mogą zapisywać czasy blokady jako
+//! `"30s"`, `"5m"`, `"2h"` czy złożone `"1h30m"`, a okna dostępu jako tokeny
+//! `"hourly"`, `"twice-daily"`, `"business-hours"`. Błędy parsowania są
+//! zwracane przez [`AlfaKeyVaultError::InvalidSchedule`].
+
+use crate::error::{AlfaKeyVaultError, Result};
+
+/// Parsuje czas trwania na sekundy, sumując pary (liczba, jednostka).
+///
+/// Obsługiwane jednostki: `s`, `m`, `h`, `d`. Łańcuchy złożone jak `"1h30m"`
+/// są sumowane; nieznane jednostki lub wiszące liczby są odrzucane.
+pub fn parse_duration(spec: &str) -> Result<u64> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return Err(AlfaKeyVaultError::InvalidSchedule("empty duration".into()));
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+
+    for c in spec.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        if number.is_empty() {
+            return Err(AlfaKeyVaultError::InvalidSchedule(format!(
+                "unit '{}' without a preceding number",
+                c
+            )));
+        }
+
+        let value: u64 = number
+            .parse()
+            .map_err(|_| AlfaKeyVaultError::InvalidSchedule(format!("invalid number '{}'", number)))?;
+        let multiplier = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            other => {
+                return Err(AlfaKeyVaultError::InvalidSchedule(format!(
+                    "unknown unit '{}'",
+                    other
+                )))
+            }
+        };
+        total = total.saturating_add(value.saturating_mul(multiplier));
+        number.clear();
+    }
+
+    if !number.is_empty() {
+        return Err(AlfaKeyVaultError::InvalidSchedule(
+            "trailing number without a unit".into(),
+        ));
+    }
+
+    Ok(total)
+}
+
+/// Parsuje token harmonogramu na listę dozwolonych godzin (0-23).
+pub fn parse_access_schedule(spec: &str) -> Result<Vec<u8>> {
+    match spec.trim().to_lowercase().as_str() {
+        "hourly" | "always" => Ok((0..24).collect()),
+        "business-hours" => Ok((9..=17).collect()),
+        "twice-daily" => Ok(vec![8, 9, 18, 19]),
+        other => Err(AlfaKeyVaultError::InvalidSchedule(format!(
+            "unknown schedule '{}'",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_durations() {
+        assert_eq!(parse_duration("30s").unwrap(), 30);
+        assert_eq!(parse_duration("5m").unwrap(), 300);
+        assert_eq!(parse_duration("2h").unwrap(), 7200);
+    }
+
+    #[test]
+    fn test_parse_compound_duration() {
+        assert_eq!(parse_duration("1h30m").unwrap(), 5400);
+    }
+
+    #[test]
+    fn test_reject_unknown_unit() {
+        assert!(matches!(
+            parse_duration("10x"),
+            Err(AlfaKeyVaultError::InvalidSchedule(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_schedule_tokens() {
+        assert_eq!(parse_access_schedule("business-hours").unwrap(), (9..=17).collect::<Vec<_>>());
+        assert_eq!(parse_access_schedule("hourly").unwrap().len(), 24);
+        assert!(parse_access_schedule("never").is_err());
+    }
+}
@@ -1,7 +1,14 @@
 //! PQX Snapshots - podpisane migawki stanu vault
 
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use chrono::{DateTime, Utc};
+use parking_lot::{Condvar, Mutex, RwLock};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -9,6 +16,90 @@ use crate::error::{AlfaKeyVaultError, Result};
 use crate::crypto::{derive_subkey_fixed, SecretKey};
 use secrecy::{SecretBox, ExposeSecret};
 
+/// Pollowalny uchwyt postępu długiej operacji (weryfikacja/budowa łańcucha).
+///
+/// Współdzielony przez `Arc<Progress>`: wątek roboczy woła [`Progress::tick`]
+/// w pętli, a wątek UI/monitoringu odpytuje liczniki i [`Progress::rate_bytes_per_sec`]
+/// bez blokowania. Liczniki są atomowe, a okno pomiaru przepływności chronione
+/// lekkim mutexem.
+#[derive(Debug)]
+pub struct Progress {
+    items_done: AtomicU64,
+    items_total: AtomicU64,
+    bytes_done: AtomicU64,
+    rate_window: Mutex<RateWindow>,
+}
+
+#[derive(Debug)]
+struct RateWindow {
+    last_update: Instant,
+    last_bytes: u64,
+}
+
+impl Progress {
+    /// Tworzy uchwyt dla znanej liczby elementów.
+    pub fn new(items_total: u64) -> Self {
+        Self {
+            items_done: AtomicU64::new(0),
+            items_total: AtomicU64::new(items_total),
+            bytes_done: AtomicU64::new(0),
+            rate_window: Mutex::new(RateWindow {
+                last_update: Instant::now(),
+                last_bytes: 0,
+            }),
+        }
+    }
+
+    /// Aktualizuje łączną liczbę elementów (gdy znana dopiero w trakcie).
+    pub fn set_total(&self, items_total: u64) {
+        self.items_total.store(items_total, Ordering::Relaxed);
+    }
+
+    /// Odnotowuje przetworzenie jednego elementu o rozmiarze `bytes`.
+    ///
+    /// `items_done` nigdy nie przekracza `items_total` — nawet gdy pliki są
+    /// dodawane/usuwane równolegle — dzięki obcięciu przy aktualizacji.
+    pub fn tick(&self, _epoch: u64, bytes: u64) {
+        let total = self.items_total.load(Ordering::Relaxed);
+        let done = self.items_done.load(Ordering::Relaxed);
+        if done < total {
+            self.items_done.store(done + 1, Ordering::Relaxed);
+        }
+        self.bytes_done.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn items_done(&self) -> u64 {
+        self.items_done.load(Ordering::Relaxed)
+    }
+
+    pub fn items_total(&self) -> u64 {
+        self.items_total.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_done(&self) -> u64 {
+        self.bytes_done.load(Ordering::Relaxed)
+    }
+
+    /// Przepływność od ostatniego odczytu (nie średnia z całego życia).
+    ///
+    /// Resetuje okno (`last_update`, `last_bytes`) przy każdym odczycie, więc
+    /// wynik odzwierciedla bieżący przepływ.
+    pub fn rate_bytes_per_sec(&self) -> f64 {
+        let mut window = self.rate_window.lock();
+        let now = Instant::now();
+        let bytes = self.bytes_done.load(Ordering::Relaxed);
+        let elapsed = now.duration_since(window.last_update).as_secs_f64();
+        let delta = bytes.saturating_sub(window.last_bytes);
+        window.last_update = now;
+        window.last_bytes = bytes;
+        if elapsed > 0.0 {
+            delta as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+}
+
 /// Snapshot vault z podpisem
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PqxSnapshot {
@@ -35,6 +126,77 @@ pub struct PqxSnapshot {
 
     /// Metadane
     pub metadata: HashMap<String, String>,
+
+    /// Rodzaj snapshotu: pełny lub przyrostowy.
+    #[serde(default)]
+    pub kind: SnapshotKind,
+
+    /// Klucze usunięte względem poprzedniego stanu (tylko dla delt).
+    #[serde(default)]
+    pub removed_keys: Vec<String>,
+
+    /// Schemat podpisu sterujący autentykacją snapshotu.
+    #[serde(default)]
+    pub scheme: SignatureScheme,
+
+    /// Klucz publiczny ML-DSA (hex) — pozwala audytorowi zweryfikować łańcuch
+    /// bez dostępu do seeda. Puste dla podpisów HMAC.
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+/// Schemat uwierzytelniania snapshotu.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SignatureScheme {
+    /// Symetryczny HMAC-SHA256 kluczowany seedem vault (integralność).
+    #[default]
+    HmacSha256,
+    /// ML-DSA-65 (Dilithium) — podpis weryfikowalny przez stronę trzecią.
+    MlDsa65,
+}
+
+/// Rodzaj snapshotu sterujący rekonstrukcją stanu.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SnapshotKind {
+    /// Pełny snapshot — `key_usages` zawiera kompletny stan.
+    #[default]
+    Full,
+    /// Snapshot przyrostowy — `key_usages` zawiera tylko zmienione wpisy;
+    /// `base_epoch` wskazuje najbliższy wcześniejszy snapshot pełny.
+    Delta { base_epoch: u64 },
+}
+
+/// Kodek kompresji serializowanego snapshotu.
+///
+/// Kompresja dotyczy wyłącznie bajtów na dysku — podpis i hash liczone są nad
+/// polami snapshotu (stan kanoniczny), więc pozostają niezależne od kodeka:
+/// snapshot zapisany z [`Compression::Snappy`] weryfikuje się poprawnie po
+/// ponownym zapisie z [`Compression::Zstd`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// Czysty JSON, bez nagłówka.
+    #[default]
+    None,
+    /// Snappy (`.json.sz`).
+    Snappy,
+    /// Zstandard (`.json.zst`).
+    Zstd,
+}
+
+/// Magiczny nagłówek pliku Snappy.
+const MAGIC_SNAPPY: &[u8; 4] = b"ALSZ";
+/// Magiczny nagłówek pliku Zstd.
+const MAGIC_ZSTD: &[u8; 4] = b"ALZS";
+
+impl Compression {
+    /// Rozszerzenie pliku dla danego kodeka.
+    fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "json",
+            Compression::Snappy => "json.sz",
+            Compression::Zstd => "json.zst",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,11 +224,23 @@ impl PqxSnapshot {
             prev_hash,
             signature: String::new(),
             metadata: HashMap::new(),
+            kind: SnapshotKind::Full,
+            removed_keys: Vec::new(),
+            scheme: SignatureScheme::HmacSha256,
+            public_key: None,
         }
     }
 
-    /// Oblicza hash snapshotu (bez podpisu)
+    /// Oblicza hash snapshotu (bez podpisu) nad własnym `key_usages`.
+    ///
+    /// Dla snapshotu pełnego jest to kompletny stan; dla delty należy użyć
+    /// [`PqxSnapshot::compute_hash_over`] ze zmaterializowanym pełnym stanem.
     pub fn compute_hash(&self) -> String {
+        self.compute_hash_over(&self.key_usages)
+    }
+
+    /// Oblicza hash nad dostarczonym, zmaterializowanym stanem `key_usages`.
+    pub fn compute_hash_over(&self, key_usages: &HashMap<String, u64>) -> String {
         use sha2::{Sha256, Digest};
 
         let mut hasher = Sha256::new();
@@ -77,7 +251,10 @@ impl PqxSnapshot {
         hasher.update(self.kdf_params.time_cost.to_le_bytes());
         hasher.update(self.kdf_params.memory_cost_kib.to_le_bytes());
 
-        for (key, count) in &self.key_usages {
+        // Stała kolejność kluczy: kolejność iteracji HashMap zmienia się po deserializacji.
+        let mut entries: Vec<_> = key_usages.iter().collect();
+        entries.sort();
+        for (key, count) in entries {
             hasher.update(key.as_bytes());
             hasher.update(count.to_le_bytes());
         }
@@ -89,56 +266,215 @@ impl PqxSnapshot {
         hex::encode(hasher.finalize())
     }
 
-    /// Podpisuje snapshot używając klucza derywowanego z seed
+    /// Podpisuje snapshot używając klucza derywowanego z seed (pełny stan).
     pub fn sign(&mut self, seed: &SecretBox<[u8; 32]>) {
-        use hmac::{Hmac, Mac};
-        use sha2::Sha256;
+        let hash = self.compute_hash();
+        self.sign_hash(seed, &hash);
+    }
 
-        // Derywuj klucz do podpisywania
-        let sign_key: SecretBox<[u8; 32]> = derive_subkey_fixed(seed, "ALFA:snapshot:sign");
+    /// Podpisuje snapshot nad zmaterializowanym stanem (dla delt).
+    pub fn sign_over(&mut self, seed: &SecretBox<[u8; 32]>, key_usages: &HashMap<String, u64>) {
+        let hash = self.compute_hash_over(key_usages);
+        self.sign_hash(seed, &hash);
+    }
 
-        // HMAC-SHA256
-        let mut mac = Hmac::<Sha256>::new_from_slice(sign_key.expose_secret())
-            .expect("HMAC key size invalid");
+    fn sign_hash(&mut self, seed: &SecretBox<[u8; 32]>, hash: &str) {
+        match self.scheme {
+            SignatureScheme::HmacSha256 => {
+                use hmac::{Hmac, Mac};
+                use sha2::Sha256;
+
+                let sign_key: SecretBox<[u8; 32]> =
+                    derive_subkey_fixed(seed, "ALFA:snapshot:sign");
+                let mut mac = Hmac::<Sha256>::new_from_slice(sign_key.expose_secret())
+                    .expect("HMAC key size invalid");
+                mac.update(hash.as_bytes());
+                self.signature = hex::encode(mac.finalize().into_bytes());
+                self.public_key = None;
+            }
+            SignatureScheme::MlDsa65 => {
+                let (sig, public_key) = mldsa_sign(seed, hash.as_bytes());
+                self.signature = hex::encode(sig);
+                self.public_key = Some(hex::encode(public_key));
+            }
+        }
+    }
 
+    /// Weryfikuje podpis snapshotu (pełny stan).
+    pub fn verify(&self, seed: &SecretBox<[u8; 32]>) -> bool {
         let hash = self.compute_hash();
-        mac.update(hash.as_bytes());
+        self.verify_hash(seed, &hash)
+    }
 
-        self.signature = hex::encode(mac.finalize().into_bytes());
+    /// Weryfikuje podpis nad zmaterializowanym stanem (dla delt).
+    pub fn verify_over(&self, seed: &SecretBox<[u8; 32]>, key_usages: &HashMap<String, u64>) -> bool {
+        let hash = self.compute_hash_over(key_usages);
+        self.verify_hash(seed, &hash)
     }
 
-    /// Weryfikuje podpis snapshotu
-    pub fn verify(&self, seed: &SecretBox<[u8; 32]>) -> bool {
-        use hmac::{Hmac, Mac};
-        use sha2::Sha256;
+    fn verify_hash(&self, seed: &SecretBox<[u8; 32]>, hash: &str) -> bool {
+        match self.scheme {
+            SignatureScheme::HmacSha256 => {
+                use hmac::{Hmac, Mac};
+                use sha2::Sha256;
 
-        let sign_key: SecretBox<[u8; 32]> = derive_subkey_fixed(seed, "ALFA:snapshot:sign");
+                let sign_key: SecretBox<[u8; 32]> =
+                    derive_subkey_fixed(seed, "ALFA:snapshot:sign");
+                let mut mac = Hmac::<Sha256>::new_from_slice(sign_key.expose_secret())
+                    .expect("HMAC key size invalid");
+                mac.update(hash.as_bytes());
 
-        let mut mac = Hmac::<Sha256>::new_from_slice(sign_key.expose_secret())
-            .expect("HMAC key size invalid");
+                let expected = hex::decode(&self.signature).unwrap_or_default();
+                mac.verify_slice(&expected).is_ok()
+            }
+            // Podpis ML-DSA weryfikowany jest wyłącznie osadzonym kluczem
+            // publicznym — seed nie jest potrzebny, więc audytor może sprawdzić
+            // łańcuch bez niego (zob. [`PqxSnapshot::verify_auditable`]).
+            SignatureScheme::MlDsa65 => self.verify_auditable_over_hash(hash),
+        }
+    }
 
+    /// Weryfikuje podpis ML-DSA osadzonym kluczem publicznym, bez seeda.
+    ///
+    /// Zwraca `false` dla snapshotów HMAC (brak klucza publicznego) oraz gdy
+    /// klucz/podpis są nieobecne lub źle sformułowane.
+    pub fn verify_auditable(&self) -> bool {
+        if self.scheme != SignatureScheme::MlDsa65 {
+            return false;
+        }
         let hash = self.compute_hash();
-        mac.update(hash.as_bytes());
+        self.verify_auditable_over_hash(&hash)
+    }
 
-        let expected = hex::decode(&self.signature).unwrap_or_default();
-        mac.verify_slice(&expected).is_ok()
+    fn verify_auditable_over_hash(&self, hash: &str) -> bool {
+        let (Some(pk_hex), Ok(sig)) = (self.public_key.as_ref(), hex::decode(&self.signature))
+        else {
+            return false;
+        };
+        match hex::decode(pk_hex) {
+            Ok(pk) => mldsa_verify(&pk, hash.as_bytes(), &sig),
+            Err(_) => false,
+        }
     }
 
-    /// Zapisuje snapshot do pliku
-    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        let json = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, json)?;
+    /// Zapisuje snapshot do pliku, opcjonalnie kompresując bajty.
+    ///
+    /// Plik skompresowany poprzedzony jest 4-bajtowym nagłówkiem magicznym, po
+    /// którym [`PqxSnapshot::load`] rozpoznaje kodek przy odczycie. Zapis jest
+    /// atomowy (`.tmp` + fsync + rename + fsync katalogu nadrzędnego), tak jak
+    /// pozostałe trwałe stany w tym repo (zob. `pin.rs::persist`,
+    /// `journal.rs::write_snapshot`) — awaria w trakcie zapisu nie może
+    /// zostawić obciętego/uszkodzonego pliku snapshotu w łańcuchu.
+    pub fn save<P: AsRef<Path>>(&self, path: P, compression: Compression) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        let bytes = match compression {
+            Compression::None => json,
+            Compression::Snappy => {
+                let mut out = MAGIC_SNAPPY.to_vec();
+                out.extend_from_slice(
+                    &snap::raw::Encoder::new()
+                        .compress_vec(&json)
+                        .map_err(|e| AlfaKeyVaultError::SnapshotError(e.to_string()))?,
+                );
+                out
+            }
+            Compression::Zstd => {
+                let mut out = MAGIC_ZSTD.to_vec();
+                out.extend_from_slice(
+                    &zstd::encode_all(&json[..], 3)
+                        .map_err(|e| AlfaKeyVaultError::SnapshotError(e.to_string()))?,
+                );
+                out
+            }
+        };
+
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let mut f = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&tmp)?;
+        f.write_all(&bytes)?;
+        f.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        if let Some(parent) = path.parent() {
+            if let Ok(dir) = std::fs::File::open(parent) {
+                let _ = dir.sync_all();
+            }
+        }
         Ok(())
     }
 
-    /// Wczytuje snapshot z pliku
+    /// Wczytuje snapshot z pliku, rozpoznając kodek po nagłówku magicznym.
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let json = std::fs::read_to_string(path)?;
-        let snapshot: PqxSnapshot = serde_json::from_str(&json)?;
+        let raw = std::fs::read(path)?;
+        let json = if raw.len() >= 4 && &raw[..4] == MAGIC_SNAPPY {
+            snap::raw::Decoder::new()
+                .decompress_vec(&raw[4..])
+                .map_err(|e| AlfaKeyVaultError::SnapshotError(e.to_string()))?
+        } else if raw.len() >= 4 && &raw[..4] == MAGIC_ZSTD {
+            zstd::decode_all(&raw[4..])
+                .map_err(|e| AlfaKeyVaultError::SnapshotError(e.to_string()))?
+        } else {
+            raw
+        };
+        let snapshot: PqxSnapshot = serde_json::from_slice(&json)?;
         Ok(snapshot)
     }
 }
 
+/// Podpisuje `msg` kluczem ML-DSA-65 derywowanym deterministycznie z seeda.
+///
+/// Zwraca `(podpis, klucz_publiczny)` w postaci bajtów. Para kluczy jest
+/// generowana z podklucza `ALFA:snapshot:mldsa`, więc ten sam seed zawsze daje
+/// ten sam klucz publiczny — audytor może przypiąć go raz i weryfikować cały
+/// łańcuch.
+fn mldsa_keypair(seed: &SecretBox<[u8; 32]>) -> ml_dsa::KeyPair<ml_dsa::MlDsa65> {
+    use ml_dsa::{B32, KeyGen, MlDsa65};
+
+    let sk: SecretBox<[u8; 32]> = derive_subkey_fixed(seed, "ALFA:snapshot:mldsa");
+    let xi = B32::from(*sk.expose_secret());
+    MlDsa65::key_gen_internal(&xi)
+}
+
+fn mldsa_sign(seed: &SecretBox<[u8; 32]>, msg: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    use ml_dsa::signature::{Keypair, Signer};
+
+    let kp = mldsa_keypair(seed);
+    let sig = kp.signing_key().sign(msg);
+    (
+        sig.encode().to_vec(),
+        kp.verifying_key().encode().to_vec(),
+    )
+}
+
+fn mldsa_verify(public_key: &[u8], msg: &[u8], signature: &[u8]) -> bool {
+    use ml_dsa::signature::Verifier;
+    use ml_dsa::{EncodedSignature, EncodedVerifyingKey, MlDsa65, Signature, VerifyingKey};
+
+    let Ok(vk_enc) = EncodedVerifyingKey::<MlDsa65>::try_from(public_key) else {
+        return false;
+    };
+    let vk = VerifyingKey::<MlDsa65>::decode(&vk_enc);
+
+    let Ok(sig_enc) = EncodedSignature::<MlDsa65>::try_from(signature) else {
+        return false;
+    };
+    match Signature::<MlDsa65>::decode(&sig_enc) {
+        Some(sig) => vk.verify(msg, &sig).is_ok(),
+        None => false,
+    }
+}
+
+/// Czy plik jest snapshotem (dowolny obsługiwany kodek).
+fn is_snapshot_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.ends_with(".json") || n.ends_with(".json.sz") || n.ends_with(".json.zst"))
+        .unwrap_or(false)
+}
+
 /// Manager snapshotów
 pub struct SnapshotManager {
     /// Katalog ze snapshotami
@@ -152,6 +488,21 @@ pub struct SnapshotManager {
 
     /// Hash ostatniego snapshotu
     last_hash: Option<String>,
+
+    /// Co ile epok wymuszać snapshot pełny (0/1 = zawsze pełny).
+    full_interval: u64,
+
+    /// Epoch ostatniego snapshotu pełnego (baza dla delt).
+    last_base_epoch: u64,
+
+    /// Zmaterializowany stan ostatniego snapshotu (baza do liczenia delt).
+    prev_state: HashMap<String, u64>,
+
+    /// Kodek kompresji dla nowo zapisywanych snapshotów.
+    compression: Compression,
+
+    /// Schemat podpisu dla nowo zapisywanych snapshotów.
+    scheme: SignatureScheme,
 }
 
 impl SnapshotManager {
@@ -164,9 +515,79 @@ impl SnapshotManager {
             max_snapshots,
             current_epoch: 0,
             last_hash: None,
+            full_interval: 0,
+            last_base_epoch: 0,
+            prev_state: HashMap::new(),
+            compression: Compression::None,
+            scheme: SignatureScheme::HmacSha256,
         }
     }
 
+    /// Jak [`Self::new`], ale skanuje `snapshots_dir` i odtwarza
+    /// `current_epoch`/`last_hash`/`last_base_epoch`/`prev_state` z
+    /// najnowszego zapisanego snapshotu zamiast zaczynać łańcuch od zera.
+    ///
+    /// Bez tego każdy restart długo działającego procesu (np. harmonogramu z
+    /// [`ScheduledSnapshotter`]) wracałby do epoch 1, kolidując z istniejącymi
+    /// plikami `snapshot_000001_*` i gubiąc `prev_state`/`last_hash` — przez
+    /// co `verify_chain` oraz składanie delt w [`Self::materialize`] przestają
+    /// być wiarygodne. Analogiczny wzorzec do `RotationManager::load_or_create`
+    /// (rotation.rs) i `BrainJournal::open` (journal.rs).
+    pub fn load_or_create<P: AsRef<Path>>(snapshots_dir: P, max_snapshots: usize) -> Result<Self> {
+        let mut manager = Self::new(snapshots_dir, max_snapshots);
+
+        let snapshots = manager.list_snapshots()?;
+        let Some(latest) = snapshots.first() else {
+            return Ok(manager);
+        };
+
+        let snapshot = PqxSnapshot::load(&latest.path)?;
+        let materialized = manager.materialize(snapshot.epoch)?.ok_or_else(|| {
+            AlfaKeyVaultError::SnapshotError(format!(
+                "nie udało się zmaterializować najnowszego epoch {} przy wznowieniu",
+                snapshot.epoch
+            ))
+        })?;
+        let base_epoch = match snapshot.kind {
+            SnapshotKind::Full => snapshot.epoch,
+            SnapshotKind::Delta { base_epoch } => base_epoch,
+        };
+
+        manager.current_epoch = snapshot.epoch;
+        manager.last_hash = Some(snapshot.compute_hash_over(&materialized));
+        manager.last_base_epoch = base_epoch;
+        manager.prev_state = materialized;
+
+        Ok(manager)
+    }
+
+    /// Ustawia schemat podpisu dla nowo zapisywanych snapshotów.
+    ///
+    /// Domyślnie [`SignatureScheme::HmacSha256`]. Ustawienie
+    /// [`SignatureScheme::MlDsa65`] powoduje dołączanie do snapshotu klucza
+    /// publicznego Dilithium, dzięki czemu łańcuch weryfikuje się bez seeda.
+    pub fn set_signature_scheme(&mut self, scheme: SignatureScheme) {
+        self.scheme = scheme;
+    }
+
+    /// Ustawia kodek kompresji dla nowo zapisywanych snapshotów.
+    ///
+    /// Nie wpływa na odczyt — [`PqxSnapshot::load`] rozpoznaje kodek każdego
+    /// pliku po nagłówku niezależnie od tego ustawienia.
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    /// Ustawia odstęp (w epokach) między snapshotami pełnymi.
+    ///
+    /// Wartość `0` lub `1` oznacza, że każdy snapshot jest pełny. Większa
+    /// wartość `n` powoduje tworzenie snapshotu pełnego co `n` epok, a pozostałe
+    /// zapisywane są jako delty względem najbliższego wcześniejszego snapshotu
+    /// pełnego.
+    pub fn set_full_interval(&mut self, full_interval: u64) {
+        self.full_interval = full_interval;
+    }
+
     /// Tworzy nowy snapshot
     pub fn create_snapshot(
         &mut self,
@@ -176,24 +597,67 @@ impl SnapshotManager {
     ) -> Result<PqxSnapshot> {
         self.current_epoch += 1;
 
-        let mut snapshot = PqxSnapshot::new(
-            self.current_epoch,
-            kdf_params,
-            key_usages,
-            self.last_hash.clone(),
-        );
+        let is_full = self.full_interval <= 1
+            || self.last_base_epoch == 0
+            || (self.current_epoch - self.last_base_epoch) >= self.full_interval;
+
+        let mut snapshot = if is_full {
+            PqxSnapshot::new(
+                self.current_epoch,
+                kdf_params,
+                key_usages.clone(),
+                self.last_hash.clone(),
+            )
+        } else {
+            // Delta: zapisz tylko wpisy zmienione i usunięte względem
+            // poprzedniego zmaterializowanego stanu.
+            let mut changed = HashMap::new();
+            for (key, count) in &key_usages {
+                if self.prev_state.get(key) != Some(count) {
+                    changed.insert(key.clone(), *count);
+                }
+            }
+            let removed: Vec<String> = self
+                .prev_state
+                .keys()
+                .filter(|k| !key_usages.contains_key(*k))
+                .cloned()
+                .collect();
+
+            let mut snap = PqxSnapshot::new(
+                self.current_epoch,
+                kdf_params,
+                changed,
+                self.last_hash.clone(),
+            );
+            snap.kind = SnapshotKind::Delta {
+                base_epoch: self.last_base_epoch,
+            };
+            snap.removed_keys = removed;
+            snap
+        };
+
+        // Podpis i hash łańcucha liczone zawsze nad pełnym (zmaterializowanym)
+        // stanem, aby `prev_hash` i weryfikacja pozostały spójne niezależnie od
+        // rodzaju snapshotu.
+        snapshot.scheme = self.scheme.clone();
+        snapshot.sign_over(seed, &key_usages);
+        self.last_hash = Some(snapshot.compute_hash_over(&key_usages));
 
-        snapshot.sign(seed);
-        self.last_hash = Some(snapshot.compute_hash());
+        if is_full {
+            self.last_base_epoch = self.current_epoch;
+        }
+        self.prev_state = key_usages;
 
         // Zapisz snapshot
         let filename = format!(
-            "snapshot_{:06}_{}.json",
+            "snapshot_{:06}_{}.{}",
             self.current_epoch,
-            Utc::now().format("%Y%m%d_%H%M%S")
+            Utc::now().format("%Y%m%d_%H%M%S"),
+            self.compression.extension(),
         );
         let path = self.snapshots_dir.join(&filename);
-        snapshot.save(&path)?;
+        snapshot.save(&path, self.compression)?;
 
         // Usuń stare snapshoty
         self.cleanup_old_snapshots()?;
@@ -201,16 +665,52 @@ impl SnapshotManager {
         Ok(snapshot)
     }
 
+    /// Odtwarza pełny stan `key_usages` dla danego epoch, składając delty.
+    ///
+    /// Snapshot pełny zwracany jest bezpośrednio; dla delty odtwarzany jest stan
+    /// bazowy, na który nakładane są kolejne delty aż do `epoch` włącznie.
+    pub fn materialize(&self, epoch: u64) -> Result<Option<HashMap<String, u64>>> {
+        let snapshot = match self.load_by_epoch(epoch)? {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+
+        match snapshot.kind {
+            SnapshotKind::Full => Ok(Some(snapshot.key_usages)),
+            SnapshotKind::Delta { base_epoch } => {
+                let mut state = match self.materialize(base_epoch)? {
+                    Some(s) => s,
+                    None => {
+                        return Err(AlfaKeyVaultError::SnapshotError(format!(
+                            "brak snapshotu bazowego {} dla delty {}",
+                            base_epoch, epoch
+                        )))
+                    }
+                };
+                for e in (base_epoch + 1)..=epoch {
+                    let delta = self.load_by_epoch(e)?.ok_or_else(|| {
+                        AlfaKeyVaultError::SnapshotError(format!(
+                            "brak snapshotu delty {} w łańcuchu do {} (usunięty przez cleanup?)",
+                            e, epoch
+                        ))
+                    })?;
+                    for (key, count) in &delta.key_usages {
+                        state.insert(key.clone(), *count);
+                    }
+                    for key in &delta.removed_keys {
+                        state.remove(key);
+                    }
+                }
+                Ok(Some(state))
+            }
+        }
+    }
+
     /// Wczytuje najnowszy snapshot
     pub fn load_latest(&self) -> Result<Option<PqxSnapshot>> {
         let mut entries: Vec<_> = std::fs::read_dir(&self.snapshots_dir)?
             .filter_map(|e| e.ok())
-            .filter(|e| {
-                e.path()
-                    .extension()
-                    .map(|ext| ext == "json")
-                    .unwrap_or(false)
-            })
+            .filter(|e| is_snapshot_file(&e.path()))
             .collect();
 
         entries.sort_by(|a, b| b.path().cmp(&a.path()));
@@ -223,7 +723,12 @@ impl SnapshotManager {
         Ok(None)
     }
 
-    /// Wczytuje snapshot o danym epoch
+    /// Wczytuje snapshot o danym epoch.
+    ///
+    /// Plik uszkodzony (np. obcięty przez awarię sprzed atomowego `save`)
+    /// zgłasza [`AlfaKeyVaultError::SnapshotError`] nazwiący epoch i ścieżkę,
+    /// zamiast nieczytelnego błędu parsowania JSON — dzięki temu wołający
+    /// (np. [`Self::materialize`]) wie dokładnie, które ogniwo łańcucha padło.
     pub fn load_by_epoch(&self, epoch: u64) -> Result<Option<PqxSnapshot>> {
         let pattern = format!("snapshot_{:06}_", epoch);
 
@@ -231,8 +736,16 @@ impl SnapshotManager {
             let entry = entry?;
             let filename = entry.file_name().to_string_lossy().to_string();
 
-            if filename.starts_with(&pattern) && filename.ends_with(".json") {
-                let snapshot = PqxSnapshot::load(entry.path())?;
+            if filename.starts_with(&pattern) && is_snapshot_file(&entry.path()) {
+                let path = entry.path();
+                let snapshot = PqxSnapshot::load(&path).map_err(|e| {
+                    AlfaKeyVaultError::SnapshotError(format!(
+                        "uszkodzony plik snapshotu dla epoch {} ({}): {}",
+                        epoch,
+                        path.display(),
+                        e
+                    ))
+                })?;
                 return Ok(Some(snapshot));
             }
         }
@@ -246,7 +759,7 @@ impl SnapshotManager {
 
         for entry in std::fs::read_dir(&self.snapshots_dir)? {
             let entry = entry?;
-            if entry.path().extension().map(|e| e == "json").unwrap_or(false) {
+            if is_snapshot_file(&entry.path()) {
                 if let Ok(snapshot) = PqxSnapshot::load(entry.path()) {
                     snapshots.push(SnapshotInfo {
                         epoch: snapshot.epoch,
@@ -262,8 +775,12 @@ impl SnapshotManager {
         Ok(snapshots)
     }
 
-    /// Weryfikuje łańcuch snapshotów
-    pub fn verify_chain(&self, seed: &SecretBox<[u8; 32]>) -> Result<ChainVerification> {
+    /// Weryfikuje łańcuch snapshotów, raportując postęp przez `progress`.
+    pub fn verify_chain(
+        &self,
+        seed: &SecretBox<[u8; 32]>,
+        progress: &Progress,
+    ) -> Result<ChainVerification> {
         let snapshots = self.list_snapshots()?;
         let mut result = ChainVerification {
             total: snapshots.len(),
@@ -272,13 +789,20 @@ impl SnapshotManager {
             chain_intact: true,
         };
 
+        progress.set_total(snapshots.len() as u64);
         let mut expected_prev_hash: Option<String> = None;
 
         for info in snapshots.iter().rev() {
+            let file_len = std::fs::metadata(&info.path).map(|m| m.len()).unwrap_or(0);
             let snapshot = PqxSnapshot::load(&info.path)?;
+            progress.tick(info.epoch, file_len);
+
+            // Podpis i hash łańcucha liczone są nad pełnym (zmaterializowanym)
+            // stanem, więc delty muszą zostać złożone z bazą zanim zweryfikujemy.
+            let materialized = self.materialize(info.epoch)?.unwrap_or_else(|| snapshot.key_usages.clone());
 
             // Weryfikuj podpis
-            if !snapshot.verify(seed) {
+            if !snapshot.verify_over(seed, &materialized) {
                 result.invalid.push(info.epoch);
                 result.chain_intact = false;
                 continue;
@@ -291,7 +815,7 @@ impl SnapshotManager {
                 }
             }
 
-            expected_prev_hash = Some(snapshot.compute_hash());
+            expected_prev_hash = Some(snapshot.compute_hash_over(&materialized));
             result.valid += 1;
         }
 
@@ -299,15 +823,16 @@ impl SnapshotManager {
     }
 
     /// Usuwa stare snapshoty
+    ///
+    /// Zachowuje `max_snapshots` najnowszych snapshotów, ale nigdy nie usuwa
+    /// żadnego snapshotu w łańcuchu, od którego zależy zachowywana delta —
+    /// czyli jej snapshotu bazowego (pełnego) ORAZ każdej pośredniej delty
+    /// pomiędzy bazą a zachowywanym snapshotem — inaczej nie dałoby się ich
+    /// zmaterializować (zob. [`SnapshotManager::materialize`]).
     fn cleanup_old_snapshots(&self) -> Result<()> {
         let mut entries: Vec<_> = std::fs::read_dir(&self.snapshots_dir)?
             .filter_map(|e| e.ok())
-            .filter(|e| {
-                e.path()
-                    .extension()
-                    .map(|ext| ext == "json")
-                    .unwrap_or(false)
-            })
+            .filter(|e| is_snapshot_file(&e.path()))
             .collect();
 
         if entries.len() <= self.max_snapshots {
@@ -317,9 +842,30 @@ impl SnapshotManager {
         // Sortuj od najstarszych
         entries.sort_by(|a, b| a.path().cmp(&b.path()));
 
-        // Usuń najstarsze
-        let to_remove = entries.len() - self.max_snapshots;
-        for entry in entries.into_iter().take(to_remove) {
+        // Epoki, od których zależą zachowywane delty — obejmuje bazę pełną
+        // oraz każdą pośrednią deltę aż do zachowywanego snapshotu; żadnej z
+        // nich nie wolno usunąć, nawet jeśli jest starsza niż próg.
+        let keep_from = entries.len() - self.max_snapshots;
+        let mut required_epochs = std::collections::HashSet::new();
+        for entry in &entries[keep_from..] {
+            if let Ok(snapshot) = PqxSnapshot::load(entry.path()) {
+                if let SnapshotKind::Delta { base_epoch } = snapshot.kind {
+                    required_epochs.insert(base_epoch);
+                    for e in (base_epoch + 1)..snapshot.epoch {
+                        required_epochs.insert(e);
+                    }
+                }
+            }
+        }
+
+        // Usuń najstarsze, pomijając wymagane epoki łańcucha.
+        for entry in entries.into_iter().take(keep_from) {
+            let is_required = PqxSnapshot::load(entry.path())
+                .map(|s| required_epochs.contains(&s.epoch))
+                .unwrap_or(false);
+            if is_required {
+                continue;
+            }
             std::fs::remove_file(entry.path())?;
         }
 
@@ -330,6 +876,22 @@ impl SnapshotManager {
     pub fn current_epoch(&self) -> u64 {
         self.current_epoch
     }
+
+    /// Uruchamia automatyczne snapshotowanie co `interval`.
+    ///
+    /// Scheduler pobiera spójną kopię `key_usages` przez `counters` (vault musi
+    /// trzymać [`SnapshotGuard`] podczas mutacji), więc snapshot nigdy nie obejmie
+    /// stanu w trakcie aktualizacji. Zwraca uchwyt zatrzymujący pętlę na `stop()`
+    /// lub przy porzuceniu.
+    pub fn request_snapshot_at(
+        self,
+        counters: UsageCounters,
+        seed: SecretBox<[u8; 32]>,
+        kdf_params: KdfParams,
+        interval: Duration,
+    ) -> SchedulerHandle {
+        SnapshotScheduler::new(self, counters, seed, kdf_params, interval).start()
+    }
 }
 
 /// Informacje o snapshocie
@@ -350,6 +912,217 @@ pub struct ChainVerification {
     pub chain_intact: bool,
 }
 
+/// Współdzielony zatrzask spójności liczników `key_usages`.
+///
+/// Vault trzyma [`SnapshotGuard`] przez cały czas modyfikacji liczników, a
+/// scheduler pobiera spójną kopię dopiero po zwolnieniu zatrzasku — dzięki temu
+/// nigdy nie powstaje podpisany snapshot „rozdartego” stanu.
+#[derive(Clone, Default)]
+pub struct UsageCounters {
+    inner: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl UsageCounters {
+    /// Tworzy zatrzask z początkowym stanem liczników.
+    pub fn new(initial: HashMap<String, u64>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(initial)),
+        }
+    }
+
+    /// Przejmuje zatrzask na czas modyfikacji liczników.
+    ///
+    /// Dopóki zwrócony [`SnapshotGuard`] żyje, scheduler czeka — nie może
+    /// zserializować stanu w trakcie aktualizacji.
+    pub fn lock(&self) -> SnapshotGuard<'_> {
+        SnapshotGuard {
+            guard: self.inner.lock(),
+        }
+    }
+
+    /// Pobiera spójną kopię liczników (czeka na zwolnienie zatrzasku).
+    fn consistent_copy(&self) -> HashMap<String, u64> {
+        self.inner.lock().clone()
+    }
+}
+
+/// Uchwyt RAII na liczniki `key_usages` trzymany podczas mutacji.
+pub struct SnapshotGuard<'a> {
+    guard: parking_lot::MutexGuard<'a, HashMap<String, u64>>,
+}
+
+impl Deref for SnapshotGuard<'_> {
+    type Target = HashMap<String, u64>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl DerefMut for SnapshotGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+/// Status schedulera udostępniany odpytującym.
+#[derive(Debug, Clone, Default)]
+pub struct SchedulerStatus {
+    /// Liczba udanych snapshotów.
+    pub runs: u64,
+    /// Czas ostatniego udanego snapshotu.
+    pub last_run: Option<DateTime<Utc>>,
+    /// Błąd z ostatniej nieudanej próby, czyszczony po sukcesie.
+    pub last_error: Option<String>,
+    /// Liczba pominiętych tyknięć (poprzedni snapshot wciąż trwał).
+    pub skipped: u64,
+}
+
+/// Scheduler tworzący snapshoty okresowo, skoordynowany z mutacjami vaulta.
+///
+/// Wzoruje się na [`crate`]-owym wzorcu wątku w tle: pętla budzi się co
+/// `interval`, pobiera spójną kopię liczników przez [`UsageCounters`] i woła
+/// [`SnapshotManager::create_snapshot`]. Gdy poprzedni snapshot jeszcze trwa,
+/// tyknięcie jest pomijane zamiast kolejkowane.
+pub struct SnapshotScheduler {
+    manager: Mutex<SnapshotManager>,
+    counters: UsageCounters,
+    seed: SecretBox<[u8; 32]>,
+    kdf_params: KdfParams,
+    interval: Duration,
+    status: Arc<RwLock<SchedulerStatus>>,
+    in_progress: AtomicBool,
+}
+
+impl SnapshotScheduler {
+    /// Tworzy scheduler dla danego managera, zatrzasku liczników i seeda.
+    pub fn new(
+        manager: SnapshotManager,
+        counters: UsageCounters,
+        seed: SecretBox<[u8; 32]>,
+        kdf_params: KdfParams,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            manager: Mutex::new(manager),
+            counters,
+            seed,
+            kdf_params,
+            interval,
+            status: Arc::new(RwLock::new(SchedulerStatus::default())),
+            in_progress: AtomicBool::new(false),
+        }
+    }
+
+    /// Bieżący status schedulera.
+    pub fn status(&self) -> SchedulerStatus {
+        self.status.read().clone()
+    }
+
+    /// Wykonuje pojedyncze tyknięcie: pomija, jeśli poprzedni snapshot trwa,
+    /// w przeciwnym razie pobiera spójny stan i tworzy snapshot.
+    ///
+    /// Udostępnione do testów i dla wywołań spoza własnej pętli.
+    pub fn run_once(&self) {
+        // Pomiń, gdy poprzedni snapshot wciąż trwa (bez kolejkowania).
+        if self.in_progress.swap(true, Ordering::AcqRel) {
+            self.status.write().skipped += 1;
+            return;
+        }
+
+        let state = self.counters.consistent_copy();
+        let now = Utc::now();
+        let result = self
+            .manager
+            .lock()
+            .create_snapshot(&self.seed, self.kdf_params.clone(), state);
+
+        let mut status = self.status.write();
+        match result {
+            Ok(_) => {
+                status.runs += 1;
+                status.last_run = Some(now);
+                status.last_error = None;
+            }
+            Err(e) => {
+                status.last_error = Some(e.to_string());
+            }
+        }
+        drop(status);
+
+        self.in_progress.store(false, Ordering::Release);
+    }
+
+    /// Uruchamia pętlę w tle, zwracając uchwyt zatrzymujący ją na `stop()`.
+    pub fn start(self) -> SchedulerHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let wake = Arc::new((Mutex::new(()), Condvar::new()));
+        let status = Arc::clone(&self.status);
+
+        let thread_stop = Arc::clone(&stop);
+        let thread_wake = Arc::clone(&wake);
+        let interval = self.interval;
+
+        let handle = thread::Builder::new()
+            .name("alfa-snapshot-scheduler".to_string())
+            .spawn(move || {
+                while !thread_stop.load(Ordering::Acquire) {
+                    self.run_once();
+
+                    // Czekaj jeden odstęp, budząc się wcześniej na żądanie stopu.
+                    let (lock, cvar) = &*thread_wake;
+                    let mut guard = lock.lock();
+                    if !thread_stop.load(Ordering::Acquire) {
+                        cvar.wait_for(&mut guard, interval);
+                    }
+                }
+            })
+            .expect("spawn snapshot scheduler thread");
+
+        SchedulerHandle {
+            stop,
+            wake,
+            handle: Some(handle),
+            status,
+        }
+    }
+}
+
+/// Uchwyt działającego [`SnapshotScheduler`]; zatrzymuje i dołącza wątek na
+/// `stop()` lub przy porzuceniu.
+pub struct SchedulerHandle {
+    stop: Arc<AtomicBool>,
+    wake: Arc<(Mutex<()>, Condvar)>,
+    handle: Option<JoinHandle<()>>,
+    status: Arc<RwLock<SchedulerStatus>>,
+}
+
+impl SchedulerHandle {
+    /// Bieżący status schedulera.
+    pub fn status(&self) -> SchedulerStatus {
+        self.status.read().clone()
+    }
+
+    /// Sygnalizuje pętli zatrzymanie i czysto dołącza wątek.
+    pub fn stop(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        self.wake.1.notify_all();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for SchedulerHandle {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -413,4 +1186,226 @@ mod tests {
         assert_eq!(s2.prev_hash, Some(h1));
         assert!(s2.verify(&seed));
     }
+
+    #[test]
+    fn test_usage_counters_guard_mutation() {
+        let counters = UsageCounters::new(HashMap::new());
+        {
+            let mut guard = counters.lock();
+            guard.insert("album:a".into(), 3);
+            guard.insert("album:b".into(), 1);
+        }
+        let state = counters.consistent_copy();
+        assert_eq!(state.get("album:a"), Some(&3));
+        assert_eq!(state.get("album:b"), Some(&1));
+    }
+
+    #[test]
+    fn test_progress_clamps_items_done() {
+        let progress = Progress::new(2);
+        progress.tick(1, 100);
+        progress.tick(2, 50);
+        progress.tick(3, 25); // nadmiarowy tick nie przekracza total
+        assert_eq!(progress.items_done(), 2);
+        assert_eq!(progress.items_total(), 2);
+        assert_eq!(progress.bytes_done(), 175);
+    }
+
+    fn test_kdf_params() -> KdfParams {
+        KdfParams {
+            algorithm: "argon2id".into(),
+            time_cost: 3,
+            memory_cost_kib: 65536,
+            parallelism: 2,
+        }
+    }
+
+    /// Świeży katalog roboczy pod `std::env::temp_dir()`, posprzątany przed startem.
+    fn temp_snapshot_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("alfa_snapshot_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_delta_materialize_composes_chain() {
+        let dir = temp_snapshot_dir("delta_materialize");
+        let seed = SecretBox::new(Box::new([7u8; 32]));
+        let mut manager = SnapshotManager::new(&dir, 100);
+        manager.set_full_interval(3);
+
+        let mut state = HashMap::new();
+        state.insert("album:a".to_string(), 1);
+        manager
+            .create_snapshot(&seed, test_kdf_params(), state.clone())
+            .unwrap(); // epoch 1: full
+
+        state.insert("album:a".to_string(), 2);
+        manager
+            .create_snapshot(&seed, test_kdf_params(), state.clone())
+            .unwrap(); // epoch 2: delta
+
+        state.insert("album:b".to_string(), 5);
+        state.remove("album:a");
+        manager
+            .create_snapshot(&seed, test_kdf_params(), state.clone())
+            .unwrap(); // epoch 3: delta
+
+        let materialized = manager.materialize(3).unwrap().unwrap();
+        assert_eq!(materialized.get("album:a"), None);
+        assert_eq!(materialized.get("album:b"), Some(&5));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_materialize_errors_on_missing_intermediate_delta() {
+        let dir = temp_snapshot_dir("missing_delta");
+        let seed = SecretBox::new(Box::new([7u8; 32]));
+        let mut manager = SnapshotManager::new(&dir, 100);
+        manager.set_full_interval(3); // full@1, delty @2 i @3
+
+        for epoch in 1..=3u64 {
+            let mut state = HashMap::new();
+            state.insert(format!("album:{epoch}"), epoch);
+            manager
+                .create_snapshot(&seed, test_kdf_params(), state)
+                .unwrap();
+        }
+
+        // Zasymuluj brakującą deltę pośrednią: usuń z dysku plik delty epoch 2,
+        // od której zależy delta epoch 3.
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            if name.starts_with("snapshot_000002_") {
+                std::fs::remove_file(&path).unwrap();
+            }
+        }
+
+        let err = manager.materialize(3).unwrap_err();
+        assert!(err.to_string().contains('2'));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_cleanup_preserves_full_delta_chain() {
+        let dir = temp_snapshot_dir("cleanup_chain");
+        let seed = SecretBox::new(Box::new([7u8; 32]));
+        // full_interval=10, max_snapshots=5: full@1, deltas@2-10, full@11, deltas@12-20.
+        let mut manager = SnapshotManager::new(&dir, 5);
+        manager.set_full_interval(10);
+
+        let mut state = HashMap::new();
+        for epoch in 1..=20u64 {
+            state.insert(format!("album:{epoch}"), epoch);
+            manager
+                .create_snapshot(&seed, test_kdf_params(), state.clone())
+                .unwrap();
+        }
+
+        // Zachowywane są epoki 16-20; ich łańcuch wymaga bazy 11 oraz delt 12-15.
+        for required in [11u64, 12, 13, 14, 15, 16, 17, 18, 19, 20] {
+            assert!(
+                manager.load_by_epoch(required).unwrap().is_some(),
+                "epoch {required} should survive cleanup"
+            );
+        }
+
+        let materialized = manager.materialize(20).unwrap().unwrap();
+        assert_eq!(materialized.get("album:20"), Some(&20));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_or_create_resumes_chain_after_restart() {
+        let dir = temp_snapshot_dir("load_or_create");
+        let seed = SecretBox::new(Box::new([7u8; 32]));
+
+        {
+            let mut manager = SnapshotManager::new(&dir, 100);
+            manager.set_full_interval(3);
+
+            let mut state = HashMap::new();
+            state.insert("album:a".to_string(), 1);
+            manager
+                .create_snapshot(&seed, test_kdf_params(), state.clone())
+                .unwrap(); // epoch 1: full
+
+            state.insert("album:a".to_string(), 2);
+            manager
+                .create_snapshot(&seed, test_kdf_params(), state.clone())
+                .unwrap(); // epoch 2: delta
+        }
+
+        // Simulate a process restart: a fresh manager built with `new` would
+        // collide with the existing epoch-1 file and forget `prev_state`.
+        let mut resumed = SnapshotManager::load_or_create(&dir, 100).unwrap();
+        assert_eq!(resumed.current_epoch(), 2);
+
+        let mut state = HashMap::new();
+        state.insert("album:a".to_string(), 2);
+        state.insert("album:b".to_string(), 9);
+        let snapshot = resumed
+            .create_snapshot(&seed, test_kdf_params(), state)
+            .unwrap();
+        assert_eq!(snapshot.epoch, 3);
+
+        let materialized = resumed.materialize(3).unwrap().unwrap();
+        assert_eq!(materialized.get("album:a"), Some(&2));
+        assert_eq!(materialized.get("album:b"), Some(&9));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_compression_roundtrip() {
+        let dir = temp_snapshot_dir("compression_roundtrip");
+        std::fs::create_dir_all(&dir).unwrap();
+        let seed = SecretBox::new(Box::new([3u8; 32]));
+        let mut key_usages = HashMap::new();
+        key_usages.insert("album:a".to_string(), 11);
+
+        for compression in [Compression::None, Compression::Snappy, Compression::Zstd] {
+            let mut snapshot = PqxSnapshot::new(1, test_kdf_params(), key_usages.clone(), None);
+            snapshot.sign(&seed);
+
+            let path = dir.join(format!("snap.{}", compression.extension()));
+            snapshot.save(&path, compression).unwrap();
+            let loaded = PqxSnapshot::load(&path).unwrap();
+
+            assert_eq!(loaded.key_usages, snapshot.key_usages);
+            assert_eq!(loaded.signature, snapshot.signature);
+            assert!(loaded.verify(&seed));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_mldsa_sign_verify() {
+        let seed = SecretBox::new(Box::new([11u8; 32]));
+
+        let mut snapshot = PqxSnapshot::new(1, test_kdf_params(), HashMap::new(), None);
+        snapshot.scheme = SignatureScheme::MlDsa65;
+        snapshot.sign(&seed);
+
+        assert!(!snapshot.signature.is_empty());
+        assert!(snapshot.public_key.is_some());
+        assert!(snapshot.verify(&seed));
+        // Audytor weryfikuje wyłącznie osadzonym kluczem publicznym, bez seeda.
+        assert!(snapshot.verify_auditable());
+
+        // Zły seed nadal weryfikuje się poprawnie, bo ML-DSA sprawdza klucz
+        // publiczny osadzony w snapshocie, a nie seed.
+        let bad_seed = SecretBox::new(Box::new([12u8; 32]));
+        assert!(snapshot.verify(&bad_seed));
+
+        // Zmanipulowany podpis nie przechodzi weryfikacji.
+        let mut tampered = snapshot.clone();
+        tampered.signature = hex::encode([0u8; 64]);
+        assert!(!tampered.verify(&seed));
+    }
 }
@@ -6,6 +6,7 @@
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 use chrono::{DateTime, Utc, Duration};
 use parking_lot::RwLock;
 
@@ -22,6 +23,19 @@ pub struct AIConfig {
     pub auto_tag_threshold: f32,
     /// Duplicate detection sensitivity (0.0 - 1.0)
     pub duplicate_sensitivity: f32,
+    /// Half-life, in days, of the recency weight applied to learned events.
+    /// An event contributes `exp(-Δdays / half_life_days)` to a photo's score,
+    /// so older behaviour fades smoothly rather than counting forever.
+    #[serde(default = "default_half_life_days")]
+    pub half_life_days: f32,
+    /// Window, in seconds, within which two `PhotoViewed` events are treated as
+    /// a co-view for temporal clustering.
+    #[serde(default = "default_coview_window_secs")]
+    pub coview_window_secs: i64,
+    /// Stable per-vault replica identity, seeded once and used to reconcile
+    /// learned state when the `ai/` directory is synced across devices.
+    #[serde(default)]
+    pub replica_id: String,
 }
 
 impl Default for AIConfig {
@@ -31,10 +45,21 @@ impl Default for AIConfig {
             max_events: 10000,
             auto_tag_threshold: 0.8,
             duplicate_sensitivity: 0.9,
+            half_life_days: default_half_life_days(),
+            coview_window_secs: default_coview_window_secs(),
+            replica_id: String::new(),
         }
     }
 }
 
+fn default_half_life_days() -> f32 {
+    30.0
+}
+
+fn default_coview_window_secs() -> i64 {
+    300
+}
+
 /// User action event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserEvent {
@@ -101,48 +126,115 @@ pub struct SelfHealingAI {
     patterns: RwLock<Vec<LearnedPattern>>,
     /// Photo clusters (for grouping)
     clusters: RwLock<HashMap<String, Vec<String>>>,
+    /// Perceptual (dHash) fingerprint per photo, used for duplicate detection
+    hashes: RwLock<HashMap<String, u64>>,
+    /// Incremental recency-weighted accumulators driving `learn()`.
+    learn_state: RwLock<LearnState>,
     /// Last heal timestamp
     last_heal: RwLock<Option<DateTime<Utc>>>,
 }
 
+/// Incrementally-maintained learning accumulators.
+///
+/// Weights are anchored to `reference`; a `learn()` call first decays every
+/// accumulated weight forward to the current time, then folds in only the
+/// events newer than `watermark`, so the scan cost is proportional to the new
+/// events rather than the whole history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LearnState {
+    /// Instant the stored weights are decayed to.
+    reference: Option<DateTime<Utc>>,
+    /// Timestamp of the newest event already folded in (exclusive watermark).
+    watermark: Option<DateTime<Utc>>,
+    /// Recency-weighted mass per `"<photo>|<EventType>"` key.
+    weights: HashMap<String, f64>,
+    /// Raw occurrence count per `"<photo>|<EventType>"` key.
+    counts: HashMap<String, usize>,
+    /// Symmetric co-view mass keyed `"<a>\u{1f}<b>"` with `a < b`.
+    coview: HashMap<String, f64>,
+    /// Recency-weighted view mass per photo, used to normalize co-views.
+    views: HashMap<String, f64>,
+}
+
 impl SelfHealingAI {
     /// Create new AI module
     pub fn new(root: &Path) -> VaultResult<Self> {
         let ai_path = root.join("ai");
         std::fs::create_dir_all(&ai_path)?;
-        
+
+        let mut config = AIConfig::default();
+        config.replica_id = uuid::Uuid::new_v4().to_string();
+
         Ok(Self {
             root: root.to_path_buf(),
-            config: AIConfig::default(),
+            config,
             events: RwLock::new(Vec::new()),
             patterns: RwLock::new(Vec::new()),
             clusters: RwLock::new(HashMap::new()),
+            hashes: RwLock::new(HashMap::new()),
+            learn_state: RwLock::new(LearnState::default()),
             last_heal: RwLock::new(None),
         })
     }
     
     /// Load existing AI state
     pub fn load(root: &Path) -> VaultResult<Self> {
-        let ai = Self::new(root)?;
-        
-        // Load events
-        let events_path = root.join("ai").join("events.json");
-        if events_path.exists() {
-            let data = std::fs::read_to_string(&events_path)?;
-            if let Ok(events) = serde_json::from_str::<Vec<UserEvent>>(&data) {
-                *ai.events.write() = events;
+        let mut ai = Self::new(root)?;
+
+        // Load config (preserving the replica id seeded for this vault)
+        let config_path = root.join("ai").join("config.json");
+        if config_path.exists() {
+            let data = std::fs::read_to_string(&config_path)?;
+            if let Ok(config) = serde_json::from_str::<AIConfig>(&data) {
+                ai.config = config;
+                if ai.config.replica_id.is_empty() {
+                    ai.config.replica_id = uuid::Uuid::new_v4().to_string();
+                }
             }
         }
-        
+
+        // Load events (version-checked; a corrupt or future-version file is an
+        // error rather than a silent reset, protecting learned history).
+        if let Some(events) =
+            load_versioned::<Vec<UserEvent>>(&root.join("ai").join("events.json"), "events")?
+        {
+            *ai.events.write() = events;
+        }
+
         // Load patterns
-        let patterns_path = root.join("ai").join("patterns.json");
-        if patterns_path.exists() {
-            let data = std::fs::read_to_string(&patterns_path)?;
-            if let Ok(patterns) = serde_json::from_str::<Vec<LearnedPattern>>(&data) {
-                *ai.patterns.write() = patterns;
+        if let Some(patterns) =
+            load_versioned::<Vec<LearnedPattern>>(&root.join("ai").join("patterns.json"), "patterns")?
+        {
+            *ai.patterns.write() = patterns;
+        }
+
+        // Load perceptual hashes
+        let hashes_path = root.join("ai").join("hashes.json");
+        if hashes_path.exists() {
+            let data = std::fs::read_to_string(&hashes_path)?;
+            if let Ok(hashes) = serde_json::from_str::<HashMap<String, u64>>(&data) {
+                *ai.hashes.write() = hashes;
             }
         }
-        
+
+        // Load incremental learning accumulators
+        let learn_path = root.join("ai").join("learn_state.json");
+        if learn_path.exists() {
+            let data = std::fs::read_to_string(&learn_path)?;
+            if let Ok(state) = serde_json::from_str::<LearnState>(&data) {
+                *ai.learn_state.write() = state;
+            }
+        }
+
+        // Load photo clusters
+        let clusters_path = root.join("ai").join("clusters.json");
+        if clusters_path.exists() {
+            let data = std::fs::read_to_string(&clusters_path)?;
+            if let Ok(clusters) = serde_json::from_str::<HashMap<String, Vec<String>>>(&data) {
+                *ai.clusters.write() = clusters;
+            }
+        }
+
         Ok(ai)
     }
     
@@ -150,17 +242,34 @@ impl SelfHealingAI {
     pub fn save(&self) -> VaultResult<()> {
         let ai_path = self.root.join("ai");
         std::fs::create_dir_all(&ai_path)?;
-        
-        // Save events
+
+        // Save config
+        let config_json = serde_json::to_string_pretty(&self.config)?;
+        std::fs::write(ai_path.join("config.json"), config_json)?;
+
+        // Save events (versioned envelope; previous file backed up to .bak)
         let events = self.events.read();
-        let events_json = serde_json::to_string_pretty(&*events)?;
-        std::fs::write(ai_path.join("events.json"), events_json)?;
-        
+        write_versioned(&ai_path.join("events.json"), "events", &*events)?;
+
         // Save patterns
         let patterns = self.patterns.read();
-        let patterns_json = serde_json::to_string_pretty(&*patterns)?;
-        std::fs::write(ai_path.join("patterns.json"), patterns_json)?;
-        
+        write_versioned(&ai_path.join("patterns.json"), "patterns", &*patterns)?;
+
+        // Save perceptual hashes
+        let hashes = self.hashes.read();
+        let hashes_json = serde_json::to_string_pretty(&*hashes)?;
+        std::fs::write(ai_path.join("hashes.json"), hashes_json)?;
+
+        // Save incremental learning accumulators
+        let learn_state = self.learn_state.read();
+        let learn_json = serde_json::to_string_pretty(&*learn_state)?;
+        std::fs::write(ai_path.join("learn_state.json"), learn_json)?;
+
+        // Save photo clusters
+        let clusters = self.clusters.read();
+        let clusters_json = serde_json::to_string_pretty(&*clusters)?;
+        std::fs::write(ai_path.join("clusters.json"), clusters_json)?;
+
         Ok(())
     }
     
@@ -226,69 +335,184 @@ impl SelfHealingAI {
     // LEARNING & PREDICTIONS
     // ═══════════════════════════════════════════════════════════════════════
     
-    /// Analyze events and learn patterns
+    /// Analyze events and learn patterns.
+    ///
+    /// Each `(photo_id, event_type)` accumulates a recency-weighted score
+    /// `Σ exp(-Δdays / half_life)`; per photo the scores are normalized into a
+    /// `confidence ∈ [0, 1]` while `occurrences` keeps the raw count. Temporal
+    /// co-occurrence of `PhotoViewed` events within `coview_window_secs`
+    /// produces [`PatternType::PhotoClustering`] groups. The accumulators are
+    /// updated incrementally: only events newer than the stored watermark are
+    /// folded in, with previously-accumulated mass decayed forward first.
     pub fn learn(&self) -> VaultResult<usize> {
+        let now = Utc::now();
+        let half_life = self.config.half_life_days.max(f32::EPSILON) as f64;
+        let window = Duration::seconds(self.config.coview_window_secs.max(1));
+
+        let mut state = self.learn_state.write();
+
+        // Decay existing mass forward to `now` so older behaviour keeps fading
+        // between calls rather than being frozen at its last-seen weight.
+        if let Some(reference) = state.reference {
+            let delta_days = (now - reference).num_milliseconds() as f64 / 86_400_000.0;
+            if delta_days > 0.0 {
+                let factor = (-delta_days / half_life).exp();
+                for w in state.weights.values_mut() {
+                    *w *= factor;
+                }
+                for w in state.coview.values_mut() {
+                    *w *= factor;
+                }
+                for w in state.views.values_mut() {
+                    *w *= factor;
+                }
+            }
+        }
+        state.reference = Some(now);
+
+        // Fold in events newer than the watermark, keeping them time-ordered so
+        // the co-view window slides correctly.
+        let watermark = state.watermark;
         let events = self.events.read();
-        let mut new_patterns = 0;
-        
-        // Count event types per photo
-        let mut photo_events: HashMap<String, HashMap<EventType, usize>> = HashMap::new();
-        
-        for event in events.iter() {
-            photo_events
-                .entry(event.photo_id.clone())
-                .or_insert_with(HashMap::new)
-                .entry(event.event_type.clone())
-                .and_modify(|c| *c += 1)
-                .or_insert(1);
+        let mut fresh: Vec<&UserEvent> = events
+            .iter()
+            .filter(|e| watermark.map_or(true, |w| e.timestamp > w))
+            .collect();
+        fresh.sort_by_key(|e| e.timestamp);
+
+        let mut viewed: Vec<(&str, DateTime<Utc>, f64)> = Vec::new();
+        for event in &fresh {
+            let weight = {
+                let delta_days = (now - event.timestamp).num_milliseconds() as f64 / 86_400_000.0;
+                (-delta_days.max(0.0) / half_life).exp()
+            };
+            let key = format!("{}|{:?}", event.photo_id, event.event_type);
+            *state.weights.entry(key.clone()).or_insert(0.0) += weight;
+            *state.counts.entry(key).or_insert(0) += 1;
+
+            if event.event_type == EventType::PhotoViewed {
+                *state.views.entry(event.photo_id.clone()).or_insert(0.0) += weight;
+                viewed.push((&event.photo_id, event.timestamp, weight));
+            }
         }
-        
-        // Learn hiding patterns
+
+        // Slide the co-view window over the freshly-seen views.
+        for i in 0..viewed.len() {
+            for j in (i + 1)..viewed.len() {
+                if viewed[j].1 - viewed[i].1 > window {
+                    break;
+                }
+                if viewed[i].0 == viewed[j].0 {
+                    continue;
+                }
+                let (a, b) = if viewed[i].0 < viewed[j].0 {
+                    (viewed[i].0, viewed[j].0)
+                } else {
+                    (viewed[j].0, viewed[i].0)
+                };
+                let pair = format!("{}\u{1f}{}", a, b);
+                *state.coview.entry(pair).or_insert(0.0) += viewed[i].2.min(viewed[j].2);
+            }
+        }
+
+        state.watermark = fresh.last().map(|e| e.timestamp).or(state.watermark);
+        drop(events);
+
+        // Per-photo normalization of the accumulated weights.
+        let mut photo_total: HashMap<&str, f64> = HashMap::new();
+        for (key, w) in &state.weights {
+            if let Some((photo, _)) = key.split_once('|') {
+                *photo_total.entry(photo).or_insert(0.0) += *w;
+            }
+        }
+
+        let mut new_patterns = 0;
         let mut patterns = self.patterns.write();
-        
-        for (photo_id, event_counts) in &photo_events {
-            // Detect hiding pattern
-            if let Some(&hidden_count) = event_counts.get(&EventType::PhotoHidden) {
-                if hidden_count > 0 {
-                    let pattern = LearnedPattern {
-                        id: format!("hide_{}", photo_id),
-                        pattern_type: PatternType::HidingPattern,
-                        confidence: 1.0,
-                        occurrences: hidden_count,
-                        last_seen: Utc::now(),
+
+        for (key, &w) in &state.weights {
+            let (photo, event) = match key.split_once('|') {
+                Some(v) => v,
+                None => continue,
+            };
+            let total = photo_total.get(photo).copied().unwrap_or(w).max(f64::EPSILON);
+            let confidence = (w / total) as f32;
+            let occurrences = state.counts.get(key).copied().unwrap_or(0);
+
+            let (id, pattern_type) = match event {
+                "PhotoHidden" => (format!("hide_{}", photo), PatternType::HidingPattern),
+                "PhotoFavorited" => (format!("fav_{}", photo), PatternType::PhotoPreference),
+                _ => continue,
+            };
+
+            match patterns.iter_mut().find(|p| p.id == id) {
+                Some(existing) => {
+                    existing.confidence = confidence;
+                    existing.occurrences = occurrences;
+                    existing.last_seen = now;
+                }
+                None => {
+                    patterns.push(LearnedPattern {
+                        id,
+                        pattern_type,
+                        confidence,
+                        occurrences,
+                        last_seen: now,
                         data: HashMap::new(),
-                    };
-                    
-                    if !patterns.iter().any(|p| p.id == pattern.id) {
-                        patterns.push(pattern);
-                        new_patterns += 1;
-                    }
+                    });
+                    new_patterns += 1;
                 }
             }
-            
-            // Detect favorite patterns
-            if let Some(&fav_count) = event_counts.get(&EventType::PhotoFavorited) {
-                if fav_count > 0 {
-                    let pattern = LearnedPattern {
-                        id: format!("fav_{}", photo_id),
-                        pattern_type: PatternType::PhotoPreference,
-                        confidence: 1.0,
-                        occurrences: fav_count,
-                        last_seen: Utc::now(),
-                        data: HashMap::new(),
-                    };
-                    
-                    if !patterns.iter().any(|p| p.id == pattern.id) {
-                        patterns.push(pattern);
-                        new_patterns += 1;
-                    }
+        }
+
+        // Emit clustering patterns for frequently co-viewed pairs.
+        let mut clusters = self.clusters.write();
+        for (pair, &co) in &state.coview {
+            let (a, b) = match pair.split_once('\u{1f}') {
+                Some(v) => v,
+                None => continue,
+            };
+            let base = state
+                .views
+                .get(a)
+                .copied()
+                .unwrap_or(0.0)
+                .min(state.views.get(b).copied().unwrap_or(0.0))
+                .max(f64::EPSILON);
+            let frequency = (co / base) as f32;
+            if frequency < self.config.auto_tag_threshold {
+                continue;
+            }
+
+            let id = format!("cluster_{}_{}", a, b);
+            let members = vec![a.to_string(), b.to_string()];
+            clusters.insert(id.clone(), members.clone());
+            let mut data = HashMap::new();
+            data.insert("members".to_string(), members.join(","));
+            match patterns.iter_mut().find(|p| p.id == id) {
+                Some(existing) => {
+                    existing.confidence = frequency.min(1.0);
+                    existing.last_seen = now;
+                    existing.data = data;
+                }
+                None => {
+                    patterns.push(LearnedPattern {
+                        id,
+                        pattern_type: PatternType::PhotoClustering,
+                        confidence: frequency.min(1.0),
+                        occurrences: co.round() as usize,
+                        last_seen: now,
+                        data,
+                    });
+                    new_patterns += 1;
                 }
             }
         }
-        
+
+        drop(clusters);
         drop(patterns);
+        drop(state);
         self.save()?;
-        
+
         Ok(new_patterns)
     }
     
@@ -337,7 +561,7 @@ impl SelfHealingAI {
     // ═══════════════════════════════════════════════════════════════════════
     
     /// Run self-healing process
-    pub fn heal(&mut self) -> VaultResult<usize> {
+    pub fn heal(&self) -> VaultResult<usize> {
         let mut fixes = 0;
         
         // 1. Clean up old events (older than 90 days)
@@ -396,6 +620,342 @@ impl SelfHealingAI {
             learning_enabled: self.config.learning_enabled,
         }
     }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // DUPLICATE DETECTION
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Compute and store the perceptual (dHash) fingerprint of a photo.
+    ///
+    /// Called at import time while the plaintext is still in hand; the hash is
+    /// kept offline alongside the clusters so [`find_duplicates`](Self::find_duplicates)
+    /// can group look-alikes later without ever re-reading the image bytes.
+    pub fn hash_photo(&self, photo_id: &str, image_data: &[u8]) -> VaultResult<()> {
+        let hash = dhash(image_data)?;
+        self.hashes.write().insert(photo_id.to_string(), hash);
+        Ok(())
+    }
+
+    /// Group near-identical photos by perceptual hash.
+    ///
+    /// Two photos are considered the same shot when the Hamming distance
+    /// between their dHashes is within the threshold derived from
+    /// [`AIConfig::duplicate_sensitivity`] (higher sensitivity ⇒ tighter
+    /// match). Transitively-similar photos are merged through union-find so a
+    /// run of gradually-changing frames lands in a single cluster, and each
+    /// group is recorded as a [`PatternType::PhotoClustering`] pattern.
+    pub fn find_duplicates(&self) -> Vec<Vec<String>> {
+        let threshold = ((1.0 - self.config.duplicate_sensitivity) * 64.0).round() as u32;
+
+        let hashes = self.hashes.read();
+        let ids: Vec<&String> = hashes.keys().collect();
+
+        let mut uf = UnionFind::new(ids.len());
+        for i in 0..ids.len() {
+            for j in (i + 1)..ids.len() {
+                if hamming(hashes[ids[i]], hashes[ids[j]]) <= threshold {
+                    uf.union(i, j);
+                }
+            }
+        }
+
+        // Collect members by representative root.
+        let mut groups: HashMap<usize, Vec<String>> = HashMap::new();
+        for (i, id) in ids.iter().enumerate() {
+            groups.entry(uf.find(i)).or_default().push((*id).clone());
+        }
+        drop(hashes);
+
+        let mut clusters: Vec<Vec<String>> = groups
+            .into_values()
+            .filter(|members| members.len() > 1)
+            .collect();
+        for members in clusters.iter_mut() {
+            members.sort();
+        }
+        clusters.sort();
+
+        // Record each duplicate group as a learned clustering pattern.
+        let mut patterns = self.patterns.write();
+        for members in &clusters {
+            let id = format!("dup_{}", members[0]);
+            let mut data = HashMap::new();
+            data.insert("members".to_string(), members.join(","));
+            patterns.retain(|p| p.id != id);
+            patterns.push(LearnedPattern {
+                id,
+                pattern_type: PatternType::PhotoClustering,
+                confidence: 1.0,
+                occurrences: members.len(),
+                last_seen: Utc::now(),
+                data,
+            });
+        }
+
+        clusters
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // CRDT MERGE (multi-device sync)
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Conflict-free merge of another replica's learned state into this one.
+    ///
+    /// The event log is treated as a grow-only set keyed by a content hash, so
+    /// the operation is idempotent, commutative and associative — re-importing
+    /// the same log changes nothing. Patterns form an observed-remove/LWW map
+    /// keyed by [`LearnedPattern::id`]: per-replica occurrence counts (tracked
+    /// in `data` under `occ:<replica>`) are max-merged to avoid double
+    /// counting, the total `occurrences` is their sum, and `last_seen` /
+    /// `confidence` take the larger value.
+    pub fn merge(&self, other: &SelfHealingAI) {
+        // Grow-only union of events, de-duplicated by content hash.
+        {
+            let mut events = self.events.write();
+            let mut seen: std::collections::HashSet<String> =
+                events.iter().map(event_hash).collect();
+            for event in other.events.read().iter() {
+                if seen.insert(event_hash(event)) {
+                    events.push(event.clone());
+                }
+            }
+            events.sort_by_key(|e| e.timestamp);
+        }
+
+        // LWW/OR map merge of patterns keyed by id.
+        {
+            let mut patterns = self.patterns.write();
+            let mut by_id: HashMap<String, usize> = patterns
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (p.id.clone(), i))
+                .collect();
+            for incoming in other.patterns.read().iter() {
+                match by_id.get(&incoming.id) {
+                    Some(&i) => {
+                        let merged = merge_pattern(&patterns[i], incoming);
+                        patterns[i] = merged;
+                    }
+                    None => {
+                        by_id.insert(incoming.id.clone(), patterns.len());
+                        patterns.push(incoming.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Load a replica from the `ai/` directory under `root` and merge it in.
+    pub fn merge_from_path(&self, root: &Path) -> VaultResult<()> {
+        let other = Self::load(root)?;
+        self.merge(&other);
+        Ok(())
+    }
+}
+
+/// Current on-disk schema version for AI state files (events, patterns).
+const AI_SCHEMA_VERSION: u32 = 1;
+
+/// Read `schema_version` from a persisted value, treating legacy files (a bare
+/// array, or an object without the field) as version 0.
+fn schema_version_of(value: &serde_json::Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// Copy an existing file to a sibling `<name>.bak` before it is rewritten.
+fn backup_file(path: &Path) -> VaultResult<()> {
+    if path.exists() {
+        if let Some(name) = path.file_name() {
+            let bak = path.with_file_name(format!("{}.bak", name.to_string_lossy()));
+            std::fs::copy(path, bak)?;
+        }
+    }
+    Ok(())
+}
+
+/// Upgrade an older on-disk layout to the current schema, field by field.
+///
+/// Version 0 is the original un-versioned format: a bare JSON array. It is
+/// wrapped into the `{schema_version, <field>: [...]}` envelope; future
+/// in-place field migrations are added here as versions are introduced.
+fn migrate_ai(field: &str, from: u32, value: serde_json::Value) -> serde_json::Value {
+    if from == 0 {
+        let payload = if value.is_array() {
+            value
+        } else {
+            value
+                .get(field)
+                .cloned()
+                .unwrap_or_else(|| serde_json::Value::Array(Vec::new()))
+        };
+        return serde_json::json!({ "schema_version": AI_SCHEMA_VERSION, field: payload });
+    }
+    value
+}
+
+/// Load a versioned state file, migrating older layouts forward.
+///
+/// Returns `Ok(None)` when the file is absent. A file whose `schema_version`
+/// is newer than this build understands yields [`VaultError::VaultCorrupted`]
+/// rather than a silent empty start.
+fn load_versioned<T: DeserializeOwned>(path: &Path, field: &str) -> VaultResult<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = std::fs::read_to_string(path)?;
+    let value: serde_json::Value = serde_json::from_str(&data)
+        .map_err(|e| VaultError::VaultCorrupted(format!("{field}: {e}")))?;
+
+    let from = schema_version_of(&value);
+    if from > AI_SCHEMA_VERSION {
+        return Err(VaultError::VaultCorrupted(format!(
+            "{field} schema version {from} is newer than supported {AI_SCHEMA_VERSION}"
+        )));
+    }
+
+    let migrated = migrate_ai(field, from, value);
+    let payload = migrated
+        .get(field)
+        .cloned()
+        .ok_or_else(|| VaultError::VaultCorrupted(format!("{field}: missing payload")))?;
+    let typed = serde_json::from_value(payload)
+        .map_err(|e| VaultError::VaultCorrupted(format!("{field}: {e}")))?;
+    Ok(Some(typed))
+}
+
+/// Write a versioned state file, backing up any existing copy to `.bak` first.
+fn write_versioned<T: Serialize>(path: &Path, field: &str, payload: &T) -> VaultResult<()> {
+    backup_file(path)?;
+    let envelope = serde_json::json!({ "schema_version": AI_SCHEMA_VERSION, field: payload });
+    let json = serde_json::to_string_pretty(&envelope)?;
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+/// Stable content hash of an event, used to deduplicate the grow-only log.
+fn event_hash(event: &UserEvent) -> String {
+    use sha2::{Digest, Sha256};
+    let mut keys: Vec<&String> = event.metadata.keys().collect();
+    keys.sort();
+    let mut hasher = Sha256::new();
+    hasher.update(event.timestamp.to_rfc3339().as_bytes());
+    hasher.update(format!("{:?}", event.event_type).as_bytes());
+    hasher.update(event.photo_id.as_bytes());
+    for k in keys {
+        hasher.update(k.as_bytes());
+        hasher.update(b"=");
+        hasher.update(event.metadata[k].as_bytes());
+        hasher.update(b";");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Merge two views of the same pattern without double-counting occurrences.
+///
+/// Each replica records its own count under `occ:<replica_id>` in `data`; the
+/// merge keeps the per-replica maximum and derives the total as their sum,
+/// falling back to the larger bare `occurrences` when no replica keys exist.
+fn merge_pattern(a: &LearnedPattern, b: &LearnedPattern) -> LearnedPattern {
+    let mut data = a.data.clone();
+    for (k, v) in &b.data {
+        if k.starts_with("occ:") {
+            let bv: usize = v.parse().unwrap_or(0);
+            let entry = data.entry(k.clone()).or_insert_with(|| "0".to_string());
+            let av: usize = entry.parse().unwrap_or(0);
+            *entry = av.max(bv).to_string();
+        } else {
+            data.entry(k.clone()).or_insert_with(|| v.clone());
+        }
+    }
+
+    let per_replica: usize = data
+        .iter()
+        .filter(|(k, _)| k.starts_with("occ:"))
+        .map(|(_, v)| v.parse::<usize>().unwrap_or(0))
+        .sum();
+    let occurrences = if per_replica > 0 {
+        per_replica
+    } else {
+        a.occurrences.max(b.occurrences)
+    };
+
+    LearnedPattern {
+        id: a.id.clone(),
+        pattern_type: a.pattern_type.clone(),
+        confidence: a.confidence.max(b.confidence),
+        occurrences,
+        last_seen: a.last_seen.max(b.last_seen),
+        data,
+    }
+}
+
+/// Compute a 64-bit difference hash (dHash) of an encoded image.
+///
+/// The image is reduced to greyscale and resized to 9×8; for each of the 8
+/// rows the 8 left-to-right adjacent-pixel comparisons yield one bit each,
+/// packing into a single `u64`.
+fn dhash(image_data: &[u8]) -> VaultResult<u64> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let img = image::load_from_memory(image_data)
+            .map_err(|e| VaultError::DeserializationError(e.to_string()))?;
+        let small = img
+            .grayscale()
+            .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+            .to_luma8();
+
+        let mut hash = 0u64;
+        let mut bit = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                let left = small.get_pixel(x, y)[0];
+                let right = small.get_pixel(x + 1, y)[0];
+                if left < right {
+                    hash |= 1 << bit;
+                }
+                bit += 1;
+            }
+        }
+        Ok(hash)
+    }))
+    .map_err(|_| VaultError::DeserializationError("image decode panicked".to_string()))?
+}
+
+/// Hamming distance between two dHash fingerprints.
+fn hamming(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Minimal union-find over a fixed set of indices with path compression.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let ra = self.find(a);
+        let rb = self.find(b);
+        if ra != rb {
+            self.parent[ra] = rb;
+        }
+    }
 }
 
 /// AI Health Status
@@ -7,6 +7,8 @@
 use jni::JNIEnv;
 use jni::objects::{JClass, JString, JByteArray};
 use jni::sys::{jbyteArray, jboolean, jint, jlong, jstring, JNI_TRUE, JNI_FALSE};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Mutex;
 use std::path::PathBuf;
 
@@ -132,30 +134,118 @@ pub extern "system" fn Java_dev_alfa_vault_NativeVault_importPhoto(
     
     let guard = VAULT.lock().unwrap();
     if let Some(ref vault) = *guard {
-        // Write temp file and import
-        let temp_path = PathBuf::from("/data/local/tmp").join(&name);
-        if std::fs::write(&temp_path, &data).is_err() {
-            return std::ptr::null_mut();
-        }
-        
-        match vault.import_photo(&temp_path, &name) {
-            Ok(id) => {
-                let _ = std::fs::remove_file(&temp_path);
-                match env.new_string(&id) {
-                    Ok(s) => s.into_raw(),
-                    Err(_) => std::ptr::null_mut(),
-                }
-            }
-            Err(_) => {
-                let _ = std::fs::remove_file(&temp_path);
-                std::ptr::null_mut()
-            }
+        // Encrypt straight from memory — no plaintext ever touches disk.
+        match vault.import_photo_bytes(&data, &name) {
+            Ok(id) => match env.new_string(&id) {
+                Ok(s) => s.into_raw(),
+                Err(_) => std::ptr::null_mut(),
+            },
+            Err(_) => std::ptr::null_mut(),
         }
     } else {
         std::ptr::null_mut()
     }
 }
 
+// Active chunked-import sessions, keyed by an opaque handle handed back to
+// Kotlin. Lets large photos/videos stream in without allocating the whole
+// array on either side.
+static IMPORT_SESSIONS: Mutex<Option<HashMap<i64, crate::vault::ImportSession>>> =
+    Mutex::new(None);
+static NEXT_IMPORT_HANDLE: AtomicI64 = AtomicI64::new(1);
+
+/// Begin a chunked import, returning a handle (0 on failure).
+#[no_mangle]
+pub extern "system" fn Java_dev_alfa_vault_NativeVault_importPhotoBegin(
+    mut env: JNIEnv,
+    _class: JClass,
+    name: JString,
+) -> jlong {
+    let name: String = match env.get_string(&name) {
+        Ok(s) => s.into(),
+        Err(_) => return 0,
+    };
+
+    let guard = VAULT.lock().unwrap();
+    let vault = match *guard {
+        Some(ref v) => v,
+        None => return 0,
+    };
+
+    match vault.import_begin(&name, None) {
+        Ok(session) => {
+            let handle = NEXT_IMPORT_HANDLE.fetch_add(1, Ordering::Relaxed);
+            IMPORT_SESSIONS
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(handle, session);
+            handle
+        }
+        Err(_) => 0,
+    }
+}
+
+/// Append a plaintext chunk to an open import session.
+#[no_mangle]
+pub extern "system" fn Java_dev_alfa_vault_NativeVault_importPhotoChunk(
+    mut env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+    data: JByteArray,
+) -> jboolean {
+    let data = match env.convert_byte_array(&data) {
+        Ok(d) => d,
+        Err(_) => return JNI_FALSE,
+    };
+
+    let guard = VAULT.lock().unwrap();
+    let vault = match *guard {
+        Some(ref v) => v,
+        None => return JNI_FALSE,
+    };
+
+    let mut sessions = IMPORT_SESSIONS.lock().unwrap();
+    match sessions.as_mut().and_then(|m| m.get_mut(&handle)) {
+        Some(session) => match vault.import_chunk(session, &data) {
+            Ok(()) => JNI_TRUE,
+            Err(_) => JNI_FALSE,
+        },
+        None => JNI_FALSE,
+    }
+}
+
+/// Finalize a chunked import, returning the new photo id (null on failure).
+#[no_mangle]
+pub extern "system" fn Java_dev_alfa_vault_NativeVault_importPhotoEnd(
+    mut env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+) -> jstring {
+    let session = {
+        let mut sessions = IMPORT_SESSIONS.lock().unwrap();
+        sessions.as_mut().and_then(|m| m.remove(&handle))
+    };
+    let session = match session {
+        Some(s) => s,
+        None => return std::ptr::null_mut(),
+    };
+
+    let guard = VAULT.lock().unwrap();
+    let vault = match *guard {
+        Some(ref v) => v,
+        None => return std::ptr::null_mut(),
+    };
+
+    match vault.import_end(session) {
+        Ok(id) => match env.new_string(&id) {
+            Ok(s) => s.into_raw(),
+            Err(_) => std::ptr::null_mut(),
+        },
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
 /// Get photo by ID
 #[no_mangle]
 pub extern "system" fn Java_dev_alfa_vault_NativeVault_getPhoto(
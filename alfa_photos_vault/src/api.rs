@@ -13,6 +13,8 @@ use crate::crypto::KeyManager;
 use crate::vault::{PhotoVault, PhotoMeta, VaultState};
 use crate::rotation::{RotationManager, RotationStatus, RotationPolicy};
 use crate::ai::SelfHealingAI;
+use crate::biometrics::{Biometrics, AuthConfig};
+use crate::oplog::{OpPayload, BlobStore};
 use crate::error::{VaultError, VaultResult};
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -83,6 +85,8 @@ pub struct PhotoVaultApi {
     vault: Arc<RwLock<PhotoVault>>,
     /// Rotation manager
     rotation: Arc<RotationManager>,
+    /// Authentication gate (lockout / inactivity)
+    biometrics: Arc<RwLock<Biometrics>>,
     /// Configuration
     config: ApiConfig,
 }
@@ -96,17 +100,29 @@ impl PhotoVaultApi {
     pub fn create<P: AsRef<Path>>(path: P, pin: &str) -> VaultResult<Self> {
         let vault = PhotoVault::create(&path, pin)?;
         let rotation = RotationManager::load_or_create(path.as_ref())?;
-        
+
+        let biometrics = Self::load_biometrics(path.as_ref());
+        biometrics.write().mark_authenticated();
         Ok(Self {
             vault: Arc::new(RwLock::new(vault)),
             rotation: Arc::new(rotation),
+            biometrics,
             config: ApiConfig {
                 vault_path: path.as_ref().to_path_buf(),
                 ..Default::default()
             },
         })
     }
-    
+
+    /// Build a persistent [`Biometrics`] gate rooted at the vault directory.
+    fn load_biometrics(path: &Path) -> Arc<RwLock<Biometrics>> {
+        let state_path = path.join("auth_state.json");
+        Arc::new(RwLock::new(Biometrics::with_persistence(
+            AuthConfig::default(),
+            &state_path,
+        )))
+    }
+
     /// Open existing vault
     pub fn open<P: AsRef<Path>>(path: P, pin: &str) -> VaultResult<Self> {
         let vault = PhotoVault::open(&path)?;
@@ -119,16 +135,19 @@ impl PhotoVaultApi {
             log::warn!("Key rotation is due! Consider rotating keys.");
         }
         
+        let biometrics = Self::load_biometrics(path.as_ref());
+        biometrics.write().mark_authenticated();
         Ok(Self {
             vault: Arc::new(RwLock::new(vault)),
             rotation: Arc::new(rotation),
+            biometrics,
             config: ApiConfig {
                 vault_path: path.as_ref().to_path_buf(),
                 ..Default::default()
             },
         })
     }
-    
+
     /// Open with custom config
     pub fn open_with_config<P: AsRef<Path>>(path: P, pin: &str, config: ApiConfig) -> VaultResult<Self> {
         let vault = PhotoVault::open(&path)?;
@@ -136,9 +155,12 @@ impl PhotoVaultApi {
         
         let rotation = RotationManager::load_or_create(path.as_ref())?;
         
+        let biometrics = Self::load_biometrics(path.as_ref());
+        biometrics.write().mark_authenticated();
         Ok(Self {
             vault: Arc::new(RwLock::new(vault)),
             rotation: Arc::new(rotation),
+            biometrics,
             config,
         })
     }
@@ -154,13 +176,47 @@ impl PhotoVaultApi {
     
     /// Unlock vault with PIN
     pub fn unlock(&self, pin: &str) -> VaultResult<()> {
-        self.vault.read().unlock(pin)
+        self.vault.read().unlock(pin)?;
+        self.biometrics.write().mark_authenticated();
+        Ok(())
+    }
+
+    /// Auto-lock the vault if the session has idled past `timeout_seconds`.
+    ///
+    /// Intended to be polled from a background task; returns `true` when it
+    /// locked the vault (zeroizing keys), `false` otherwise.
+    pub fn maybe_auto_lock(&self) -> bool {
+        if !self.is_unlocked() {
+            return false;
+        }
+        let mut bio = self.biometrics.write();
+        if bio.is_expired() {
+            self.vault.read().lock();
+            bio.lock();
+            true
+        } else {
+            false
+        }
     }
     
     /// Check if vault is unlocked
     pub fn is_unlocked(&self) -> bool {
         self.vault.read().is_unlocked()
     }
+
+    /// Change the vault PIN, re-wrapping the master key under the new PIN.
+    ///
+    /// The old PIN is checked through the [`Biometrics`] gate, so repeated
+    /// wrong guesses count toward the same lockout as a failed unlock. Only
+    /// the wrapped-key blob is rewritten; photos are never re-encrypted.
+    pub fn change_pin(&self, old_pin: &str, new_pin: &str) -> VaultResult<()> {
+        let vault = self.vault.clone();
+        self.biometrics
+            .write()
+            .change_pin(old_pin, new_pin, |old, new| {
+                vault.read().change_pin(old, new)
+            })
+    }
     
     // ═══════════════════════════════════════════════════════════════════════
     // PHOTO OPERATIONS
@@ -214,22 +270,40 @@ impl PhotoVaultApi {
     
     /// Add tag to photo
     pub fn add_tag(&self, photo_id: &str, tag: &str) -> VaultResult<()> {
-        self.vault.read().add_tag(photo_id, tag)
+        self.vault.read().add_tag(photo_id, tag)?;
+        self.vault.read().record_op(OpPayload::AddTag {
+            id: photo_id.to_string(),
+            tag: tag.to_string(),
+        })
     }
-    
+
     /// Remove tag from photo
     pub fn remove_tag(&self, photo_id: &str, tag: &str) -> VaultResult<()> {
-        self.vault.read().remove_tag(photo_id, tag)
+        self.vault.read().remove_tag(photo_id, tag)?;
+        self.vault.read().record_op(OpPayload::RemoveTag {
+            id: photo_id.to_string(),
+            tag: tag.to_string(),
+        })
     }
-    
+
     /// Toggle favorite status
     pub fn toggle_favorite(&self, photo_id: &str) -> VaultResult<bool> {
-        self.vault.read().toggle_favorite(photo_id)
+        let value = self.vault.read().toggle_favorite(photo_id)?;
+        self.vault.read().record_op(OpPayload::SetFavorite {
+            id: photo_id.to_string(),
+            value,
+        })?;
+        Ok(value)
     }
-    
+
     /// Toggle hidden status
     pub fn toggle_hidden(&self, photo_id: &str) -> VaultResult<bool> {
-        self.vault.read().toggle_hidden(photo_id)
+        let value = self.vault.read().toggle_hidden(photo_id)?;
+        self.vault.read().record_op(OpPayload::SetHidden {
+            id: photo_id.to_string(),
+            value,
+        })?;
+        Ok(value)
     }
     
     // ═══════════════════════════════════════════════════════════════════════
@@ -243,12 +317,81 @@ impl PhotoVaultApi {
     
     /// Move photo to King's Vault
     pub fn hide_photo(&self, photo_id: &str) -> VaultResult<()> {
-        self.vault.read().hide_photo(photo_id)
+        self.vault.read().hide_photo(photo_id)?;
+        self.vault.read().record_op(OpPayload::SetHidden {
+            id: photo_id.to_string(),
+            value: true,
+        })
     }
-    
+
     /// Unhide photo from King's Vault
     pub fn unhide_photo(&self, photo_id: &str) -> VaultResult<()> {
-        self.vault.read().unhide_photo(photo_id)
+        self.vault.read().unhide_photo(photo_id)?;
+        self.vault.read().record_op(OpPayload::SetHidden {
+            id: photo_id.to_string(),
+            value: false,
+        })
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // MULTI-KEY MANAGEMENT (PER-ALBUM KEYS)
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Register a new named data key whose material is derived from `pin`.
+    /// The key is left mounted. Returns its generated id.
+    pub fn add_key(&self, name: &str, pin: &str) -> VaultResult<String> {
+        self.vault.read().register_key(name, pin.as_bytes())
+    }
+
+    /// Mount a named key, verifying `pin` against the stored album key.
+    pub fn mount_key(&self, name: &str, pin: &str) -> VaultResult<()> {
+        let id = self.key_id_by_name(name)?;
+        self.vault.read().mount_key_with(&id, pin.as_bytes())
+    }
+
+    /// Unmount a named key, zeroizing its material.
+    pub fn unmount_key(&self, name: &str) -> VaultResult<()> {
+        let id = self.key_id_by_name(name)?;
+        self.vault.read().unmount_key(&id);
+        Ok(())
+    }
+
+    /// Unmount every named key.
+    pub fn unmount_all(&self) {
+        self.vault.read().unmount_all();
+    }
+
+    /// Set the default key used by imports that don't name one.
+    pub fn set_default_key(&self, name: &str) -> VaultResult<()> {
+        let id = self.key_id_by_name(name)?;
+        self.vault.read().set_default_key(&id)
+    }
+
+    /// List registered keys with their current mount state.
+    pub fn list_keys(&self) -> Vec<crate::vault::KeyInfo> {
+        self.vault.read().list_keys()
+    }
+
+    /// Resolve a key name to its id, erroring if no such key is registered.
+    fn key_id_by_name(&self, name: &str) -> VaultResult<String> {
+        self.vault
+            .read()
+            .list_keys()
+            .into_iter()
+            .find(|k| k.name == name)
+            .map(|k| k.id)
+            .ok_or_else(|| VaultError::KeyNotFound(name.to_string()))
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // MULTI-DEVICE SYNC
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Synchronize the vault against a shared blob store, pushing local
+    /// operations and merging any recorded remotely. Returns the number of
+    /// remote operations newly applied. The vault must be unlocked.
+    pub fn sync(&self, remote: &dyn BlobStore) -> VaultResult<usize> {
+        self.vault.read().sync(remote)
     }
     
     // ═══════════════════════════════════════════════════════════════════════
@@ -270,10 +413,10 @@ impl PhotoVaultApi {
         self.rotation.days_until_rotation()
     }
     
-    /// Perform key rotation (re-encrypts all files with new epoch key)
-    pub fn rotate_keys(&self, new_pin: &str) -> VaultResult<u64> {
-        // This would re-encrypt all files with new keys
-        // For now, just update rotation state
+    /// Perform key rotation (derives and wraps a fresh per-epoch data key)
+    pub fn rotate_keys(&self, _new_pin: &str) -> VaultResult<u64> {
+        // Attach the current master key so the epoch key can be derived/wrapped.
+        self.rotation.set_master_key(self.vault.read().master_key()?);
         self.rotation.rotate()
     }
     
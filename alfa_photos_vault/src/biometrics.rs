@@ -2,11 +2,16 @@
 //!
 //! PIN and biometric authentication (Android Keystore integration)
 
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
 use crate::error::{VaultError, VaultResult};
 
+/// Hard ceiling on the escalating lockout backoff (24 hours).
+const MAX_LOCKOUT_SECONDS: u64 = 24 * 60 * 60;
+
 /// Authentication method
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum AuthMethod {
@@ -50,33 +55,33 @@ impl Default for AuthConfig {
 }
 
 /// Authentication state
-#[derive(Debug, Clone)]
+///
+/// The brute-force penalty fields (`failed_attempts`, `locked_until`,
+/// `lockout_cycles`) are serialized so a process restart cannot reset the
+/// penalty; the transient session fields are skipped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AuthState {
-    /// Is authenticated
+    /// Is authenticated (session-only, never persisted)
+    #[serde(skip)]
     pub authenticated: bool,
-    /// Authentication time
+    /// Authentication time (session-only, never persisted)
+    #[serde(skip)]
     pub auth_time: Option<DateTime<Utc>>,
     /// Failed attempts
     pub failed_attempts: u8,
     /// Locked until
     pub locked_until: Option<DateTime<Utc>>,
-}
-
-impl Default for AuthState {
-    fn default() -> Self {
-        Self {
-            authenticated: false,
-            auth_time: None,
-            failed_attempts: 0,
-            locked_until: None,
-        }
-    }
+    /// Number of completed lockout cycles (drives exponential backoff)
+    #[serde(default)]
+    pub lockout_cycles: u32,
 }
 
 /// Biometric authenticator (stub - actual implementation is platform-specific)
 pub struct Biometrics {
     config: AuthConfig,
     state: AuthState,
+    /// Optional on-disk location for the persistent penalty state.
+    state_path: Option<PathBuf>,
 }
 
 impl Biometrics {
@@ -85,9 +90,75 @@ impl Biometrics {
         Self {
             config,
             state: AuthState::default(),
+            state_path: None,
         }
     }
-    
+
+    /// Create a handler that persists its penalty state to `path`, loading any
+    /// existing state so a restart cannot reset the brute-force penalty.
+    pub fn with_persistence(config: AuthConfig, path: &Path) -> Self {
+        let state = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<AuthState>(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            config,
+            state,
+            state_path: Some(path.to_path_buf()),
+        }
+    }
+
+    /// Persist the penalty state if a path is configured (best-effort).
+    fn persist(&self) {
+        if let Some(path) = &self.state_path {
+            if let Ok(bytes) = serde_json::to_vec(&self.state) {
+                let _ = std::fs::write(path, bytes);
+            }
+        }
+    }
+
+    /// Compute the lockout duration for the current cycle, doubling each cycle
+    /// and capped at [`MAX_LOCKOUT_SECONDS`].
+    fn lockout_seconds(&self) -> u64 {
+        let cycles = self.state.lockout_cycles.max(1);
+        self.config
+            .lockout_duration
+            .saturating_mul(1u64.checked_shl(cycles - 1).unwrap_or(u64::MAX))
+            .min(MAX_LOCKOUT_SECONDS)
+    }
+
+    /// Record a failed attempt, escalating into a longer lockout each time the
+    /// attempt counter reaches `max_attempts`.
+    fn register_failure(&mut self) -> VaultError {
+        self.state.failed_attempts += 1;
+        let locked = if self.state.failed_attempts >= self.config.max_attempts {
+            self.state.lockout_cycles += 1;
+            self.state.locked_until = Some(
+                Utc::now() + chrono::Duration::seconds(self.lockout_seconds() as i64),
+            );
+            self.state.failed_attempts = 0;
+            true
+        } else {
+            false
+        };
+        self.persist();
+        if locked {
+            VaultError::TooManyAttempts
+        } else {
+            VaultError::InvalidPin
+        }
+    }
+
+    /// Record a successful authentication, clearing all penalties.
+    fn register_success(&mut self) {
+        self.state.authenticated = true;
+        self.state.auth_time = Some(Utc::now());
+        self.state.failed_attempts = 0;
+        self.state.locked_until = None;
+        self.state.lockout_cycles = 0;
+        self.persist();
+    }
+
     /// Check if locked out
     pub fn is_locked(&self) -> bool {
         if let Some(until) = self.state.locked_until {
@@ -114,22 +185,10 @@ impl Biometrics {
         }
         
         if verify(_pin) {
-            self.state.authenticated = true;
-            self.state.auth_time = Some(Utc::now());
-            self.state.failed_attempts = 0;
-            self.state.locked_until = None;
+            self.register_success();
             Ok(())
         } else {
-            self.state.failed_attempts += 1;
-            
-            if self.state.failed_attempts >= self.config.max_attempts {
-                self.state.locked_until = Some(
-                    Utc::now() + chrono::Duration::seconds(self.config.lockout_duration as i64)
-                );
-                Err(VaultError::TooManyAttempts)
-            } else {
-                Err(VaultError::InvalidPin)
-            }
+            Err(self.register_failure())
         }
     }
     
@@ -141,16 +200,63 @@ impl Biometrics {
         }
         
         if callback() {
-            self.state.authenticated = true;
-            self.state.auth_time = Some(Utc::now());
-            self.state.failed_attempts = 0;
+            self.register_success();
             Ok(())
         } else {
             self.state.failed_attempts += 1;
+            self.persist();
             Err(VaultError::BiometricFailed)
         }
     }
     
+    /// Change the PIN under the same lockout guard used for authentication.
+    ///
+    /// `apply` performs the actual re-wrap (see
+    /// [`PhotoVault::change_pin`](crate::vault::PhotoVault::change_pin)). A
+    /// rejected old PIN counts as a failed attempt and can trigger the same
+    /// escalating lockout as a failed unlock; a successful change resets the
+    /// counter.
+    pub fn change_pin(
+        &mut self,
+        old_pin: &str,
+        new_pin: &str,
+        apply: impl FnOnce(&str, &str) -> VaultResult<()>,
+    ) -> VaultResult<()> {
+        if self.is_locked() {
+            return Err(VaultError::TooManyAttempts);
+        }
+
+        match apply(old_pin, new_pin) {
+            Ok(()) => {
+                self.register_success();
+                Ok(())
+            }
+            Err(e) => {
+                // A wrong old PIN escalates the same backoff as a failed unlock;
+                // a non-auth failure is returned verbatim.
+                if matches!(e, VaultError::InvalidPin) {
+                    Err(self.register_failure())
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    /// Mark the session authenticated as of now (e.g. after an unlock that
+    /// bypassed [`authenticate_pin`], such as recovery-phrase unlock).
+    pub fn mark_authenticated(&mut self) {
+        self.state.authenticated = true;
+        self.state.auth_time = Some(Utc::now());
+    }
+
+    /// Refresh the activity timestamp so the inactivity timer restarts.
+    pub fn touch(&mut self) {
+        if self.state.authenticated {
+            self.state.auth_time = Some(Utc::now());
+        }
+    }
+
     /// Lock (clear authentication)
     pub fn lock(&mut self) {
         self.state.authenticated = false;
@@ -211,4 +317,51 @@ mod tests {
             Err(VaultError::TooManyAttempts)
         ));
     }
+
+    #[test]
+    fn test_escalating_backoff() {
+        let config = AuthConfig {
+            max_attempts: 1,
+            lockout_duration: 10,
+            ..Default::default()
+        };
+        let mut bio = Biometrics::new(config);
+
+        // Each single failure completes a cycle; the window doubles each time.
+        let _ = bio.authenticate_pin("x", |_| false);
+        assert_eq!(bio.state.lockout_cycles, 1);
+        assert_eq!(bio.lockout_seconds(), 10);
+
+        bio.state.locked_until = None; // pretend the window elapsed
+        let _ = bio.authenticate_pin("x", |_| false);
+        assert_eq!(bio.state.lockout_cycles, 2);
+        assert_eq!(bio.lockout_seconds(), 20);
+
+        // A success clears the escalation.
+        bio.state.locked_until = None;
+        bio.authenticate_pin("ok", |_| true).unwrap();
+        assert_eq!(bio.state.lockout_cycles, 0);
+    }
+
+    #[test]
+    fn test_change_pin_guarded_by_lockout() {
+        let config = AuthConfig {
+            max_attempts: 2,
+            ..Default::default()
+        };
+        let mut bio = Biometrics::new(config);
+
+        // Wrong old PIN counts as a failed attempt.
+        let wrong = |_: &str, _: &str| Err(VaultError::InvalidPin);
+        assert!(matches!(
+            bio.change_pin("bad", "new", wrong),
+            Err(VaultError::InvalidPin)
+        ));
+        // Second failure trips the lockout.
+        assert!(matches!(
+            bio.change_pin("bad", "new", wrong),
+            Err(VaultError::TooManyAttempts)
+        ));
+        assert!(bio.is_locked());
+    }
 }
@@ -0,0 +1,144 @@
+//! ALFA Photos Vault - BlurHash Encoder
+//!
+//! Computes a compact [BlurHash](https://blurha.sh) string from a decoded
+//! image. The string is tiny enough to embed in [`PhotoMeta`](crate::vault::PhotoMeta)
+//! so a UI can paint a blurred placeholder straight from the index, without
+//! decrypting any thumbnail — which matters while the vault is still being
+//! unlocked and thumbnails are decrypted lazily.
+
+use image::{DynamicImage, GenericImageView};
+
+/// Base-83 alphabet used by the BlurHash format.
+const ALPHABET: &[u8; 83] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// Encode `value` into `length` base-83 characters (most significant first).
+fn encode_base83(value: u32, length: usize) -> String {
+    let mut out = String::with_capacity(length);
+    for i in 1..=length {
+        let digit = (value / 83u32.pow((length - i) as u32)) % 83;
+        out.push(ALPHABET[digit as usize] as char);
+    }
+    out
+}
+
+/// sRGB (0-255) to linear light.
+fn srgb_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Linear light to sRGB (0-255), matching the reference encoder's rounding.
+fn linear_to_srgb(value: f32) -> u32 {
+    let v = value.clamp(0.0, 1.0);
+    let s = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (s * 255.0 + 0.5) as u32
+}
+
+/// Signed power used when quantizing AC coefficients.
+fn sign_pow(value: f32, exp: f32) -> f32 {
+    value.abs().powf(exp).copysign(value)
+}
+
+/// Encode a decoded image into a BlurHash string with `x_components` by
+/// `y_components` DCT basis functions (each must lie in the 1..=9 range the
+/// format allows). Returns `None` if the component counts are out of range.
+pub fn encode(img: &DynamicImage, x_components: usize, y_components: usize) -> Option<String> {
+    if !(1..=9).contains(&x_components) || !(1..=9).contains(&y_components) {
+        return None;
+    }
+
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let rgb = img.to_rgb8();
+
+    // Accumulate each basis coefficient over the linear-light pixels.
+    let mut factors = Vec::with_capacity(x_components * y_components);
+    for y in 0..y_components {
+        for x in 0..x_components {
+            let normalisation = if x == 0 && y == 0 { 1.0 } else { 2.0 };
+            let mut r = 0.0f32;
+            let mut g = 0.0f32;
+            let mut b = 0.0f32;
+            for py in 0..height {
+                for px in 0..width {
+                    let basis = (std::f32::consts::PI * x as f32 * px as f32 / width as f32).cos()
+                        * (std::f32::consts::PI * y as f32 * py as f32 / height as f32).cos();
+                    let pixel = rgb.get_pixel(px, py);
+                    r += basis * srgb_to_linear(pixel[0]);
+                    g += basis * srgb_to_linear(pixel[1]);
+                    b += basis * srgb_to_linear(pixel[2]);
+                }
+            }
+            let scale = normalisation / (width as f32 * height as f32);
+            factors.push([r * scale, g * scale, b * scale]);
+        }
+    }
+
+    let dc = factors[0];
+    let ac = &factors[1..];
+
+    // Header: one char for the component counts.
+    let size_flag = (x_components - 1) + (y_components - 1) * 9;
+    let mut hash = encode_base83(size_flag as u32, 1);
+
+    // Second char: the maximum AC magnitude, quantized into 0..=82.
+    let max_ac = ac
+        .iter()
+        .flat_map(|f| f.iter().copied())
+        .fold(0.0f32, |acc, v| acc.max(v.abs()));
+    let (quantised_max, max_value) = if ac.is_empty() {
+        (0u32, 1.0f32)
+    } else {
+        let q = ((max_ac * 166.0 - 0.5).floor().clamp(0.0, 82.0)) as u32;
+        (q, (q as f32 + 1.0) / 166.0)
+    };
+    hash.push_str(&encode_base83(quantised_max, 1));
+
+    // DC term: the average colour, four chars.
+    let dc_value = (linear_to_srgb(dc[0]) << 16) | (linear_to_srgb(dc[1]) << 8) | linear_to_srgb(dc[2]);
+    hash.push_str(&encode_base83(dc_value, 4));
+
+    // AC terms: two chars each, normalized against the maximum magnitude.
+    for f in ac {
+        let quant = |v: f32| {
+            ((sign_pow(v / max_value, 0.5) * 9.0 + 9.5).floor().clamp(0.0, 18.0)) as u32
+        };
+        let value = quant(f[0]) * 19 * 19 + quant(f[1]) * 19 + quant(f[2]);
+        hash.push_str(&encode_base83(value, 2));
+    }
+
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodes_expected_length_and_header() {
+        // A flat 32x32 image: 4x3 components => 1 + 1 + 4 + (4*3-1)*2 = 28 chars.
+        let img = DynamicImage::new_rgb8(32, 32);
+        let hash = encode(&img, 4, 3).unwrap();
+        assert_eq!(hash.len(), 1 + 1 + 4 + (4 * 3 - 1) * 2);
+        // Header char encodes (4-1) + (3-1)*9 = 21 => base-83 digit 'L'.
+        assert_eq!(hash.chars().next().unwrap(), ALPHABET[21] as char);
+    }
+
+    #[test]
+    fn test_rejects_bad_component_counts() {
+        let img = DynamicImage::new_rgb8(8, 8);
+        assert!(encode(&img, 0, 3).is_none());
+        assert!(encode(&img, 4, 10).is_none());
+    }
+}
@@ -0,0 +1,216 @@
+//! ALFA Photos Vault - Content-Defined Chunk Store
+//!
+//! Splits photos into content-defined chunks (FastCDC) and stores each chunk
+//! content-addressed and encrypted under a key derived from its content hash.
+//! Near-identical imports share chunks, giving real cross-photo deduplication;
+//! a reference count ensures a chunk is removed only when the last photo
+//! referencing it is deleted.
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::crypto::{decrypt_aes_gcm, derive_key, encrypt_aes_gcm, EncryptedData, KeyManager};
+use crate::error::{VaultError, VaultResult};
+use crate::secure_fs::SecureFs;
+
+/// Minimum chunk size (2 KiB).
+const MIN_SIZE: usize = 2 * 1024;
+/// Target average chunk size (8 KiB).
+const AVG_SIZE: usize = 8 * 1024;
+/// Maximum chunk size (64 KiB).
+const MAX_SIZE: usize = 64 * 1024;
+
+/// HKDF context for per-chunk convergent keys.
+const CHUNK_CONTEXT: &[u8] = b"ALFA:CHUNK:v1";
+
+/// Build the 256-entry gear table deterministically via splitmix64.
+fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x9E3779B97F4A7C15;
+    for slot in table.iter_mut() {
+        // splitmix64 step.
+        state = state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        *slot = z ^ (z >> 31);
+    }
+    table
+}
+
+/// A normalized-chunking cut-mask pair: a stricter mask before the average
+/// size (harder to cut, pushing chunks toward the target) and a looser mask
+/// after it (easier to cut, capping the tail).
+const MASK_S: u64 = (1 << 15) - 1;
+const MASK_L: u64 = (1 << 11) - 1;
+
+/// Split `data` into content-defined chunk boundaries using FastCDC.
+pub fn chunk_boundaries(data: &[u8]) -> Vec<(usize, usize)> {
+    let gear = gear_table();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < data.len() {
+        let remaining = data.len() - start;
+        if remaining <= MIN_SIZE {
+            chunks.push((start, data.len()));
+            break;
+        }
+
+        let mut hash: u64 = 0;
+        let mut end = start + MIN_SIZE;
+        let normal = (start + AVG_SIZE).min(data.len());
+        let hard_limit = (start + MAX_SIZE).min(data.len());
+
+        // Roll from the minimum size onward, cutting on the mask for the zone.
+        let mut i = start + MIN_SIZE;
+        let mut cut = false;
+        while i < hard_limit {
+            hash = (hash << 1).wrapping_add(gear[data[i] as usize]);
+            let mask = if i < normal { MASK_S } else { MASK_L };
+            if hash & mask == 0 {
+                end = i + 1;
+                cut = true;
+                break;
+            }
+            i += 1;
+        }
+        if !cut {
+            end = hard_limit;
+        }
+
+        chunks.push((start, end));
+        start = end;
+    }
+
+    chunks
+}
+
+/// Content-addressed, reference-counted encrypted chunk store.
+pub struct ChunkStore {
+    fs: SecureFs,
+    refcounts: HashMap<String, u64>,
+}
+
+impl ChunkStore {
+    /// Open (or initialize) the chunk store under the vault, loading refcounts.
+    pub fn open(root: &std::path::Path, keys: &KeyManager) -> VaultResult<Self> {
+        std::fs::create_dir_all(root.join("chunks"))?;
+        let fs = SecureFs::new(root);
+        let refcounts = match fs.read_file("chunks/refcounts.enc") {
+            Ok(bytes) => {
+                let encrypted = EncryptedData::parse(&bytes)?;
+                let plain = decrypt_aes_gcm(keys.index_key(), &encrypted, &[])?;
+                serde_json::from_slice(&plain)?
+            }
+            Err(_) => HashMap::new(),
+        };
+        Ok(Self { fs, refcounts })
+    }
+
+    /// Derive the convergent key for a chunk from its content hash.
+    fn chunk_key(hash_hex: &str) -> VaultResult<crate::crypto::VaultKey> {
+        derive_key(hash_hex.as_bytes(), b"", CHUNK_CONTEXT)
+    }
+
+    /// Store a file as chunks, returning the ordered list of chunk hashes.
+    pub fn store(&mut self, keys: &KeyManager, data: &[u8]) -> VaultResult<Vec<String>> {
+        let mut hashes = Vec::new();
+        for (start, end) in chunk_boundaries(data) {
+            let chunk = &data[start..end];
+            let hash = hex_digest(chunk);
+
+            let count = self.refcounts.entry(hash.clone()).or_insert(0);
+            if *count == 0 {
+                // First reference: encrypt and persist the chunk content.
+                let key = Self::chunk_key(&hash)?;
+                let encrypted = encrypt_aes_gcm(&key, chunk, &[])?;
+                self.fs
+                    .write_file(&format!("chunks/{hash}.enc"), &encrypted.to_bytes())?;
+            }
+            *count += 1;
+            hashes.push(hash);
+        }
+        self.persist(keys)?;
+        Ok(hashes)
+    }
+
+    /// Reassemble and decrypt a file from its ordered chunk hashes.
+    pub fn read(&self, _keys: &KeyManager, hashes: &[String]) -> VaultResult<Vec<u8>> {
+        let mut out = Vec::new();
+        for hash in hashes {
+            let bytes = self.fs.read_file(&format!("chunks/{hash}.enc"))?;
+            let encrypted = EncryptedData::parse(&bytes)?;
+            let key = Self::chunk_key(hash)?;
+            let chunk = decrypt_aes_gcm(&key, &encrypted, &[])?;
+            // Defensive: a hash mismatch means a corrupted/tampered chunk.
+            if hex_digest(&chunk) != *hash {
+                return Err(VaultError::VaultCorrupted(format!("chunk {hash} hash mismatch")));
+            }
+            out.extend_from_slice(&chunk);
+        }
+        Ok(out)
+    }
+
+    /// Release references to a file's chunks, deleting any that reach zero.
+    pub fn release(&mut self, keys: &KeyManager, hashes: &[String]) -> VaultResult<()> {
+        for hash in hashes {
+            if let Some(count) = self.refcounts.get_mut(hash) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    self.refcounts.remove(hash);
+                    let _ = self.fs.delete_file(&format!("chunks/{hash}.enc"));
+                }
+            }
+        }
+        self.persist(keys)
+    }
+
+    /// Number of distinct stored chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.refcounts.len()
+    }
+
+    fn persist(&self, keys: &KeyManager) -> VaultResult<()> {
+        let bytes = serde_json::to_vec(&self.refcounts)?;
+        let encrypted = encrypt_aes_gcm(keys.index_key(), &bytes, &[])?;
+        self.fs
+            .write_file("chunks/refcounts.enc", &encrypted.to_bytes())
+    }
+}
+
+/// Hex-encode the SHA-256 digest of `data`.
+fn hex_digest(data: &[u8]) -> String {
+    let digest = Sha256::digest(data);
+    let mut s = String::with_capacity(64);
+    for b in digest {
+        s.push_str(&format!("{b:02x}"));
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunking_is_deterministic_and_bounded() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+        let a = chunk_boundaries(&data);
+        let b = chunk_boundaries(&data);
+        assert_eq!(a, b);
+
+        // Chunks cover the whole input contiguously and respect the max size.
+        assert_eq!(a.first().unwrap().0, 0);
+        assert_eq!(a.last().unwrap().1, data.len());
+        for (s, e) in &a {
+            assert!(e - s <= MAX_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(chunk_boundaries(&[]).is_empty());
+    }
+}
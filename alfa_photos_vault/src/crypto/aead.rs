@@ -3,17 +3,91 @@
 //! AES-256-GCM for photos, XChaCha20-Poly1305 for index/metadata.
 
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
+use aes_gcm_siv::Aes256GcmSiv;
 use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
 use zeroize::Zeroize;
 
 use super::keys::{VaultKey, NONCE_LEN, XCHACHA_NONCE_LEN, generate_nonce, generate_xchacha_nonce};
 use crate::error::{VaultError, VaultResult};
 
-/// Encrypted data with nonce prepended
+/// Selectable authenticated-encryption cipher for vault data.
+///
+/// AES-256-GCM is the default on hardware-accelerated platforms, while
+/// XChaCha20-Poly1305's 192-bit nonce lets random nonces stay safe across a
+/// large photo library. The chosen method is recorded in [`VaultConfig`] and
+/// tagged into every blob (see [`encrypt`]) so files stay decryptable after
+/// the vault default changes.
+///
+/// [`VaultConfig`]: crate::vault::VaultConfig
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum EncryptionMethod {
+    /// AES-256-GCM with a 96-bit nonce.
+    #[default]
+    Aes256Gcm,
+    /// XChaCha20-Poly1305 with a 192-bit nonce.
+    XChaCha20Poly1305,
+    /// AES-256-GCM-SIV: nonce-misuse-resistant (synthetic IV), 96-bit nonce.
+    ///
+    /// The synthetic-IV construction folds a POLYVAL MAC of the plaintext into
+    /// the counter IV, so a repeated random nonce under the same key leaks only
+    /// whether two messages are identical rather than the authentication key.
+    /// Intended for the index key, which encrypts thousands of small records
+    /// and would otherwise drift toward the random-nonce birthday bound.
+    Aes256GcmSiv,
+}
+
+impl EncryptionMethod {
+    /// One-byte on-disk tag prefixed to each encrypted blob.
+    pub fn tag(self) -> u8 {
+        match self {
+            EncryptionMethod::Aes256Gcm => 1,
+            EncryptionMethod::XChaCha20Poly1305 => 2,
+            EncryptionMethod::Aes256GcmSiv => 3,
+        }
+    }
+
+    /// Recover a method from its on-disk tag.
+    pub fn from_tag(tag: u8) -> VaultResult<Self> {
+        match tag {
+            1 => Ok(EncryptionMethod::Aes256Gcm),
+            2 => Ok(EncryptionMethod::XChaCha20Poly1305),
+            3 => Ok(EncryptionMethod::Aes256GcmSiv),
+            other => Err(VaultError::DecryptionFailed(format!(
+                "unknown encryption method tag {other}"
+            ))),
+        }
+    }
+
+    /// Nonce length in bytes for this cipher.
+    pub fn nonce_len(self) -> usize {
+        match self {
+            EncryptionMethod::Aes256Gcm | EncryptionMethod::Aes256GcmSiv => NONCE_LEN,
+            EncryptionMethod::XChaCha20Poly1305 => XCHACHA_NONCE_LEN,
+        }
+    }
+}
+
+/// Magic bytes identifying the self-describing envelope format.
+const ENVELOPE_MAGIC: &[u8; 3] = b"AEV";
+/// Envelope format version.
+const ENVELOPE_VERSION: u8 = 1;
+/// Fixed header length: magic(3) + version(1) + algorithm(1) + nonce length(1).
+const ENVELOPE_HEADER_LEN: usize = 3 + 1 + 1 + 1;
+
+/// Encrypted data in a self-describing, versioned envelope.
+///
+/// On disk the layout is `magic || version || algorithm id || nonce length ||
+/// nonce || ciphertext`, so a blob records which cipher produced it and can be
+/// decrypted without out-of-band knowledge. The fixed header (everything up to
+/// and including the nonce length) is bound as AEAD associated data, so the
+/// algorithm id and version cannot be rewritten to force a downgrade.
 pub struct EncryptedData {
+    /// Cipher that produced the ciphertext.
+    pub method: EncryptionMethod,
     /// Nonce (12 or 24 bytes depending on cipher)
     pub nonce: Vec<u8>,
     /// Ciphertext with authentication tag
@@ -21,118 +95,550 @@ pub struct EncryptedData {
 }
 
 impl EncryptedData {
-    /// Serialize to bytes (nonce || ciphertext)
+    /// Cipher recorded in this envelope.
+    pub fn algorithm(&self) -> EncryptionMethod {
+        self.method
+    }
+
+    /// The fixed envelope header for a given method and nonce length. This is
+    /// prepended to every blob and also fed to the AEAD as associated data.
+    fn header(method: EncryptionMethod, nonce_len: usize) -> [u8; ENVELOPE_HEADER_LEN] {
+        let mut h = [0u8; ENVELOPE_HEADER_LEN];
+        h[..3].copy_from_slice(ENVELOPE_MAGIC);
+        h[3] = ENVELOPE_VERSION;
+        h[4] = method.tag();
+        h[5] = nonce_len as u8;
+        h
+    }
+
+    /// Serialize to the self-describing envelope: header || nonce || ciphertext.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut result = Vec::with_capacity(self.nonce.len() + self.ciphertext.len());
+        let header = Self::header(self.method, self.nonce.len());
+        let mut result =
+            Vec::with_capacity(header.len() + self.nonce.len() + self.ciphertext.len());
+        result.extend_from_slice(&header);
         result.extend_from_slice(&self.nonce);
         result.extend_from_slice(&self.ciphertext);
         result
     }
-    
-    /// Deserialize from bytes (AES-GCM format)
-    pub fn from_bytes_aes(data: &[u8]) -> VaultResult<Self> {
-        if data.len() < NONCE_LEN + 16 {
+
+    /// Parse a self-describing envelope, dispatching on the algorithm id. Fails
+    /// if the magic, version, or declared nonce length is wrong.
+    pub fn parse(data: &[u8]) -> VaultResult<Self> {
+        if data.len() < ENVELOPE_HEADER_LEN {
             return Err(VaultError::DecryptionFailed("Data too short".into()));
         }
-        
-        Ok(Self {
-            nonce: data[..NONCE_LEN].to_vec(),
-            ciphertext: data[NONCE_LEN..].to_vec(),
-        })
-    }
-    
-    /// Deserialize from bytes (XChaCha20 format)
-    pub fn from_bytes_xchacha(data: &[u8]) -> VaultResult<Self> {
-        if data.len() < XCHACHA_NONCE_LEN + 16 {
+        if &data[..3] != ENVELOPE_MAGIC {
+            return Err(VaultError::DecryptionFailed("bad envelope magic".into()));
+        }
+        if data[3] != ENVELOPE_VERSION {
+            return Err(VaultError::DecryptionFailed(format!(
+                "unsupported envelope version {}",
+                data[3]
+            )));
+        }
+        let method = EncryptionMethod::from_tag(data[4])?;
+        let nonce_len = data[5] as usize;
+        if nonce_len != method.nonce_len() {
+            return Err(VaultError::DecryptionFailed("nonce length mismatch".into()));
+        }
+        if data.len() < ENVELOPE_HEADER_LEN + nonce_len + 16 {
             return Err(VaultError::DecryptionFailed("Data too short".into()));
         }
-        
+        let nonce = data[ENVELOPE_HEADER_LEN..ENVELOPE_HEADER_LEN + nonce_len].to_vec();
+        let ciphertext = data[ENVELOPE_HEADER_LEN + nonce_len..].to_vec();
         Ok(Self {
-            nonce: data[..XCHACHA_NONCE_LEN].to_vec(),
-            ciphertext: data[XCHACHA_NONCE_LEN..].to_vec(),
+            method,
+            nonce,
+            ciphertext,
         })
     }
+
+    /// Associated data bound to the AEAD for this envelope: the caller's `aad`
+    /// (file id, version, …) followed by the fixed envelope header.
+    fn aad_with_header(method: EncryptionMethod, nonce_len: usize, aad: &[u8]) -> Vec<u8> {
+        let header = Self::header(method, nonce_len);
+        let mut out = Vec::with_capacity(aad.len() + header.len());
+        out.extend_from_slice(aad);
+        out.extend_from_slice(&header);
+        out
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
 // AES-256-GCM (for photos and thumbnails)
 // ═══════════════════════════════════════════════════════════════════════════
 
-/// Encrypt data with AES-256-GCM
-pub fn encrypt_aes_gcm(key: &VaultKey, plaintext: &[u8]) -> VaultResult<EncryptedData> {
+/// Encrypt data with AES-256-GCM, binding `aad` (plus the envelope header) as
+/// associated data so a blob cannot be replayed under a different file id.
+pub fn encrypt_aes_gcm(key: &VaultKey, plaintext: &[u8], aad: &[u8]) -> VaultResult<EncryptedData> {
     let cipher = Aes256Gcm::new_from_slice(key.expose())
         .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
-    
+
     let nonce_bytes = generate_nonce();
     let nonce = Nonce::from_slice(&nonce_bytes);
-    
+    let bound = EncryptedData::aad_with_header(EncryptionMethod::Aes256Gcm, NONCE_LEN, aad);
+
     let ciphertext = cipher
-        .encrypt(nonce, plaintext)
+        .encrypt(nonce, Payload { msg: plaintext, aad: &bound })
         .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
-    
+
     Ok(EncryptedData {
+        method: EncryptionMethod::Aes256Gcm,
         nonce: nonce_bytes.to_vec(),
         ciphertext,
     })
 }
 
-/// Decrypt data with AES-256-GCM
-pub fn decrypt_aes_gcm(key: &VaultKey, encrypted: &EncryptedData) -> VaultResult<Vec<u8>> {
+/// Decrypt data with AES-256-GCM, rebinding the same `aad` and envelope header.
+pub fn decrypt_aes_gcm(key: &VaultKey, encrypted: &EncryptedData, aad: &[u8]) -> VaultResult<Vec<u8>> {
     let cipher = Aes256Gcm::new_from_slice(key.expose())
         .map_err(|e| VaultError::DecryptionFailed(e.to_string()))?;
-    
+
     if encrypted.nonce.len() != NONCE_LEN {
         return Err(VaultError::DecryptionFailed("Invalid nonce length".into()));
     }
-    
+
     let nonce = Nonce::from_slice(&encrypted.nonce);
-    
-    let mut plaintext = cipher
-        .decrypt(nonce, encrypted.ciphertext.as_slice())
+    let bound = EncryptedData::aad_with_header(EncryptionMethod::Aes256Gcm, NONCE_LEN, aad);
+
+    let plaintext = cipher
+        .decrypt(nonce, Payload { msg: encrypted.ciphertext.as_slice(), aad: &bound })
         .map_err(|_| VaultError::DecryptionFailed("Authentication failed".into()))?;
-    
+
     // The plaintext will be zeroized when dropped
     Ok(plaintext)
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// AES-256-GCM-SIV (nonce-misuse-resistant, for the long-lived index key)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Encrypt data with AES-256-GCM-SIV (shares the 12-byte AES nonce format).
+pub fn encrypt_aes_gcm_siv(
+    key: &VaultKey,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> VaultResult<EncryptedData> {
+    let cipher = Aes256GcmSiv::new_from_slice(key.expose())
+        .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+
+    let nonce_bytes = generate_nonce();
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let bound = EncryptedData::aad_with_header(EncryptionMethod::Aes256GcmSiv, NONCE_LEN, aad);
+
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad: &bound })
+        .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+
+    Ok(EncryptedData {
+        method: EncryptionMethod::Aes256GcmSiv,
+        nonce: nonce_bytes.to_vec(),
+        ciphertext,
+    })
+}
+
+/// Decrypt data with AES-256-GCM-SIV
+pub fn decrypt_aes_gcm_siv(
+    key: &VaultKey,
+    encrypted: &EncryptedData,
+    aad: &[u8],
+) -> VaultResult<Vec<u8>> {
+    let cipher = Aes256GcmSiv::new_from_slice(key.expose())
+        .map_err(|e| VaultError::DecryptionFailed(e.to_string()))?;
+
+    if encrypted.nonce.len() != NONCE_LEN {
+        return Err(VaultError::DecryptionFailed("Invalid nonce length".into()));
+    }
+
+    let nonce = Nonce::from_slice(&encrypted.nonce);
+    let bound = EncryptedData::aad_with_header(EncryptionMethod::Aes256GcmSiv, NONCE_LEN, aad);
+
+    cipher
+        .decrypt(nonce, Payload { msg: encrypted.ciphertext.as_slice(), aad: &bound })
+        .map_err(|_| VaultError::DecryptionFailed("Authentication failed".into()))
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // XChaCha20-Poly1305 (for index and metadata - faster for small data)
 // ═══════════════════════════════════════════════════════════════════════════
 
 /// Encrypt data with XChaCha20-Poly1305
-pub fn encrypt_xchacha(key: &VaultKey, plaintext: &[u8]) -> VaultResult<EncryptedData> {
+pub fn encrypt_xchacha(key: &VaultKey, plaintext: &[u8], aad: &[u8]) -> VaultResult<EncryptedData> {
     let cipher = XChaCha20Poly1305::new_from_slice(key.expose())
         .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
-    
+
     let nonce_bytes = generate_xchacha_nonce();
     let nonce = XNonce::from_slice(&nonce_bytes);
-    
+    let bound =
+        EncryptedData::aad_with_header(EncryptionMethod::XChaCha20Poly1305, XCHACHA_NONCE_LEN, aad);
+
     let ciphertext = cipher
-        .encrypt(nonce, plaintext)
+        .encrypt(nonce, Payload { msg: plaintext, aad: &bound })
         .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
-    
+
     Ok(EncryptedData {
+        method: EncryptionMethod::XChaCha20Poly1305,
         nonce: nonce_bytes.to_vec(),
         ciphertext,
     })
 }
 
 /// Decrypt data with XChaCha20-Poly1305
-pub fn decrypt_xchacha(key: &VaultKey, encrypted: &EncryptedData) -> VaultResult<Vec<u8>> {
+pub fn decrypt_xchacha(key: &VaultKey, encrypted: &EncryptedData, aad: &[u8]) -> VaultResult<Vec<u8>> {
     let cipher = XChaCha20Poly1305::new_from_slice(key.expose())
         .map_err(|e| VaultError::DecryptionFailed(e.to_string()))?;
-    
+
     if encrypted.nonce.len() != XCHACHA_NONCE_LEN {
         return Err(VaultError::DecryptionFailed("Invalid nonce length".into()));
     }
-    
+
     let nonce = XNonce::from_slice(&encrypted.nonce);
-    
+    let bound =
+        EncryptedData::aad_with_header(EncryptionMethod::XChaCha20Poly1305, XCHACHA_NONCE_LEN, aad);
+
     cipher
-        .decrypt(nonce, encrypted.ciphertext.as_slice())
+        .decrypt(nonce, Payload { msg: encrypted.ciphertext.as_slice(), aad: &bound })
         .map_err(|_| VaultError::DecryptionFailed("Authentication failed".into()))
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// Method dispatch (self-describing tagged blobs)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Encrypt with the selected method, returning a self-describing envelope
+/// (see [`EncryptedData::to_bytes`]) that records the cipher and version. This
+/// binds no extra associated data beyond the envelope header; callers that want
+/// to pin a file id use [`encrypt_aes_gcm`] / [`encrypt_xchacha`] directly.
+pub fn encrypt(method: EncryptionMethod, key: &VaultKey, plaintext: &[u8]) -> VaultResult<Vec<u8>> {
+    let encrypted = match method {
+        EncryptionMethod::Aes256Gcm => encrypt_aes_gcm(key, plaintext, &[])?,
+        EncryptionMethod::XChaCha20Poly1305 => encrypt_xchacha(key, plaintext, &[])?,
+        EncryptionMethod::Aes256GcmSiv => encrypt_aes_gcm_siv(key, plaintext, &[])?,
+    };
+    Ok(encrypted.to_bytes())
+}
+
+/// Decrypt an envelope produced by [`encrypt`], selecting the cipher from the
+/// envelope's algorithm id so the caller need not know it in advance.
+pub fn decrypt(key: &VaultKey, data: &[u8]) -> VaultResult<Vec<u8>> {
+    let parsed = EncryptedData::parse(data)?;
+    match parsed.algorithm() {
+        EncryptionMethod::Aes256Gcm => decrypt_aes_gcm(key, &parsed, &[]),
+        EncryptionMethod::XChaCha20Poly1305 => decrypt_xchacha(key, &parsed, &[]),
+        EncryptionMethod::Aes256GcmSiv => decrypt_aes_gcm_siv(key, &parsed, &[]),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Key-committing wrapper (defence against partitioning-oracle attacks)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Domain-separation label whose MAC under the key forms the key commitment.
+const KEY_COMMITMENT_LABEL: &[u8] = b"ALFA-PV/key-commitment/v1";
+
+/// Length of the key-commitment tag prepended to a committing envelope.
+pub const KEY_COMMITMENT_LEN: usize = 32;
+
+/// Encrypt into a *key-committing* envelope: a 32-byte commitment
+/// `HMAC-SHA256(key, fixed_label)` followed by the ordinary envelope.
+///
+/// AES-256-GCM and XChaCha20-Poly1305 are not key-committing, so a single
+/// ciphertext can be crafted to decrypt under many keys — the lever behind
+/// partitioning-oracle attacks on low-entropy (PIN/biometric-derived) keys.
+/// Committing to the key closes that door: only the genuine key reproduces the
+/// stored commitment.
+pub fn encrypt_committing(
+    method: EncryptionMethod,
+    key: &VaultKey,
+    plaintext: &[u8],
+) -> VaultResult<Vec<u8>> {
+    let commitment = compute_hmac(key, KEY_COMMITMENT_LABEL);
+    let envelope = encrypt(method, key, plaintext)?;
+    let mut out = Vec::with_capacity(KEY_COMMITMENT_LEN + envelope.len());
+    out.extend_from_slice(&commitment);
+    out.extend_from_slice(&envelope);
+    Ok(out)
+}
+
+/// Decrypt a committing envelope produced by [`encrypt_committing`]. The key
+/// commitment is recomputed and compared in constant time *before* any AEAD
+/// decryption is attempted; a mismatch fails with [`VaultError::DecryptionFailed`].
+pub fn decrypt_committing(key: &VaultKey, data: &[u8]) -> VaultResult<Vec<u8>> {
+    if data.len() < KEY_COMMITMENT_LEN {
+        return Err(VaultError::DecryptionFailed("Data too short".into()));
+    }
+    let (commitment, envelope) = data.split_at(KEY_COMMITMENT_LEN);
+    let expected = compute_hmac(key, KEY_COMMITMENT_LABEL);
+    if !ct_eq(&expected, commitment) {
+        return Err(VaultError::DecryptionFailed("key commitment mismatch".into()));
+    }
+    decrypt(key, envelope)
+}
+
+/// Constant-time comparison so a commitment check leaks no timing signal.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Streaming segmented AEAD (STREAM construction, for large photo payloads)
+// ═══════════════════════════════════════════════════════════════════════════
+
+use std::io::{Read, Write};
+
+/// Plaintext chunk size for the streaming AEAD (64 KiB).
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Length of the random per-file nonce prefix.
+pub const STREAM_PREFIX_LEN: usize = 7;
+
+/// Build the 12-byte AES-GCM nonce for a stream chunk: the random per-file
+/// prefix, a 32-bit big-endian chunk counter, and a final-chunk flag byte. The
+/// flag is part of the authenticated nonce, so a truncated stream fails to
+/// authenticate its (now-final) chunk unless the flag matches — and a stream
+/// that ends on a non-final chunk is rejected as truncated.
+fn stream_nonce(prefix: &[u8; STREAM_PREFIX_LEN], counter: u32, last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..STREAM_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[STREAM_PREFIX_LEN..STREAM_PREFIX_LEN + 4].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    nonce
+}
+
+/// Fill `buf` with as many bytes as the reader yields, up to `buf.len()`,
+/// returning the number read (short only at EOF).
+fn read_fully(reader: &mut impl Read, buf: &mut [u8]) -> VaultResult<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) => return Err(VaultError::IoError(e)),
+        }
+    }
+    Ok(filled)
+}
+
+/// Encrypt a reader into a writer as independently-authenticated 64 KiB chunks.
+///
+/// On-disk layout: a 7-byte random nonce prefix, then per chunk a
+/// `[u32 len][u8 last-flag][ciphertext]` record. Each chunk is sealed under its
+/// own nonce so tampering or truncation is detected on decrypt.
+pub fn encrypt_stream<R: Read, W: Write>(
+    key: &VaultKey,
+    mut reader: R,
+    mut writer: W,
+) -> VaultResult<()> {
+    let cipher = Aes256Gcm::new_from_slice(key.expose())
+        .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+
+    let full = generate_nonce();
+    let mut prefix = [0u8; STREAM_PREFIX_LEN];
+    prefix.copy_from_slice(&full[..STREAM_PREFIX_LEN]);
+    writer
+        .write_all(&prefix)
+        .map_err(VaultError::IoError)?;
+
+    // Read one chunk ahead so we know which chunk is the last one.
+    let mut current = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut cur_len = read_fully(&mut reader, &mut current)?;
+    let mut counter: u32 = 0;
+
+    loop {
+        let mut next = vec![0u8; STREAM_CHUNK_SIZE];
+        let next_len = read_fully(&mut reader, &mut next)?;
+        let last = next_len == 0;
+
+        let nonce = stream_nonce(&prefix, counter, last);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), &current[..cur_len])
+            .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+
+        writer
+            .write_all(&(ciphertext.len() as u32).to_be_bytes())
+            .map_err(VaultError::IoError)?;
+        writer
+            .write_all(&[last as u8])
+            .map_err(VaultError::IoError)?;
+        writer
+            .write_all(&ciphertext)
+            .map_err(VaultError::IoError)?;
+
+        if last {
+            break;
+        }
+        current = next;
+        cur_len = next_len;
+        counter = counter.checked_add(1).ok_or_else(|| {
+            VaultError::EncryptionFailed("stream chunk counter overflow".into())
+        })?;
+    }
+    Ok(())
+}
+
+/// Incremental, push-based counterpart to [`encrypt_stream`] for callers that
+/// receive plaintext in arbitrary-sized pieces as they arrive (e.g. chunks
+/// handed down from Kotlin) rather than through a blocking [`Read`]. Produces
+/// the exact same wire format — a record per [`STREAM_CHUNK_SIZE`] chunk,
+/// decryptable by [`decrypt_stream`] — but buffers at most one chunk of
+/// plaintext at a time instead of reading one chunk ahead.
+pub struct StreamEncryptor {
+    cipher: Aes256Gcm,
+    prefix: [u8; STREAM_PREFIX_LEN],
+    pending: Vec<u8>,
+    counter: u32,
+}
+
+impl StreamEncryptor {
+    /// Start a new incremental stream, returning the encryptor and the random
+    /// prefix that must be written to the output before any sealed chunk.
+    pub fn new(key: &VaultKey) -> VaultResult<(Self, [u8; STREAM_PREFIX_LEN])> {
+        let cipher = Aes256Gcm::new_from_slice(key.expose())
+            .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+        let full = generate_nonce();
+        let mut prefix = [0u8; STREAM_PREFIX_LEN];
+        prefix.copy_from_slice(&full[..STREAM_PREFIX_LEN]);
+        Ok((
+            Self {
+                cipher,
+                prefix,
+                pending: Vec::with_capacity(STREAM_CHUNK_SIZE),
+                counter: 0,
+            },
+            prefix,
+        ))
+    }
+
+    /// Feed plaintext bytes in. Returns the sealed record for every full
+    /// `STREAM_CHUNK_SIZE` chunk now available — ready to write out
+    /// immediately — while any remainder under a chunk stays buffered for the
+    /// next call. Never holds more than one chunk beyond what has been pushed.
+    pub fn push(&mut self, data: &[u8]) -> VaultResult<Vec<u8>> {
+        self.pending.extend_from_slice(data);
+        let mut out = Vec::new();
+        while self.pending.len() >= STREAM_CHUNK_SIZE {
+            let chunk: Vec<u8> = self.pending.drain(..STREAM_CHUNK_SIZE).collect();
+            self.seal_chunk(&chunk, false, &mut out)?;
+        }
+        Ok(out)
+    }
+
+    /// Seal whatever remains buffered (possibly empty) as the final record.
+    pub fn finish(mut self) -> VaultResult<Vec<u8>> {
+        let mut out = Vec::new();
+        let remaining = std::mem::take(&mut self.pending);
+        self.seal_chunk(&remaining, true, &mut out)?;
+        Ok(out)
+    }
+
+    fn seal_chunk(&mut self, chunk: &[u8], last: bool, out: &mut Vec<u8>) -> VaultResult<()> {
+        let nonce = stream_nonce(&self.prefix, self.counter, last);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), chunk)
+            .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+        out.extend_from_slice(&(ciphertext.len() as u32).to_be_bytes());
+        out.push(last as u8);
+        out.extend_from_slice(&ciphertext);
+        self.counter = self.counter.checked_add(1).ok_or_else(|| {
+            VaultError::EncryptionFailed("stream chunk counter overflow".into())
+        })?;
+        Ok(())
+    }
+}
+
+/// Decrypt a stream produced by [`encrypt_stream`], rejecting truncation: the
+/// stream must end exactly on the chunk whose last-flag is set.
+pub fn decrypt_stream<R: Read, W: Write>(
+    key: &VaultKey,
+    mut reader: R,
+    mut writer: W,
+) -> VaultResult<()> {
+    let cipher = Aes256Gcm::new_from_slice(key.expose())
+        .map_err(|e| VaultError::DecryptionFailed(e.to_string()))?;
+
+    let mut prefix = [0u8; STREAM_PREFIX_LEN];
+    read_exact_or_err(&mut reader, &mut prefix)?;
+
+    let mut counter: u32 = 0;
+    let mut saw_last = false;
+    loop {
+        let mut len_buf = [0u8; 4];
+        match reader.read(&mut len_buf[..1]) {
+            Ok(0) => break, // clean EOF between records
+            Ok(_) => read_exact_or_err(&mut reader, &mut len_buf[1..])?,
+            Err(e) => return Err(VaultError::IoError(e)),
+        }
+        if saw_last {
+            return Err(VaultError::DecryptionFailed(
+                "trailing data after final stream chunk".into(),
+            ));
+        }
+        let len = u32::from_be_bytes(len_buf) as usize;
+
+        let mut flag = [0u8; 1];
+        read_exact_or_err(&mut reader, &mut flag)?;
+        let last = flag[0] != 0;
+
+        let mut ciphertext = vec![0u8; len];
+        read_exact_or_err(&mut reader, &mut ciphertext)?;
+
+        let nonce = stream_nonce(&prefix, counter, last);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| VaultError::DecryptionFailed("Authentication failed".into()))?;
+        writer
+            .write_all(&plaintext)
+            .map_err(VaultError::IoError)?;
+
+        saw_last = last;
+        counter = counter.checked_add(1).ok_or_else(|| {
+            VaultError::DecryptionFailed("stream chunk counter overflow".into())
+        })?;
+    }
+
+    if !saw_last {
+        return Err(VaultError::DecryptionFailed(
+            "stream truncated before final chunk".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Decrypt a single stream chunk in isolation, enabling range reads without
+/// buffering the whole payload. The caller supplies the file's nonce `prefix`,
+/// the chunk's `counter`, whether it is the final chunk, and its ciphertext.
+pub fn decrypt_stream_chunk(
+    key: &VaultKey,
+    prefix: &[u8; STREAM_PREFIX_LEN],
+    counter: u32,
+    last: bool,
+    ciphertext: &[u8],
+) -> VaultResult<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key.expose())
+        .map_err(|e| VaultError::DecryptionFailed(e.to_string()))?;
+    let nonce = stream_nonce(prefix, counter, last);
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext)
+        .map_err(|_| VaultError::DecryptionFailed("Authentication failed".into()))
+}
+
+/// Read exactly `buf.len()` bytes or fail with a decryption error (a short read
+/// here means the stream was truncated mid-record).
+fn read_exact_or_err(reader: &mut impl Read, buf: &mut [u8]) -> VaultResult<()> {
+    reader
+        .read_exact(buf)
+        .map_err(|_| VaultError::DecryptionFailed("stream truncated mid-record".into()))
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // HMAC for integrity verification
 // ═══════════════════════════════════════════════════════════════════════════
@@ -166,23 +672,35 @@ mod tests {
         let key = VaultKey::generate();
         let plaintext = b"ALFA Photos Vault - Top Secret Photo Data";
         
-        let encrypted = encrypt_aes_gcm(&key, plaintext).unwrap();
-        let decrypted = decrypt_aes_gcm(&key, &encrypted).unwrap();
-        
+        let encrypted = encrypt_aes_gcm(&key, plaintext, &[]).unwrap();
+        let decrypted = decrypt_aes_gcm(&key, &encrypted, &[]).unwrap();
+
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
-    
+
     #[test]
     fn test_xchacha_roundtrip() {
         let key = VaultKey::generate();
         let plaintext = b"ALFA Index Database Content";
-        
-        let encrypted = encrypt_xchacha(&key, plaintext).unwrap();
-        let decrypted = decrypt_xchacha(&key, &encrypted).unwrap();
-        
+
+        let encrypted = encrypt_xchacha(&key, plaintext, &[]).unwrap();
+        let decrypted = decrypt_xchacha(&key, &encrypted, &[]).unwrap();
+
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
-    
+
+    #[test]
+    fn test_aes_gcm_siv_roundtrip() {
+        let key = VaultKey::generate();
+        let plaintext = b"ALFA Index Record - misuse resistant";
+
+        let encrypted = encrypt_aes_gcm_siv(&key, plaintext, &[]).unwrap();
+        assert_eq!(encrypted.nonce.len(), NONCE_LEN);
+        let decrypted = decrypt_aes_gcm_siv(&key, &encrypted, &[]).unwrap();
+
+        assert_eq!(plaintext.as_slice(), decrypted.as_slice());
+    }
+
     #[test]
     fn test_hmac() {
         let key = VaultKey::generate();
@@ -196,14 +714,113 @@ mod tests {
         assert!(!verify_hmac(&key, tampered, &mac));
     }
     
+    #[test]
+    fn test_envelope_dispatch_roundtrip() {
+        let key = VaultKey::generate();
+        let plaintext = b"self-describing blob stays decryptable";
+
+        for method in [
+            EncryptionMethod::Aes256Gcm,
+            EncryptionMethod::XChaCha20Poly1305,
+            EncryptionMethod::Aes256GcmSiv,
+        ] {
+            let blob = encrypt(method, &key, plaintext).unwrap();
+            assert_eq!(EncryptedData::parse(&blob).unwrap().algorithm(), method);
+            let decrypted = decrypt(&key, &blob).unwrap();
+            assert_eq!(plaintext.as_slice(), decrypted.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_aad_binds_file_id() {
+        let key = VaultKey::generate();
+        let plaintext = b"bound to its file id";
+
+        let encrypted = encrypt_aes_gcm(&key, plaintext, b"photo-42").unwrap();
+        let blob = encrypted.to_bytes();
+        let parsed = EncryptedData::parse(&blob).unwrap();
+
+        assert_eq!(decrypt_aes_gcm(&key, &parsed, b"photo-42").unwrap(), plaintext);
+        // Replaying the same ciphertext under a different file id must fail.
+        assert!(decrypt_aes_gcm(&key, &parsed, b"photo-99").is_err());
+    }
+
+    #[test]
+    fn test_committing_rejects_wrong_key() {
+        let key = VaultKey::generate();
+        let other = VaultKey::generate();
+        let plaintext = b"committed to exactly one key";
+
+        let blob = encrypt_committing(EncryptionMethod::Aes256Gcm, &key, plaintext).unwrap();
+        assert_eq!(decrypt_committing(&key, &blob).unwrap(), plaintext);
+        assert!(matches!(
+            decrypt_committing(&other, &blob),
+            Err(VaultError::DecryptionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_envelope_rejected() {
+        let key = VaultKey::generate();
+        assert!(decrypt(&key, &[0xFF, 1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let key = VaultKey::generate();
+        // Spans several chunks plus a partial final chunk.
+        let plaintext: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+
+        let mut ciphertext = Vec::new();
+        encrypt_stream(&key, plaintext.as_slice(), &mut ciphertext).unwrap();
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(&key, ciphertext.as_slice(), &mut decrypted).unwrap();
+
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_stream_truncation_detected() {
+        let key = VaultKey::generate();
+        let plaintext = vec![0x5au8; STREAM_CHUNK_SIZE * 2 + 10];
+
+        let mut ciphertext = Vec::new();
+        encrypt_stream(&key, plaintext.as_slice(), &mut ciphertext).unwrap();
+
+        // Cutting off the final chunk must be rejected, not silently truncated.
+        let truncated = &ciphertext[..ciphertext.len() / 2];
+        let mut out = Vec::new();
+        assert!(decrypt_stream(&key, truncated, &mut out).is_err());
+    }
+
+    #[test]
+    fn test_stream_encryptor_matches_decrypt_stream() {
+        let key = VaultKey::generate();
+        // Fed in odd-sized pieces that don't line up with STREAM_CHUNK_SIZE,
+        // as chunks arriving from Kotlin would be.
+        let plaintext: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+
+        let (mut encryptor, prefix) = StreamEncryptor::new(&key).unwrap();
+        let mut ciphertext = prefix.to_vec();
+        for piece in plaintext.chunks(50_000) {
+            ciphertext.extend(encryptor.push(piece).unwrap());
+        }
+        ciphertext.extend(encryptor.finish().unwrap());
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(&key, ciphertext.as_slice(), &mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
     #[test]
     fn test_wrong_key_fails() {
         let key1 = VaultKey::generate();
         let key2 = VaultKey::generate();
         let plaintext = b"Secret data";
         
-        let encrypted = encrypt_aes_gcm(&key1, plaintext).unwrap();
-        let result = decrypt_aes_gcm(&key2, &encrypted);
+        let encrypted = encrypt_aes_gcm(&key1, plaintext, &[]).unwrap();
+        let result = decrypt_aes_gcm(&key2, &encrypted, &[]);
         
         assert!(result.is_err());
     }
@@ -0,0 +1,99 @@
+//! ALFA Photos Vault - Hierarchical Key Derivation Paths
+//!
+//! BIP32-style derivation of keys along arbitrary slash-separated paths such
+//! as `m/albums/2024/photo_001`, enabling per-album, per-device and
+//! per-sharing-session subtrees without hardcoding new context constants.
+
+use crate::error::{VaultError, VaultResult};
+
+/// HKDF context tag for path derivation.
+pub const PATH_CONTEXT: &[u8] = b"ALFA:PATH:v1";
+
+/// Marker byte mixed into the `info` for hardened segments.
+const HARDENED_MARKER: u8 = 0x01;
+
+/// Marker byte mixed into the `info` for non-hardened segments.
+const NORMAL_MARKER: u8 = 0x00;
+
+/// A single segment of a derivation path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Segment label (without any hardened suffix).
+    pub label: String,
+    /// Whether the segment is hardened.
+    pub hardened: bool,
+}
+
+/// A parsed BIP32-style derivation path.
+///
+/// Accepts an optional leading `m` master marker followed by slash-separated
+/// segments. A trailing `'` or `h` marks a segment as hardened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath {
+    segments: Vec<Segment>,
+}
+
+impl DerivationPath {
+    /// Parse a path like `m/albums/2024/photo_001'`.
+    pub fn parse(path: &str) -> VaultResult<Self> {
+        let mut segments = Vec::new();
+        for (i, raw) in path.split('/').enumerate() {
+            if i == 0 && (raw == "m" || raw.is_empty()) {
+                continue;
+            }
+            if raw.is_empty() {
+                return Err(VaultError::KeyDerivationFailed(
+                    "empty path segment".to_string(),
+                ));
+            }
+            let (label, hardened) = match raw.strip_suffix('\'').or_else(|| raw.strip_suffix('h')) {
+                Some(stripped) => (stripped.to_string(), true),
+                None => (raw.to_string(), false),
+            };
+            if label.is_empty() {
+                return Err(VaultError::KeyDerivationFailed(
+                    "empty path segment".to_string(),
+                ));
+            }
+            segments.push(Segment { label, hardened });
+        }
+        Ok(Self { segments })
+    }
+
+    /// Iterate over the path's segments, root first.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Build the HKDF `info` bytes for a segment (label plus hardened marker).
+    pub(crate) fn segment_info(segment: &Segment) -> Vec<u8> {
+        let mut info = Vec::with_capacity(PATH_CONTEXT.len() + segment.label.len() + 1);
+        info.extend_from_slice(PATH_CONTEXT);
+        info.push(if segment.hardened {
+            HARDENED_MARKER
+        } else {
+            NORMAL_MARKER
+        });
+        info.extend_from_slice(segment.label.as_bytes());
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path() {
+        let path = DerivationPath::parse("m/albums/2024/photo_001'").unwrap();
+        assert_eq!(path.segments().len(), 3);
+        assert_eq!(path.segments()[0].label, "albums");
+        assert!(!path.segments()[0].hardened);
+        assert!(path.segments()[2].hardened);
+    }
+
+    #[test]
+    fn test_empty_segment_rejected() {
+        assert!(DerivationPath::parse("m//x").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! ALFA Photos Vault - HPKE Sealing
+//!
+//! Hybrid public-key encryption, modelled on RFC 9180 base mode, so a vault
+//! item can be sealed to a recipient's public key and opened only with their
+//! private key. An ephemeral X25519 exchange yields the shared secret; HKDF-SHA256
+//! (salted with a suite label, `enc` and the recipient key, expanded with the
+//! caller's `info`) turns it into an AES-256-GCM key, and the AAD is
+//! authenticated by AES-GCM. The key schedule and the random per-item nonce are
+//! not those of RFC 9180, so sealed items are not interoperable with other HPKE
+//! implementations.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroize;
+
+use super::keys::{generate_nonce, NONCE_LEN};
+use crate::error::{VaultError, VaultResult};
+
+/// HPKE suite label mixed into the key schedule.
+const HPKE_CONTEXT: &[u8] = b"ALFA:HPKE:X25519-SHA256-AES256GCM:v1";
+
+/// A recipient's long-lived X25519 key pair.
+pub struct RecipientKey {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl RecipientKey {
+    /// Generate a fresh recipient key pair.
+    pub fn generate() -> Self {
+        use rand::RngCore;
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let secret = StaticSecret::from(bytes);
+        bytes.zeroize();
+        let public = PublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    /// The recipient's public key bytes (share these with senders).
+    pub fn public_bytes(&self) -> [u8; 32] {
+        self.public.to_bytes()
+    }
+}
+
+/// A sealed vault item: ephemeral public key plus the encrypted payload.
+pub struct SealedItem {
+    /// Encapsulated ephemeral public key (`enc`).
+    pub enc: [u8; 32],
+    /// AES-GCM nonce.
+    pub nonce: [u8; NONCE_LEN],
+    /// Ciphertext with authentication tag.
+    pub ciphertext: Vec<u8>,
+}
+
+/// Derive the AEAD key from the DH shared secret and the KEM context.
+fn schedule_key(shared: &[u8], enc: &[u8; 32], recipient_pk: &[u8; 32], info: &[u8]) -> VaultResult<[u8; 32]> {
+    // Salt binds the encapsulation transcript; info carries the caller context.
+    let mut salt = Vec::with_capacity(HPKE_CONTEXT.len() + 64);
+    salt.extend_from_slice(HPKE_CONTEXT);
+    salt.extend_from_slice(enc);
+    salt.extend_from_slice(recipient_pk);
+
+    let hk = Hkdf::<Sha256>::new(Some(&salt), shared);
+    let mut key = [0u8; 32];
+    hk.expand(info, &mut key)
+        .map_err(|e| VaultError::KeyDerivationFailed(e.to_string()))?;
+    Ok(key)
+}
+
+/// Seal `plaintext` to `recipient_pub`, binding `info` and `aad`.
+pub fn seal(
+    recipient_pub: &[u8; 32],
+    info: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> VaultResult<SealedItem> {
+    use rand::RngCore;
+    let mut eph_bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut eph_bytes);
+    let eph_secret = StaticSecret::from(eph_bytes);
+    eph_bytes.zeroize();
+
+    let enc = PublicKey::from(&eph_secret).to_bytes();
+    let recipient = PublicKey::from(*recipient_pub);
+    let mut shared = eph_secret.diffie_hellman(&recipient).to_bytes();
+
+    let mut key = schedule_key(&shared, &enc, recipient_pub, info)?;
+    shared.zeroize();
+
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+    key.zeroize();
+
+    let nonce_bytes = generate_nonce();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), Payload { msg: plaintext, aad })
+        .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+
+    Ok(SealedItem {
+        enc,
+        nonce: nonce_bytes,
+        ciphertext,
+    })
+}
+
+/// Open a [`SealedItem`] with the recipient's private key.
+pub fn open(
+    recipient: &RecipientKey,
+    sealed: &SealedItem,
+    info: &[u8],
+    aad: &[u8],
+) -> VaultResult<Vec<u8>> {
+    let eph_pub = PublicKey::from(sealed.enc);
+    let mut shared = recipient.secret.diffie_hellman(&eph_pub).to_bytes();
+
+    let recipient_pk = recipient.public_bytes();
+    let mut key = schedule_key(&shared, &sealed.enc, &recipient_pk, info)?;
+    shared.zeroize();
+
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| VaultError::DecryptionFailed(e.to_string()))?;
+    key.zeroize();
+
+    cipher
+        .decrypt(
+            Nonce::from_slice(&sealed.nonce),
+            Payload {
+                msg: sealed.ciphertext.as_slice(),
+                aad,
+            },
+        )
+        .map_err(|_| VaultError::DecryptionFailed("HPKE authentication failed".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let recipient = RecipientKey::generate();
+        let pk = recipient.public_bytes();
+
+        let sealed = seal(&pk, b"album-share", b"photo_001", b"secret photo").unwrap();
+        let opened = open(&recipient, &sealed, b"album-share", b"photo_001").unwrap();
+        assert_eq!(opened, b"secret photo");
+    }
+
+    #[test]
+    fn test_wrong_recipient_fails() {
+        let recipient = RecipientKey::generate();
+        let other = RecipientKey::generate();
+        let sealed = seal(&recipient.public_bytes(), b"i", b"a", b"data").unwrap();
+        assert!(open(&other, &sealed, b"i", b"a").is_err());
+    }
+
+    #[test]
+    fn test_aad_mismatch_fails() {
+        let recipient = RecipientKey::generate();
+        let sealed = seal(&recipient.public_bytes(), b"i", b"aad1", b"data").unwrap();
+        assert!(open(&recipient, &sealed, b"i", b"aad2").is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! ALFA Photos Vault - Runtime Keyring
+//!
+//! A concurrent key session manager so a long-running process can hold only
+//! the keys it is actively using. Keys are derived from the cached master on
+//! `mount` and zeroized on `unmount`, giving applications mount/unmount
+//! lifecycle, a default key, and per-key automount without leaking material
+//! for keys that aren't in use.
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+
+use super::keys::{derive_key, VaultKey};
+use crate::error::{VaultError, VaultResult};
+
+/// Identifier for a keyring entry.
+pub type KeyId = String;
+
+/// HKDF context for keyring-derived keys.
+const KEYRING_CONTEXT: &[u8] = b"ALFA:KEYRING:v1";
+
+/// A registered key and its current mount state.
+struct KeyEntry {
+    /// The derivation context (HKDF `info`) for this key.
+    context: Vec<u8>,
+    /// Whether the key should be mounted automatically on startup.
+    automount: bool,
+    /// The live key material, present only while mounted.
+    key: Option<VaultKey>,
+}
+
+/// Per-key status snapshot.
+#[derive(Debug, Clone)]
+pub struct KeyStats {
+    /// Key identifier.
+    pub id: KeyId,
+    /// Whether the key is currently mounted.
+    pub mounted: bool,
+    /// Whether the key is automounted on startup.
+    pub automount: bool,
+    /// The key's derivation context.
+    pub context: Vec<u8>,
+}
+
+/// Concurrent keyring with mount/unmount lifecycle and a default key.
+pub struct Keyring {
+    master: RwLock<Option<VaultKey>>,
+    entries: DashMap<KeyId, KeyEntry>,
+    default: RwLock<Option<KeyId>>,
+}
+
+impl Keyring {
+    /// Create a keyring backed by the given master key.
+    pub fn new(master: VaultKey) -> Self {
+        Self {
+            master: RwLock::new(Some(master)),
+            entries: DashMap::new(),
+            default: RwLock::new(None),
+        }
+    }
+
+    /// Register a key without mounting it.
+    pub fn register(&self, id: impl Into<KeyId>, context: impl Into<Vec<u8>>, automount: bool) {
+        let id = id.into();
+        self.entries.insert(
+            id,
+            KeyEntry {
+                context: context.into(),
+                automount,
+                key: None,
+            },
+        );
+    }
+
+    /// Derive and mount a registered key.
+    pub fn mount(&self, id: &str) -> VaultResult<()> {
+        let master_guard = self.master.read();
+        let master = master_guard
+            .as_ref()
+            .ok_or(VaultError::VaultLocked)?;
+        let mut entry = self
+            .entries
+            .get_mut(id)
+            .ok_or_else(|| VaultError::FileNotFound(id.to_string()))?;
+        let derived = derive_key(master.expose(), id.as_bytes(), &entry.context)?;
+        // Mixing the keyring context keeps these distinct from other subtrees.
+        let derived = derive_key(derived.expose(), KEYRING_CONTEXT, &entry.context)?;
+        entry.key = Some(derived);
+        Ok(())
+    }
+
+    /// Unmount a key, dropping (and zeroizing) its material.
+    pub fn unmount(&self, id: &str) -> VaultResult<()> {
+        let mut entry = self
+            .entries
+            .get_mut(id)
+            .ok_or_else(|| VaultError::FileNotFound(id.to_string()))?;
+        entry.key = None;
+        Ok(())
+    }
+
+    /// Mount every entry flagged for automount. Returns the number mounted.
+    pub fn automount_all(&self) -> VaultResult<usize> {
+        let ids: Vec<KeyId> = self
+            .entries
+            .iter()
+            .filter(|e| e.automount)
+            .map(|e| e.key().clone())
+            .collect();
+        let mut count = 0;
+        for id in ids {
+            self.mount(&id)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Clone the currently mounted key for `id`, if mounted.
+    pub fn get(&self, id: &str) -> Option<VaultKey> {
+        self.entries.get(id).and_then(|e| e.key.clone())
+    }
+
+    /// Set the default key id used when no explicit id is given.
+    pub fn set_default(&self, id: impl Into<KeyId>) {
+        *self.default.write() = Some(id.into());
+    }
+
+    /// Clone the default key, mounting-aware.
+    pub fn default_key(&self) -> Option<VaultKey> {
+        let id = self.default.read().clone()?;
+        self.get(&id)
+    }
+
+    /// List per-key stats, mounted keys first.
+    pub fn stats(&self) -> Vec<KeyStats> {
+        let mut stats: Vec<KeyStats> = self
+            .entries
+            .iter()
+            .map(|e| KeyStats {
+                id: e.key().clone(),
+                mounted: e.value().key.is_some(),
+                automount: e.value().automount,
+                context: e.value().context.clone(),
+            })
+            .collect();
+        // Mounted first, then by id for stable ordering.
+        stats.sort_by(|a, b| b.mounted.cmp(&a.mounted).then(a.id.cmp(&b.id)));
+        stats
+    }
+
+    /// Unmount everything and wipe the cached master.
+    pub fn clear(&self) {
+        self.entries.clear();
+        *self.default.write() = None;
+        *self.master.write() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn master() -> VaultKey {
+        VaultKey::new([0x24u8; 32])
+    }
+
+    #[test]
+    fn test_mount_unmount() {
+        let ring = Keyring::new(master());
+        ring.register("album:2024", b"ctx-2024".to_vec(), false);
+
+        assert!(ring.get("album:2024").is_none());
+        ring.mount("album:2024").unwrap();
+        let k = ring.get("album:2024").unwrap();
+
+        // Deterministic across mounts.
+        ring.unmount("album:2024").unwrap();
+        ring.mount("album:2024").unwrap();
+        assert_eq!(ring.get("album:2024").unwrap().expose(), k.expose());
+
+        ring.unmount("album:2024").unwrap();
+        assert!(ring.get("album:2024").is_none());
+    }
+
+    #[test]
+    fn test_automount_and_default() {
+        let ring = Keyring::new(master());
+        ring.register("a", b"a".to_vec(), true);
+        ring.register("b", b"b".to_vec(), false);
+
+        assert_eq!(ring.automount_all().unwrap(), 1);
+        assert!(ring.get("a").is_some());
+        assert!(ring.get("b").is_none());
+
+        ring.set_default("a");
+        assert!(ring.default_key().is_some());
+    }
+
+    #[test]
+    fn test_stats_mounted_first() {
+        let ring = Keyring::new(master());
+        ring.register("z", b"z".to_vec(), false);
+        ring.register("a", b"a".to_vec(), false);
+        ring.mount("z").unwrap();
+
+        let stats = ring.stats();
+        assert_eq!(stats[0].id, "z");
+        assert!(stats[0].mounted);
+    }
+
+    #[test]
+    fn test_clear_locks() {
+        let ring = Keyring::new(master());
+        ring.register("a", b"a".to_vec(), false);
+        ring.clear();
+        assert!(matches!(ring.mount("a"), Err(VaultError::VaultLocked)));
+    }
+}
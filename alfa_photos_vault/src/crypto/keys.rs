@@ -2,7 +2,10 @@
 //!
 //! Derives specialized keys from ALFA_KEYVAULT master seed.
 
+use std::collections::{HashMap, HashSet};
+
 use hkdf::Hkdf;
+use parking_lot::Mutex;
 use sha2::Sha256;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 use secrecy::{Secret, ExposeSecret};
@@ -76,7 +79,12 @@ pub struct KeyManager {
     /// Master key from ALFA_KEYVAULT
     #[zeroize(skip)]
     master: VaultKey,
-    
+
+    /// The original 32-byte master seed, retained so it can be exported as a
+    /// BIP39 recovery phrase (see [`export_mnemonic`](Self::export_mnemonic)).
+    #[zeroize(skip)]
+    master_seed: VaultKey,
+
     /// Derived key for photos
     #[zeroize(skip)]
     photos_key: VaultKey,
@@ -92,6 +100,10 @@ pub struct KeyManager {
     /// Derived key for HMAC
     #[zeroize(skip)]
     hmac_key: VaultKey,
+
+    /// Observed nonce counters per file id, guarding against GCM nonce reuse.
+    #[zeroize(skip)]
+    nonce_tracker: Mutex<HashMap<String, HashSet<u64>>>,
 }
 
 impl KeyManager {
@@ -106,7 +118,12 @@ impl KeyManager {
         
         // Derive master key from seed
         let master = derive_key(seed, b"", contexts::PHOTOS)?;
-        
+
+        // Retain the canonical 32-byte seed for mnemonic export.
+        let mut seed_bytes = [0u8; KEY_LEN];
+        seed_bytes.copy_from_slice(&seed[..KEY_LEN]);
+        let master_seed = VaultKey::new(seed_bytes);
+
         // Derive specialized keys
         let photos_key = derive_key(master.expose(), b"photos", contexts::PHOTOS)?;
         let thumbs_key = derive_key(master.expose(), b"thumbs", contexts::THUMBS)?;
@@ -115,10 +132,12 @@ impl KeyManager {
         
         Ok(Self {
             master,
+            master_seed,
             photos_key,
             thumbs_key,
             index_key,
             hmac_key,
+            nonce_tracker: Mutex::new(HashMap::new()),
         })
     }
     
@@ -141,6 +160,16 @@ impl KeyManager {
     pub fn hmac_key(&self) -> &VaultKey {
         &self.hmac_key
     }
+
+    /// The canonical 32-byte master seed, used for BIP39 mnemonic export.
+    pub(crate) fn master_seed(&self) -> &VaultKey {
+        &self.master_seed
+    }
+
+    /// The derived master key, used as the root for epoch-key rotation.
+    pub(crate) fn master(&self) -> &VaultKey {
+        &self.master
+    }
     
     /// Derive a unique key for a specific file
     pub fn derive_file_key(&self, file_id: &str) -> VaultResult<VaultKey> {
@@ -151,6 +180,82 @@ impl KeyManager {
     pub fn derive_thumb_key(&self, file_id: &str) -> VaultResult<VaultKey> {
         derive_key(self.thumbs_key.expose(), file_id.as_bytes(), contexts::FILE_KEY)
     }
+
+    /// Derive a deterministic AES-GCM nonce for a `(file, counter)` pair.
+    ///
+    /// HKDF-expands from the file key using the file id and a monotonically
+    /// increasing counter as `info`, guaranteeing a unique nonce per chunk
+    /// without random state. Reusing a counter already observed for the same
+    /// file id returns [`VaultError::NonceReused`]. Random nonces remain
+    /// available via [`generate_nonce`] for the stateless case.
+    pub fn derive_nonce(
+        &self,
+        file_key: &VaultKey,
+        file_id: &str,
+        counter: u64,
+    ) -> VaultResult<[u8; NONCE_LEN]> {
+        {
+            let mut tracker = self.nonce_tracker.lock();
+            let seen = tracker.entry(file_id.to_string()).or_default();
+            if !seen.insert(counter) {
+                return Err(VaultError::NonceReused {
+                    file_id: file_id.to_string(),
+                    counter,
+                });
+            }
+        }
+
+        let mut info = Vec::with_capacity(file_id.len() + 8);
+        info.extend_from_slice(file_id.as_bytes());
+        info.extend_from_slice(&counter.to_be_bytes());
+
+        let hk = Hkdf::<Sha256>::new(None, file_key.expose());
+        let mut nonce = [0u8; NONCE_LEN];
+        hk.expand(&info, &mut nonce)
+            .map_err(|e| VaultError::KeyDerivationFailed(e.to_string()))?;
+        Ok(nonce)
+    }
+
+    /// Derive a synthetic-IV-style 24-byte XChaCha20 nonce.
+    ///
+    /// Mixes the plaintext and AAD into the derived nonce so that identical
+    /// inputs under the same key reuse the same nonce safely (SIV property),
+    /// requiring no reuse tracking.
+    pub fn derive_siv_nonce(
+        &self,
+        file_key: &VaultKey,
+        file_id: &str,
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> VaultResult<[u8; XCHACHA_NONCE_LEN]> {
+        let mut info = Vec::with_capacity(file_id.len() + aad.len() + plaintext.len() + 2);
+        info.extend_from_slice(file_id.as_bytes());
+        info.push(0x00);
+        info.extend_from_slice(aad);
+        info.push(0x00);
+        info.extend_from_slice(plaintext);
+
+        let hk = Hkdf::<Sha256>::new(None, file_key.expose());
+        let mut nonce = [0u8; XCHACHA_NONCE_LEN];
+        hk.expand(&info, &mut nonce)
+            .map_err(|e| VaultError::KeyDerivationFailed(e.to_string()))?;
+        Ok(nonce)
+    }
+
+    /// Derive a key along a BIP32-style path such as `m/albums/2024/photo_001`.
+    ///
+    /// Each segment is folded through [`derive_key`] with the running parent
+    /// key as IKM and the segment label (plus a hardened-marker byte) as the
+    /// HKDF `info`, so hardened and non-hardened segments yield distinct keys
+    /// and exposing a child never reveals its siblings.
+    pub fn derive_path(&self, path: &crate::crypto::derivation::DerivationPath) -> VaultResult<VaultKey> {
+        let mut current = self.master.clone();
+        for segment in path.segments() {
+            let info = crate::crypto::derivation::DerivationPath::segment_info(segment);
+            current = derive_key(current.expose(), segment.label.as_bytes(), &info)?;
+        }
+        Ok(current)
+    }
 }
 
 /// Derive a key using HKDF-SHA256
@@ -202,4 +307,62 @@ mod tests {
         let fk3 = km.derive_file_key("photo_002").unwrap();
         assert_ne!(fk1.expose(), fk3.expose());
     }
+
+    #[test]
+    fn test_derive_path() {
+        use crate::crypto::derivation::DerivationPath;
+
+        let seed = [0x42u8; 64];
+        let km = KeyManager::from_master_seed(&seed).unwrap();
+
+        // Deterministic for the same path.
+        let p = DerivationPath::parse("m/albums/2024/photo_001").unwrap();
+        let k1 = km.derive_path(&p).unwrap();
+        let k2 = km.derive_path(&p).unwrap();
+        assert_eq!(k1.expose(), k2.expose());
+
+        // Different paths diverge.
+        let other = DerivationPath::parse("m/albums/2024/photo_002").unwrap();
+        assert_ne!(k1.expose(), km.derive_path(&other).unwrap().expose());
+
+        // Hardened vs non-hardened differ at the same label.
+        let soft = DerivationPath::parse("m/albums/device").unwrap();
+        let hard = DerivationPath::parse("m/albums/device'").unwrap();
+        assert_ne!(
+            km.derive_path(&soft).unwrap().expose(),
+            km.derive_path(&hard).unwrap().expose()
+        );
+    }
+
+    #[test]
+    fn test_derive_nonce_unique_and_tracked() {
+        let km = KeyManager::from_master_seed(&[0x42u8; 64]).unwrap();
+        let fk = km.derive_file_key("photo_001").unwrap();
+
+        let n0 = km.derive_nonce(&fk, "photo_001", 0).unwrap();
+        let n1 = km.derive_nonce(&fk, "photo_001", 1).unwrap();
+        assert_ne!(n0, n1);
+
+        // Reusing counter 0 for the same file is rejected.
+        assert!(matches!(
+            km.derive_nonce(&fk, "photo_001", 0),
+            Err(VaultError::NonceReused { .. })
+        ));
+
+        // Same counter for a different file is fine.
+        assert!(km.derive_nonce(&fk, "photo_002", 0).is_ok());
+    }
+
+    #[test]
+    fn test_siv_nonce_deterministic() {
+        let km = KeyManager::from_master_seed(&[0x42u8; 64]).unwrap();
+        let fk = km.derive_file_key("photo_001").unwrap();
+
+        let a = km.derive_siv_nonce(&fk, "photo_001", b"data", b"aad").unwrap();
+        let b = km.derive_siv_nonce(&fk, "photo_001", b"data", b"aad").unwrap();
+        assert_eq!(a, b);
+
+        let c = km.derive_siv_nonce(&fk, "photo_001", b"other", b"aad").unwrap();
+        assert_ne!(a, c);
+    }
 }
@@ -0,0 +1,221 @@
+//! ALFA Photos Vault - Passphrase-Protected Keystore
+//!
+//! Encrypted-at-rest container for the master seed, derived from a user
+//! passphrase, so the vault can boot from a file + password instead of an
+//! in-memory seed. Mirrors the established encrypted-keyfile pattern
+//! (KDF + MAC-checked ciphertext) while defaulting to Argon2id.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use super::aead::compute_hmac;
+use super::keys::{generate_nonce, KeyManager, VaultKey, NONCE_LEN};
+use crate::error::{VaultError, VaultResult};
+
+/// Current keystore format version.
+pub const KEYSTORE_VERSION: u8 = 1;
+
+/// KDF parameters persisted alongside the ciphertext.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// KDF algorithm identifier (currently always `argon2id`).
+    pub algorithm: String,
+    /// Per-keystore random salt.
+    pub salt: Vec<u8>,
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Iteration (time) cost.
+    pub iterations: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            algorithm: "argon2id".to_string(),
+            salt: Vec::new(),
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 2,
+        }
+    }
+}
+
+/// Encrypted master-seed container.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyStore {
+    /// Format version.
+    pub version: u8,
+    /// KDF choice and parameters.
+    pub kdf: KdfParams,
+    /// AEAD cipher identifier.
+    pub cipher: String,
+    /// AES-GCM nonce.
+    pub nonce: Vec<u8>,
+    /// Encrypted seed with the GCM tag.
+    pub ciphertext: Vec<u8>,
+    /// HMAC-SHA256 over the ciphertext.
+    pub mac: Vec<u8>,
+}
+
+/// Stretch the passphrase with Argon2id, then split into encryption and MAC
+/// subkeys via HKDF so AES-256-GCM and HMAC-SHA256 each get a full 32 bytes.
+fn derive_subkeys(passphrase: &str, params: &KdfParams) -> VaultResult<(VaultKey, VaultKey)> {
+    let argon_params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(32),
+    )
+    .map_err(|e| VaultError::KeyDerivationFailed(format!("invalid Argon2 params: {e}")))?;
+
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params);
+    let mut stretched = [0u8; 32];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), &params.salt, &mut stretched)
+        .map_err(|e| VaultError::KeyDerivationFailed(format!("Argon2 failed: {e}")))?;
+
+    let hk = Hkdf::<Sha256>::new(None, &stretched);
+    stretched.zeroize();
+    let mut okm = [0u8; 64];
+    hk.expand(b"ALFA:KEYSTORE:v1", &mut okm)
+        .map_err(|e| VaultError::KeyDerivationFailed(e.to_string()))?;
+
+    let mut enc = [0u8; 32];
+    let mut mac = [0u8; 32];
+    enc.copy_from_slice(&okm[..32]);
+    mac.copy_from_slice(&okm[32..]);
+    okm.zeroize();
+
+    Ok((VaultKey::new(enc), VaultKey::new(mac)))
+}
+
+impl KeyStore {
+    /// Encrypt `seed` under `passphrase`, producing a loadable keystore.
+    pub fn seal(seed: &[u8], passphrase: &str, memory_kib: u32, iterations: u32) -> VaultResult<Self> {
+        use rand::RngCore;
+        let mut salt = vec![0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+
+        let params = KdfParams {
+            salt,
+            memory_kib,
+            iterations,
+            ..Default::default()
+        };
+        let (enc_key, mac_key) = derive_subkeys(passphrase, &params)?;
+
+        let cipher = Aes256Gcm::new_from_slice(enc_key.expose())
+            .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+        let nonce_bytes = generate_nonce();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), seed)
+            .map_err(|e| VaultError::EncryptionFailed(e.to_string()))?;
+
+        let mac = compute_hmac(&mac_key, &ciphertext).to_vec();
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            kdf: params,
+            cipher: "AES-256-GCM".to_string(),
+            nonce: nonce_bytes.to_vec(),
+            ciphertext,
+            mac,
+        })
+    }
+
+    /// Re-derive subkeys, verify the MAC, and decrypt the stored seed.
+    pub fn open(&self, passphrase: &str) -> VaultResult<Vec<u8>> {
+        if self.nonce.len() != NONCE_LEN {
+            return Err(VaultError::DecryptionFailed("invalid nonce length".into()));
+        }
+        let (enc_key, mac_key) = derive_subkeys(passphrase, &self.kdf)?;
+
+        // Check the MAC before attempting decryption (constant-time compare).
+        if self.mac.len() != 32 {
+            return Err(VaultError::MacMismatch);
+        }
+        let computed = compute_hmac(&mac_key, &self.ciphertext);
+        let diff = computed
+            .iter()
+            .zip(self.mac.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if diff != 0 {
+            return Err(VaultError::MacMismatch);
+        }
+
+        let cipher = Aes256Gcm::new_from_slice(enc_key.expose())
+            .map_err(|e| VaultError::DecryptionFailed(e.to_string()))?;
+        cipher
+            .decrypt(Nonce::from_slice(&self.nonce), self.ciphertext.as_slice())
+            .map_err(|_| VaultError::DecryptionFailed("authentication failed".into()))
+    }
+
+    /// Serialize the keystore to JSON.
+    pub fn to_json(&self) -> VaultResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a keystore from JSON.
+    pub fn from_json(data: &str) -> VaultResult<Self> {
+        serde_json::from_str(data).map_err(VaultError::from)
+    }
+}
+
+impl KeyManager {
+    /// Boot a [`KeyManager`] from a passphrase-protected keystore.
+    pub fn from_keystore(keystore: &KeyStore, passphrase: &str) -> VaultResult<Self> {
+        let mut seed = keystore.open(passphrase)?;
+        let result = Self::from_master_seed(&seed);
+        seed.zeroize();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap Argon2 parameters keep the unit tests fast.
+    const TEST_MEM: u32 = 8 * 1024;
+    const TEST_ITERS: u32 = 1;
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let seed = [0x33u8; 32];
+        let ks = KeyStore::seal(&seed, "correct horse", TEST_MEM, TEST_ITERS).unwrap();
+        assert_eq!(ks.open("correct horse").unwrap(), seed.to_vec());
+    }
+
+    #[test]
+    fn test_wrong_passphrase_mac_mismatch() {
+        let seed = [0x44u8; 32];
+        let ks = KeyStore::seal(&seed, "right", TEST_MEM, TEST_ITERS).unwrap();
+        assert!(matches!(ks.open("wrong"), Err(VaultError::MacMismatch)));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let seed = [0x55u8; 32];
+        let ks = KeyStore::seal(&seed, "pw", TEST_MEM, TEST_ITERS).unwrap();
+        let json = ks.to_json().unwrap();
+        let restored = KeyStore::from_json(&json).unwrap();
+        assert_eq!(restored.open("pw").unwrap(), seed.to_vec());
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_rejected() {
+        let seed = [0x66u8; 32];
+        let mut ks = KeyStore::seal(&seed, "pw", TEST_MEM, TEST_ITERS).unwrap();
+        ks.ciphertext[0] ^= 0xff;
+        assert!(matches!(ks.open("pw"), Err(VaultError::MacMismatch)));
+    }
+}
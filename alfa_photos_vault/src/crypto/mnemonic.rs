@@ -0,0 +1,203 @@
+//! ALFA Photos Vault - BIP39 Mnemonic Backup
+//!
+//! Encodes the 32-byte ALFA_KEYVAULT master seed to, and decodes it from, a
+//! human-transcribable BIP39 word phrase so users can back up the vault as
+//! words rather than raw bytes.
+
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::keys::KeyManager;
+use crate::error::{VaultError, VaultResult};
+
+/// The vendored standard 2048-word BIP39 English word list, one word per line.
+const WORDLIST_RAW: &str = include_str!("bip39_english.txt");
+
+/// Look up the word at `index` (0..2048) in the BIP39 word list.
+fn word_at(index: usize) -> &'static str {
+    WORDLIST_RAW.lines().nth(index).unwrap_or("")
+}
+
+/// Find the index of `word` in the BIP39 word list, if present.
+fn index_of(word: &str) -> Option<usize> {
+    WORDLIST_RAW.lines().position(|w| w == word)
+}
+
+/// A BIP39 mnemonic phrase.
+#[derive(Clone)]
+pub struct Mnemonic {
+    words: Vec<String>,
+}
+
+impl Mnemonic {
+    /// Encode a seed (entropy) to a mnemonic phrase.
+    ///
+    /// The entropy bit-length must be a multiple of 32.
+    pub fn from_seed(seed: &[u8]) -> VaultResult<Self> {
+        let entropy_bits = seed.len() * 8;
+        if entropy_bits == 0 || entropy_bits % 32 != 0 {
+            return Err(VaultError::InvalidKeyLength {
+                expected: 32,
+                actual: seed.len(),
+            });
+        }
+
+        let checksum_bits = entropy_bits / 32;
+        let digest = Sha256::digest(seed);
+
+        // Concatenate entropy bits followed by the checksum bits, then slice
+        // into 11-bit groups indexing the word list.
+        let total_bits = entropy_bits + checksum_bits;
+        let mut words = Vec::with_capacity(total_bits / 11);
+        let mut bit = 0;
+        while bit < total_bits {
+            let mut index = 0usize;
+            for _ in 0..11 {
+                let byte_pos = bit / 8;
+                let bit_pos = 7 - (bit % 8);
+                let source = if byte_pos < seed.len() {
+                    seed[byte_pos]
+                } else {
+                    digest[byte_pos - seed.len()]
+                };
+                let value = (source >> bit_pos) & 1;
+                index = (index << 1) | value as usize;
+                bit += 1;
+            }
+            words.push(word_at(index).to_string());
+        }
+
+        Ok(Self { words })
+    }
+
+    /// Parse a mnemonic from a space-separated phrase, verifying the checksum.
+    pub fn parse(phrase: &str) -> VaultResult<Self> {
+        let words: Vec<String> = phrase.split_whitespace().map(|w| w.to_string()).collect();
+        if words.is_empty() || (words.len() * 11) % 33 != 0 {
+            return Err(VaultError::DeserializationError(
+                "mnemonic length is not a valid multiple".to_string(),
+            ));
+        }
+        let mnemonic = Self { words };
+        // Validate by round-tripping through to_entropy (checks vocabulary and checksum).
+        mnemonic.to_entropy()?;
+        Ok(mnemonic)
+    }
+
+    /// Decode the mnemonic back to the original entropy, verifying the checksum.
+    pub fn to_entropy(&self) -> VaultResult<Vec<u8>> {
+        let total_bits = self.words.len() * 11;
+        let entropy_bits = total_bits / 33 * 32;
+        let checksum_bits = total_bits - entropy_bits;
+
+        let mut bits = vec![0u8; total_bits];
+        for (w, word) in self.words.iter().enumerate() {
+            let index = index_of(word)
+                .ok_or_else(|| VaultError::DeserializationError(format!("unknown word: {word}")))?;
+            for b in 0..11 {
+                bits[w * 11 + b] = ((index >> (10 - b)) & 1) as u8;
+            }
+        }
+
+        let mut entropy = vec![0u8; entropy_bits / 8];
+        for (i, byte) in entropy.iter_mut().enumerate() {
+            let mut v = 0u8;
+            for b in 0..8 {
+                v = (v << 1) | bits[i * 8 + b];
+            }
+            *byte = v;
+        }
+
+        // Recompute and compare the checksum bits.
+        let digest = Sha256::digest(&entropy);
+        for b in 0..checksum_bits {
+            let expected = (digest[b / 8] >> (7 - (b % 8))) & 1;
+            if bits[entropy_bits + b] != expected {
+                bits.zeroize();
+                return Err(VaultError::DeserializationError(
+                    "mnemonic checksum mismatch".to_string(),
+                ));
+            }
+        }
+
+        bits.zeroize();
+        Ok(entropy)
+    }
+
+    /// Render the phrase as a space-separated string.
+    pub fn phrase(&self) -> String {
+        self.words.join(" ")
+    }
+}
+
+impl KeyManager {
+    /// Reconstruct a [`KeyManager`] from a BIP39 mnemonic backup.
+    pub fn from_mnemonic(mnemonic: &Mnemonic) -> VaultResult<Self> {
+        let mut entropy = mnemonic.to_entropy()?;
+        let result = Self::from_master_seed(&entropy);
+        entropy.zeroize();
+        result
+    }
+
+    /// Export the master seed as a 24-word BIP39 recovery phrase.
+    ///
+    /// The returned string is wrapped in [`Zeroizing`] so the phrase is wiped
+    /// from memory once the caller drops it.
+    pub fn export_mnemonic(&self) -> VaultResult<Zeroizing<String>> {
+        let mnemonic = Mnemonic::from_seed(self.master_seed().expose())?;
+        Ok(Zeroizing::new(mnemonic.phrase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mnemonic_round_trip() {
+        let seed = [0x13u8; 32];
+        let m = Mnemonic::from_seed(&seed).unwrap();
+        assert_eq!(m.to_entropy().unwrap(), seed.to_vec());
+
+        // Parse back from the rendered phrase.
+        let reparsed = Mnemonic::parse(&m.phrase()).unwrap();
+        assert_eq!(reparsed.to_entropy().unwrap(), seed.to_vec());
+    }
+
+    #[test]
+    fn test_export_then_restore_key_manager() {
+        let seed = [0x2bu8; 32];
+        let km = KeyManager::from_master_seed(&seed).unwrap();
+
+        let phrase = km.export_mnemonic().unwrap();
+        let restored = KeyManager::from_mnemonic(&Mnemonic::parse(&phrase).unwrap()).unwrap();
+
+        // The restored manager derives the same index key.
+        assert_eq!(
+            km.index_key().expose(),
+            restored.index_key().expose()
+        );
+    }
+
+    #[test]
+    fn test_bad_entropy_length() {
+        assert!(Mnemonic::from_seed(&[0u8; 7]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_word_rejected() {
+        let m = Mnemonic::from_seed(&[0x55u8; 32]).unwrap();
+        let mut words: Vec<&str> = m.phrase().split(' ').collect();
+        words[0] = "notaword";
+        assert!(Mnemonic::parse(&words.join(" ")).is_err());
+    }
+
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let m = Mnemonic::from_seed(&[0x7au8; 32]).unwrap();
+        let mut words: Vec<String> = m.phrase().split(' ').map(String::from).collect();
+        // Swap the last word for a different valid word to break the checksum.
+        words[23] = if words[23] == "zoo" { "zero".into() } else { "zoo".into() };
+        assert!(Mnemonic::parse(&words.join(" ")).is_err());
+    }
+}
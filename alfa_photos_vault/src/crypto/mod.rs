@@ -5,7 +5,19 @@
 pub mod keys;
 pub mod aead;
 pub mod hkdf;
+pub mod shard;
+pub mod derivation;
+pub mod mnemonic;
+pub mod hpke;
+pub mod keystore;
+pub mod keyring;
 
 pub use keys::*;
 pub use aead::*;
 pub use hkdf::*;
+pub use shard::*;
+pub use derivation::*;
+pub use mnemonic::*;
+pub use hpke::*;
+pub use keystore::*;
+pub use keyring::*;
@@ -0,0 +1,256 @@
+//! ALFA Photos Vault - Threshold Secret Sharing
+//!
+//! Splits the 32-byte ALFA_KEYVAULT master seed into `n` shares with a
+//! `k`-of-`n` recovery threshold using Shamir's scheme over GF(256)
+//! (the AES field, reduction polynomial 0x11B). Any `k` shares reconstruct
+//! the seed via Lagrange interpolation at x=0; fewer reveal nothing.
+
+use zeroize::Zeroize;
+
+use super::keys::KEY_LEN;
+use crate::error::{VaultError, VaultResult};
+
+/// Share format version.
+pub const SHARE_VERSION: u8 = 1;
+
+/// A single Shamir share of the master seed.
+///
+/// Carries a version/threshold header and the share's x-coordinate so that a
+/// set of shares can be validated and recombined without external metadata.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretShare {
+    /// Share format version.
+    pub version: u8,
+    /// Recovery threshold `k` this share was produced for.
+    pub threshold: u8,
+    /// Distinct nonzero x-coordinate of this share (1..=n).
+    pub x: u8,
+    /// Evaluations `f_j(x)` for each of the 32 secret bytes.
+    pub y: [u8; KEY_LEN],
+}
+
+impl Drop for SecretShare {
+    fn drop(&mut self) {
+        self.y.zeroize();
+    }
+}
+
+/// Multiply two elements of GF(256) with reduction polynomial 0x11B.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let high_bit = a & 0x80;
+        a <<= 1;
+        if high_bit != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse in GF(256) via exponentiation (a^254 = a^-1).
+fn gf_inv(a: u8) -> u8 {
+    // For nonzero a the field has order 255, so a^254 = a^-1.
+    let mut result = 1u8;
+    for _ in 0..254 {
+        result = gf_mul(result, a);
+    }
+    result
+}
+
+/// Evaluate a GF(256) polynomial (coefficients low-to-high) at `x`.
+fn gf_eval(coeffs: &[u8], x: u8) -> u8 {
+    // Horner's method from the highest-degree coefficient down.
+    let mut acc = 0u8;
+    for &c in coeffs.iter().rev() {
+        acc = gf_mul(acc, x) ^ c;
+    }
+    acc
+}
+
+/// Split a 32-byte master seed into `n` shares recoverable from any `k`.
+pub fn split_seed(seed: &[u8], k: u8, n: u8) -> VaultResult<Vec<SecretShare>> {
+    if seed.len() != KEY_LEN {
+        return Err(VaultError::InvalidKeyLength {
+            expected: KEY_LEN,
+            actual: seed.len(),
+        });
+    }
+    if k < 1 || n < 1 || k > n {
+        return Err(VaultError::InvalidShareParameters(format!(
+            "require 1 <= k <= n, got k={k}, n={n}"
+        )));
+    }
+    // x-coordinates run 1..=n, so n must fit in the 255 nonzero field elements.
+    if n > 255 {
+        return Err(VaultError::InvalidShareParameters(
+            "n must be <= 255".to_string(),
+        ));
+    }
+
+    use rand::RngCore;
+    let mut rng = rand::thread_rng();
+
+    // For each secret byte, a degree-(k-1) polynomial with that byte as the
+    // constant term and random higher coefficients.
+    let mut polys = vec![[0u8; KEY_LEN]; k as usize];
+    polys[0].copy_from_slice(seed);
+    for coeff in polys.iter_mut().skip(1) {
+        rng.fill_bytes(coeff);
+    }
+
+    let mut shares = Vec::with_capacity(n as usize);
+    for x in 1..=n {
+        let mut y = [0u8; KEY_LEN];
+        for (j, y_j) in y.iter_mut().enumerate() {
+            let coeffs: Vec<u8> = polys.iter().map(|p| p[j]).collect();
+            *y_j = gf_eval(&coeffs, x);
+        }
+        shares.push(SecretShare {
+            version: SHARE_VERSION,
+            threshold: k,
+            x,
+            y,
+        });
+    }
+
+    polys.zeroize();
+    Ok(shares)
+}
+
+/// Reconstruct a 32-byte seed from `k` shares via Lagrange interpolation at x=0.
+fn interpolate(shares: &[&SecretShare]) -> [u8; KEY_LEN] {
+    let mut seed = [0u8; KEY_LEN];
+    for (j, seed_j) in seed.iter_mut().enumerate() {
+        let mut acc = 0u8;
+        for (i, share_i) in shares.iter().enumerate() {
+            // Lagrange basis L_i(0) = prod_{m != i} x_m / (x_m - x_i).
+            let mut num = 1u8;
+            let mut den = 1u8;
+            for (m, share_m) in shares.iter().enumerate() {
+                if m == i {
+                    continue;
+                }
+                num = gf_mul(num, share_m.x);
+                den = gf_mul(den, share_m.x ^ share_i.x);
+            }
+            let basis = gf_mul(num, gf_inv(den));
+            acc ^= gf_mul(share_i.y[j], basis);
+        }
+        *seed_j = acc;
+    }
+    seed
+}
+
+/// Recover the master seed from any `k` (or more) consistent shares.
+///
+/// Rejects duplicate x-indices and share sets that are mutually inconsistent
+/// (two distinct `k`-subsets reconstructing different seeds).
+pub fn recover_seed(shares: &[SecretShare]) -> VaultResult<[u8; KEY_LEN]> {
+    if shares.is_empty() {
+        return Err(VaultError::InvalidShareParameters(
+            "no shares provided".to_string(),
+        ));
+    }
+
+    let k = shares[0].threshold as usize;
+    if shares.len() < k {
+        return Err(VaultError::InvalidShareParameters(format!(
+            "need {k} shares, got {}",
+            shares.len()
+        )));
+    }
+
+    // Reject duplicate / zero x-indices.
+    let mut seen = [false; 256];
+    for share in shares {
+        if share.x == 0 {
+            return Err(VaultError::InvalidShareParameters(
+                "share x-index must be nonzero".to_string(),
+            ));
+        }
+        if seen[share.x as usize] {
+            return Err(VaultError::DuplicateShareIndex(share.x));
+        }
+        seen[share.x as usize] = true;
+    }
+
+    let refs: Vec<&SecretShare> = shares.iter().collect();
+    let seed = interpolate(&refs[..k]);
+
+    // If extra shares were supplied, each must lie on the same polynomial: the
+    // k-subset that swaps it in for the first share must reconstruct the same seed.
+    for extra in &refs[k..] {
+        let mut alt: Vec<&SecretShare> = refs[1..k].to_vec();
+        alt.push(*extra);
+        if interpolate(&alt) != seed {
+            return Err(VaultError::ShareSetInconsistent);
+        }
+    }
+
+    Ok(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gf_inverse() {
+        for a in 1u8..=255 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1, "inverse of {a}");
+        }
+    }
+
+    #[test]
+    fn test_split_and_recover() {
+        let seed = [0x5au8; KEY_LEN];
+        let shares = split_seed(&seed, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        // Any k-subset recovers the seed.
+        let subset = [
+            shares[0].clone(),
+            shares[2].clone(),
+            shares[4].clone(),
+        ];
+        assert_eq!(recover_seed(&subset).unwrap(), seed);
+
+        // All shares recover the seed too.
+        assert_eq!(recover_seed(&shares).unwrap(), seed);
+    }
+
+    #[test]
+    fn test_duplicate_index_rejected() {
+        let seed = [0x11u8; KEY_LEN];
+        let shares = split_seed(&seed, 2, 3).unwrap();
+        let dup = [shares[0].clone(), shares[0].clone()];
+        assert!(matches!(
+            recover_seed(&dup),
+            Err(VaultError::DuplicateShareIndex(_))
+        ));
+    }
+
+    #[test]
+    fn test_inconsistent_set_rejected() {
+        let seed = [0x22u8; KEY_LEN];
+        let mut shares = split_seed(&seed, 2, 4).unwrap();
+        // Corrupt one extra share so the cross-subset check disagrees.
+        shares[3].y[0] ^= 0xff;
+        assert!(matches!(
+            recover_seed(&shares),
+            Err(VaultError::ShareSetInconsistent)
+        ));
+    }
+
+    #[test]
+    fn test_bad_parameters() {
+        let seed = [0u8; KEY_LEN];
+        assert!(split_seed(&seed, 4, 3).is_err());
+        assert!(split_seed(&[0u8; 8], 2, 3).is_err());
+    }
+}
@@ -26,7 +26,22 @@ pub enum VaultError {
     
     #[error("HMAC verification failed - file corrupted or tampered")]
     HmacVerificationFailed,
-    
+
+    #[error("Invalid share parameters: {0}")]
+    InvalidShareParameters(String),
+
+    #[error("Duplicate share index: {0}")]
+    DuplicateShareIndex(u8),
+
+    #[error("Inconsistent share set - reconstructed seeds disagree")]
+    ShareSetInconsistent,
+
+    #[error("Keystore MAC mismatch - wrong passphrase or tampered file")]
+    MacMismatch,
+
+    #[error("Nonce counter {counter} already used for file {file_id}")]
+    NonceReused { file_id: String, counter: u64 },
+
     // ═══════════════════════════════════════════════════════════════
     // VAULT ERRORS
     // ═══════════════════════════════════════════════════════════════
@@ -45,6 +60,12 @@ pub enum VaultError {
     
     #[error("Invalid PIN")]
     InvalidPin,
+
+    #[error("Named key not found: {0}")]
+    KeyNotFound(String),
+
+    #[error("Named key not mounted: {0}")]
+    KeyNotMounted(String),
     
     #[error("Biometric authentication failed")]
     BiometricFailed,
@@ -70,6 +91,9 @@ pub enum VaultError {
     
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+
+    #[error("Integrity verification failed for: {0}")]
+    IntegrityFailure(String),
     
     // ═══════════════════════════════════════════════════════════════
     // INDEX ERRORS
@@ -93,6 +117,9 @@ pub enum VaultError {
     
     #[error("Image processing error: {0}")]
     ImageError(String),
+
+    #[error("Image decode failed for {0} - treated as untrusted input")]
+    ImageDecodeFailed(String),
     
     // ═══════════════════════════════════════════════════════════════
     // SYNC ERRORS
@@ -148,6 +175,7 @@ impl VaultError {
             self,
             VaultError::IndexCorrupted(_)
                 | VaultError::ThumbnailFailed(_)
+                | VaultError::ImageDecodeFailed(_)
                 | VaultError::SyncFailed(_)
         )
     }
@@ -0,0 +1,290 @@
+//! ALFA Photos Vault - Read-only FUSE filesystem
+//!
+//! Exposes an unlocked [`PhotoVault`] as a read-only FUSE mount so photos can
+//! be browsed and copied with ordinary file tools. Decryption happens lazily
+//! on `read()`; nothing is ever written back through this layer.
+//!
+//! Layout:
+//!
+//! ```text
+//! /
+//! ├── all/            every photo, named <original_name>
+//! ├── favorites/      photos flagged favorite
+//! ├── hidden/         photos flagged hidden
+//! └── .thumbs/        decrypted thumbnails, named <original_name>
+//! ```
+//!
+//! Only built with the `fuse` feature on Unix targets.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use fuser::{
+    FileAttr, FileType, Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry, Request,
+};
+
+use crate::error::VaultResult;
+use crate::vault::{PhotoMeta, PhotoVault};
+
+/// Attribute/entry cache TTL handed back to the kernel.
+const TTL: Duration = Duration::from_secs(1);
+
+/// Fixed inode numbers for the directory skeleton.
+const INO_ROOT: u64 = 1;
+const INO_ALL: u64 = 2;
+const INO_FAVORITES: u64 = 3;
+const INO_HIDDEN: u64 = 4;
+const INO_THUMBS: u64 = 5;
+/// Photo/thumbnail inodes start here so they never collide with directories.
+const INO_FILE_BASE: u64 = 1024;
+
+/// What a file inode resolves to inside the vault.
+#[derive(Clone)]
+enum FileKind {
+    Photo,
+    Thumb,
+}
+
+/// A concrete file exposed by the mount.
+#[derive(Clone)]
+struct FileNode {
+    photo_id: String,
+    name: String,
+    size: u64,
+    kind: FileKind,
+}
+
+/// Read-only FUSE adapter over an unlocked vault.
+pub struct VaultFs {
+    vault: Arc<PhotoVault>,
+    /// Inode → file node, for `getattr`/`open`/`read`.
+    files: HashMap<u64, FileNode>,
+    /// (parent inode, name) → child inode, for `lookup`.
+    by_name: HashMap<(u64, String), u64>,
+    /// Directory inode → ordered (inode, name) children, for `readdir`.
+    dirs: HashMap<u64, Vec<(u64, String)>>,
+}
+
+impl VaultFs {
+    /// Build the inode table from the vault's current photo list.
+    pub fn new(vault: Arc<PhotoVault>) -> VaultResult<Self> {
+        let mut fs = Self {
+            vault,
+            files: HashMap::new(),
+            by_name: HashMap::new(),
+            dirs: HashMap::new(),
+        };
+
+        // Directory skeleton.
+        for (parent, ino, name) in [
+            (INO_ROOT, INO_ALL, "all"),
+            (INO_ROOT, INO_FAVORITES, "favorites"),
+            (INO_ROOT, INO_HIDDEN, "hidden"),
+            (INO_ROOT, INO_THUMBS, ".thumbs"),
+        ] {
+            fs.by_name.insert((parent, name.to_string()), ino);
+            fs.dirs.entry(parent).or_default().push((ino, name.to_string()));
+            fs.dirs.entry(ino).or_default();
+        }
+
+        let photos = fs.vault.list_photos()?;
+        let mut next = INO_FILE_BASE;
+        for meta in &photos {
+            // Each photo appears in /all plus its category, as the same inode
+            // so tools see identical content regardless of path.
+            let ino = next;
+            next += 1;
+            fs.add_file(ino, INO_ALL, meta, FileKind::Photo, meta.original_size);
+            if meta.is_favorite {
+                fs.link(INO_FAVORITES, ino, &meta.original_name);
+            }
+            if meta.is_hidden {
+                fs.link(INO_HIDDEN, ino, &meta.original_name);
+            }
+
+            // Thumbnail under /.thumbs with a matching name.
+            let tino = next;
+            next += 1;
+            fs.add_file(tino, INO_THUMBS, meta, FileKind::Thumb, 0);
+        }
+
+        Ok(fs)
+    }
+
+    fn add_file(&mut self, ino: u64, parent: u64, meta: &PhotoMeta, kind: FileKind, size: u64) {
+        let node = FileNode {
+            photo_id: meta.id.clone(),
+            name: meta.original_name.clone(),
+            size,
+            kind,
+        };
+        self.files.insert(ino, node);
+        self.link(parent, ino, &meta.original_name);
+    }
+
+    fn link(&mut self, parent: u64, ino: u64, name: &str) {
+        self.by_name.insert((parent, name.to_string()), ino);
+        self.dirs.entry(parent).or_default().push((ino, name.to_string()));
+    }
+
+    fn dir_attr(&self, ino: u64) -> FileAttr {
+        attr(ino, 0, FileType::Directory)
+    }
+
+    fn file_attr(&self, ino: u64, node: &FileNode) -> FileAttr {
+        attr(ino, node.size, FileType::RegularFile)
+    }
+
+    /// Decrypt the bytes backing a file node on demand.
+    fn contents(&self, node: &FileNode) -> VaultResult<Vec<u8>> {
+        match node.kind {
+            FileKind::Photo => self.vault.get_photo(&node.photo_id),
+            FileKind::Thumb => self.vault.get_thumbnail(&node.photo_id),
+        }
+    }
+}
+
+/// Build a [`FileAttr`] with sane read-only permissions.
+fn attr(ino: u64, size: u64, kind: FileType) -> FileAttr {
+    let perm = if kind == FileType::Directory { 0o555 } else { 0o444 };
+    FileAttr {
+        ino,
+        size,
+        blocks: size.div_ceil(512),
+        atime: UNIX_EPOCH,
+        mtime: UNIX_EPOCH,
+        ctime: UNIX_EPOCH,
+        crtime: UNIX_EPOCH,
+        kind,
+        perm,
+        nlink: 1,
+        uid: unsafe { libc::getuid() },
+        gid: unsafe { libc::getgid() },
+        rdev: 0,
+        blksize: 512,
+        flags: 0,
+    }
+}
+
+fn is_dir(ino: u64) -> bool {
+    matches!(ino, INO_ROOT | INO_ALL | INO_FAVORITES | INO_HIDDEN | INO_THUMBS)
+}
+
+impl Filesystem for VaultFs {
+    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let name = match name.to_str() {
+            Some(n) => n.to_string(),
+            None => return reply.error(libc::ENOENT),
+        };
+        match self.by_name.get(&(parent, name)).copied() {
+            Some(ino) if is_dir(ino) => reply.entry(&TTL, &self.dir_attr(ino), 0),
+            Some(ino) => {
+                let node = self.files[&ino].clone();
+                reply.entry(&TTL, &self.file_attr(ino, &node), 0);
+            }
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request, ino: u64, reply: ReplyAttr) {
+        if is_dir(ino) {
+            reply.attr(&TTL, &self.dir_attr(ino));
+        } else if let Some(node) = self.files.get(&ino).cloned() {
+            reply.attr(&TTL, &self.file_attr(ino, &node));
+        } else {
+            reply.error(libc::ENOENT);
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let children = match self.dirs.get(&ino) {
+            Some(c) => c,
+            None => return reply.error(libc::ENOENT),
+        };
+
+        let mut entries: Vec<(u64, FileType, String)> =
+            vec![(ino, FileType::Directory, ".".into()), (INO_ROOT, FileType::Directory, "..".into())];
+        for (child, name) in children {
+            let kind = if is_dir(*child) { FileType::Directory } else { FileType::RegularFile };
+            entries.push((*child, kind, name.clone()));
+        }
+
+        for (i, (child, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(child, (i + 1) as i64, kind, &name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn open(&mut self, _req: &Request, ino: u64, _flags: i32, reply: fuser::ReplyOpen) {
+        if self.files.contains_key(&ino) {
+            reply.opened(0, 0);
+        } else {
+            reply.error(libc::ENOENT);
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let node = match self.files.get(&ino).cloned() {
+            Some(n) => n,
+            None => return reply.error(libc::ENOENT),
+        };
+        match self.contents(&node) {
+            Ok(data) => {
+                let start = (offset as usize).min(data.len());
+                let end = (start + size as usize).min(data.len());
+                reply.data(&data[start..end]);
+            }
+            Err(_) => reply.error(libc::EIO),
+        }
+    }
+}
+
+/// Mount the unlocked `vault` read-only at `mountpoint`, re-locking on SIGINT.
+///
+/// Blocks until the filesystem is unmounted (e.g. by `Ctrl-C` or `umount`).
+pub fn mount(vault: Arc<PhotoVault>, mountpoint: &std::path::Path) -> VaultResult<()> {
+    use fuser::MountOption;
+
+    let fs = VaultFs::new(Arc::clone(&vault))?;
+    let options = vec![
+        MountOption::RO,
+        MountOption::FSName("alfa-photos".to_string()),
+        MountOption::DefaultPermissions,
+    ];
+
+    // Unmount and re-lock cleanly when interrupted.
+    let session = fuser::spawn_mount2(fs, mountpoint, &options)
+        .map_err(|e| crate::error::VaultError::IoError(e))?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = tx.send(());
+    })
+    .map_err(|e| crate::error::VaultError::AiError(e.to_string()))?;
+
+    let _ = rx.recv();
+    drop(session); // unmounts
+    vault.lock();
+    Ok(())
+}
@@ -11,8 +11,173 @@ use parking_lot::Mutex;
 
 use crate::crypto::{KeyManager, encrypt_xchacha, decrypt_xchacha, EncryptedData};
 use crate::vault::PhotoMeta;
+use crate::oplog::{HybridClock, HybridTimestamp};
 use crate::error::{VaultError, VaultResult};
 
+/// Write a full-state checkpoint at least every this many appended ops.
+const CHECKPOINT_INTERVAL: u64 = 64;
+
+/// A single materialized-view mutation recorded in the append-only `ops` table.
+///
+/// The `photos`/`tags` tables are a derived view of replaying these in
+/// timestamp order; ops themselves are immutable once written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum IndexOp {
+    /// Insert a new photo with its full metadata.
+    AddPhoto(Box<PhotoMeta>),
+    /// Replace an existing photo's metadata (last-writer-wins).
+    UpdateMeta(Box<PhotoMeta>),
+    /// Remove a photo and its tags.
+    RemovePhoto { id: String },
+    /// Attach a tag to a photo.
+    AddTag { id: String, tag: String },
+    /// Detach a tag from a photo.
+    RemoveTag { id: String, tag: String },
+}
+
+/// An op paired with the timestamp that orders it (encrypted as one blob).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LoggedOp {
+    ts: HybridTimestamp,
+    op: IndexOp,
+}
+
+/// A full snapshot of the resolved state at a point in the op stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexCheckpoint {
+    ts: HybridTimestamp,
+    photos: Vec<PhotoMeta>,
+}
+
+/// Wire representation of a single `ops` row, used by `export_log`/`merge`.
+/// `data` is the already-encrypted op blob, so the export stays confidential.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpRow {
+    wall_ms: u64,
+    counter: u64,
+    actor: String,
+    data: Vec<u8>,
+}
+
+/// Decode a base64 pHash string (as produced by `img_hash`) into raw bytes.
+fn decode_phash_bits(s: &str) -> Option<Vec<u8>> {
+    img_hash::ImageHash::<Box<[u8]>>::from_base64(s)
+        .ok()
+        .map(|h| h.as_bytes().to_vec())
+}
+
+/// Hamming distance between two equal-length hashes; unequal lengths are
+/// treated as infinitely far apart so they never cluster together.
+fn hamming_distance(a: &[u8], b: &[u8]) -> u32 {
+    if a.len() != b.len() {
+        return u32::MAX;
+    }
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// A node in a BK-tree: a hash plus children indexed by their Hamming distance
+/// to it.
+struct BkNode {
+    hash: Vec<u8>,
+    idx: usize,
+    children: std::collections::HashMap<u32, Box<BkNode>>,
+}
+
+/// A BK-tree over perceptual hashes, supporting range queries by Hamming
+/// distance with triangle-inequality pruning.
+#[derive(Default)]
+struct BkTree {
+    root: Option<Box<BkNode>>,
+}
+
+impl BkTree {
+    fn insert(&mut self, hash: Vec<u8>, idx: usize) {
+        match &mut self.root {
+            None => {
+                self.root = Some(Box::new(BkNode {
+                    hash,
+                    idx,
+                    children: std::collections::HashMap::new(),
+                }))
+            }
+            Some(root) => {
+                let mut node = root.as_mut();
+                loop {
+                    let d = hamming_distance(&node.hash, &hash);
+                    if let std::collections::hash_map::Entry::Vacant(e) = node.children.entry(d) {
+                        e.insert(Box::new(BkNode {
+                            hash,
+                            idx,
+                            children: std::collections::HashMap::new(),
+                        }));
+                        return;
+                    }
+                    node = node.children.get_mut(&d).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Return the indices of every hash within `max_distance` of `target`.
+    fn query(&self, target: &[u8], max_distance: u32) -> Vec<usize> {
+        let mut out = Vec::new();
+        let mut stack: Vec<&BkNode> = self.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let d = hamming_distance(&node.hash, target);
+            if d <= max_distance {
+                out.push(node.idx);
+            }
+            // Only descend into children whose distance to this node could
+            // possibly fall within max_distance of the target.
+            let lo = d.saturating_sub(max_distance);
+            let hi = d.saturating_add(max_distance);
+            for (&child_d, child) in &node.children {
+                if child_d >= lo && child_d <= hi {
+                    stack.push(child);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Disjoint-set union used to collapse the near-duplicate graph into
+/// connected components.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // Path compression.
+        let mut cur = x;
+        while self.parent[cur] != root {
+            let next = self.parent[cur];
+            self.parent[cur] = root;
+            cur = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let ra = self.find(a);
+        let rb = self.find(b);
+        if ra != rb {
+            self.parent[ra] = rb;
+        }
+    }
+}
+
 /// Photo Index - encrypted database for photo metadata
 pub struct PhotoIndex {
     /// Database connection
@@ -21,6 +186,8 @@ pub struct PhotoIndex {
     root: PathBuf,
     /// Key manager reference
     keys: Arc<KeyManager>,
+    /// Hybrid-logical clock stamping appended ops (device id breaks ties).
+    clock: Mutex<HybridClock>,
 }
 
 impl PhotoIndex {
@@ -28,73 +195,175 @@ impl PhotoIndex {
     pub fn create(root: &Path, keys: &Arc<KeyManager>) -> VaultResult<Self> {
         let db_path = root.join("db").join("index.db");
         let conn = Connection::open(&db_path)?;
-        
-        // Create tables
+
+        // WAL for crash safety under concurrent reads/writes.
+        conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+
+        // Create tables. Metadata stays encrypted in `data`; only opaque ids and
+        // the hidden/favorite flags needed for indexed listings are plaintext.
         conn.execute_batch(
             r#"
             CREATE TABLE IF NOT EXISTS photos (
                 id TEXT PRIMARY KEY,
                 data BLOB NOT NULL,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                is_hidden INTEGER NOT NULL DEFAULT 0,
+                is_favorite INTEGER NOT NULL DEFAULT 0
             );
-            
+
             CREATE TABLE IF NOT EXISTS tags (
                 photo_id TEXT NOT NULL,
                 tag TEXT NOT NULL,
                 PRIMARY KEY (photo_id, tag),
                 FOREIGN KEY (photo_id) REFERENCES photos(id) ON DELETE CASCADE
             );
-            
+
             CREATE INDEX IF NOT EXISTS idx_tags ON tags(tag);
             CREATE INDEX IF NOT EXISTS idx_created ON photos(created_at);
+            CREATE INDEX IF NOT EXISTS idx_hidden ON photos(is_hidden);
+            CREATE INDEX IF NOT EXISTS idx_favorite ON photos(is_favorite);
+
+            CREATE TABLE IF NOT EXISTS ops (
+                wall_ms INTEGER NOT NULL,
+                counter INTEGER NOT NULL,
+                actor   TEXT    NOT NULL,
+                data    BLOB    NOT NULL,
+                PRIMARY KEY (wall_ms, counter, actor)
+            );
+
+            CREATE TABLE IF NOT EXISTS checkpoints (
+                wall_ms INTEGER NOT NULL,
+                counter INTEGER NOT NULL,
+                actor   TEXT    NOT NULL,
+                data    BLOB    NOT NULL,
+                PRIMARY KEY (wall_ms, counter, actor)
+            );
+
+            CREATE TABLE IF NOT EXISTS index_meta (
+                key   TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
             "#,
         )?;
-        
+
+        let device_id = Self::load_or_init_device_id(&conn)?;
+
         Ok(Self {
             conn: Mutex::new(conn),
             root: root.to_path_buf(),
             keys: Arc::clone(keys),
+            clock: Mutex::new(HybridClock::new(device_id)),
         })
     }
-    
+
     /// Open existing index
     pub fn open(root: &Path, keys: &Arc<KeyManager>) -> VaultResult<Self> {
         let db_path = root.join("db").join("index.db");
-        
+
         if !db_path.exists() {
             return Err(VaultError::IndexCorrupted("Database not found".into()));
         }
-        
+
         let conn = Connection::open(&db_path)?;
-        
-        Ok(Self {
+        conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+
+        // Ensure the op-log tables exist for indexes created before this schema.
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS ops (
+                wall_ms INTEGER NOT NULL,
+                counter INTEGER NOT NULL,
+                actor   TEXT    NOT NULL,
+                data    BLOB    NOT NULL,
+                PRIMARY KEY (wall_ms, counter, actor)
+            );
+            CREATE TABLE IF NOT EXISTS checkpoints (
+                wall_ms INTEGER NOT NULL,
+                counter INTEGER NOT NULL,
+                actor   TEXT    NOT NULL,
+                data    BLOB    NOT NULL,
+                PRIMARY KEY (wall_ms, counter, actor)
+            );
+            CREATE TABLE IF NOT EXISTS index_meta (
+                key   TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            "#,
+        )?;
+
+        let device_id = Self::load_or_init_device_id(&conn)?;
+
+        let index = Self {
             conn: Mutex::new(conn),
             root: root.to_path_buf(),
             keys: Arc::clone(keys),
-        })
+            clock: Mutex::new(HybridClock::new(device_id)),
+        };
+
+        // Rebuild the materialized view from the newest checkpoint + later ops,
+        // and advance the clock past every op we've already seen.
+        index.replay()?;
+        Ok(index)
     }
-    
-    /// Add a photo to the index
+
+    /// Load the persisted device id, generating and storing one on first use.
+    /// The device id is the final tiebreaker for op timestamps, so it must be
+    /// stable across opens.
+    fn load_or_init_device_id(conn: &Connection) -> VaultResult<String> {
+        let existing: Option<String> = conn
+            .query_row(
+                "SELECT value FROM index_meta WHERE key = 'device_id'",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT OR REPLACE INTO index_meta (key, value) VALUES ('device_id', ?1)",
+            params![id],
+        )?;
+        Ok(id)
+    }
+
+    /// Add a photo to the index, recording an `AddPhoto` op.
     pub fn add_photo(&self, meta: &PhotoMeta) -> VaultResult<()> {
+        self.append_op(IndexOp::AddPhoto(Box::new(meta.clone())))?;
+        self.materialize_add(meta)
+    }
+
+    /// Apply an insert/replace to the materialized `photos`/`tags` view without
+    /// recording an op (used by `add_photo`, `update_photo`, and replay).
+    fn materialize_add(&self, meta: &PhotoMeta) -> VaultResult<()> {
         // Encrypt metadata
         let meta_json = serde_json::to_vec(meta)?;
-        let encrypted = encrypt_xchacha(self.keys.index_key(), &meta_json)?;
+        let encrypted = encrypt_xchacha(self.keys.index_key(), &meta_json, &[])?;
         let encrypted_bytes = encrypted.to_bytes();
-        
+
         let conn = self.conn.lock();
         conn.execute(
-            "INSERT OR REPLACE INTO photos (id, data, created_at) VALUES (?1, ?2, ?3)",
-            params![meta.id, encrypted_bytes, meta.imported_at.to_rfc3339()],
+            "INSERT OR REPLACE INTO photos (id, data, created_at, is_hidden, is_favorite) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                meta.id,
+                encrypted_bytes,
+                meta.imported_at.to_rfc3339(),
+                meta.is_hidden as i64,
+                meta.is_favorite as i64,
+            ],
         )?;
-        
-        // Add tags
+
+        // Tags are re-derived on replace so edits don't leave stale rows.
+        conn.execute("DELETE FROM tags WHERE photo_id = ?1", params![meta.id])?;
         for tag in &meta.tags {
             conn.execute(
                 "INSERT OR IGNORE INTO tags (photo_id, tag) VALUES (?1, ?2)",
                 params![meta.id, tag],
             )?;
         }
-        
+
         Ok(())
     }
     
@@ -111,8 +380,8 @@ impl PhotoIndex {
             .map_err(|_| VaultError::PhotoNotFound(id.to_string()))?;
         
         // Decrypt metadata
-        let encrypted = EncryptedData::from_bytes_xchacha(&encrypted_bytes)?;
-        let meta_json = decrypt_xchacha(self.keys.index_key(), &encrypted)?;
+        let encrypted = EncryptedData::parse(&encrypted_bytes)?;
+        let meta_json = decrypt_xchacha(self.keys.index_key(), &encrypted, &[])?;
         
         serde_json::from_slice(&meta_json)
             .map_err(|e| VaultError::DeserializationError(e.to_string()))
@@ -131,8 +400,8 @@ impl PhotoIndex {
         let mut photos = Vec::new();
         for row in rows {
             if let Ok(encrypted_bytes) = row {
-                if let Ok(encrypted) = EncryptedData::from_bytes_xchacha(&encrypted_bytes) {
-                    if let Ok(meta_json) = decrypt_xchacha(self.keys.index_key(), &encrypted) {
+                if let Ok(encrypted) = EncryptedData::parse(&encrypted_bytes) {
+                    if let Ok(meta_json) = decrypt_xchacha(self.keys.index_key(), &encrypted, &[]) {
                         if let Ok(meta) = serde_json::from_slice::<PhotoMeta>(&meta_json) {
                             photos.push(meta);
                         }
@@ -144,16 +413,36 @@ impl PhotoIndex {
         Ok(photos)
     }
     
-    /// List hidden photos only
+    /// List hidden photos only (indexed query on the `is_hidden` flag).
     pub fn list_hidden(&self) -> VaultResult<Vec<PhotoMeta>> {
-        let all = self.list_all()?;
-        Ok(all.into_iter().filter(|p| p.is_hidden).collect())
+        self.query_flagged("is_hidden")
     }
-    
-    /// List favorites only
+
+    /// List favorites only (indexed query on the `is_favorite` flag).
     pub fn list_favorites(&self) -> VaultResult<Vec<PhotoMeta>> {
-        let all = self.list_all()?;
-        Ok(all.into_iter().filter(|p| p.is_favorite).collect())
+        self.query_flagged("is_favorite")
+    }
+
+    /// Decrypt every photo whose given boolean flag column is set.
+    fn query_flagged(&self, column: &str) -> VaultResult<Vec<PhotoMeta>> {
+        let conn = self.conn.lock();
+        let sql = format!(
+            "SELECT data FROM photos WHERE {column} = 1 ORDER BY created_at DESC"
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| row.get::<_, Vec<u8>>(0))?;
+
+        let mut photos = Vec::new();
+        for row in rows.flatten() {
+            if let Ok(encrypted) = EncryptedData::parse(&row) {
+                if let Ok(meta_json) = decrypt_xchacha(self.keys.index_key(), &encrypted, &[]) {
+                    if let Ok(meta) = serde_json::from_slice::<PhotoMeta>(&meta_json) {
+                        photos.push(meta);
+                    }
+                }
+            }
+        }
+        Ok(photos)
     }
     
     /// Search by tag
@@ -174,8 +463,8 @@ impl PhotoIndex {
         let mut photos = Vec::new();
         for row in rows {
             if let Ok(encrypted_bytes) = row {
-                if let Ok(encrypted) = EncryptedData::from_bytes_xchacha(&encrypted_bytes) {
-                    if let Ok(meta_json) = decrypt_xchacha(self.keys.index_key(), &encrypted) {
+                if let Ok(encrypted) = EncryptedData::parse(&encrypted_bytes) {
+                    if let Ok(meta_json) = decrypt_xchacha(self.keys.index_key(), &encrypted, &[]) {
                         if let Ok(meta) = serde_json::from_slice::<PhotoMeta>(&meta_json) {
                             photos.push(meta);
                         }
@@ -187,21 +476,268 @@ impl PhotoIndex {
         Ok(photos)
     }
     
-    /// Update photo metadata
+    /// Update photo metadata, recording an `UpdateMeta` op.
     pub fn update_photo(&self, meta: &PhotoMeta) -> VaultResult<()> {
-        self.add_photo(meta) // Uses INSERT OR REPLACE
+        self.append_op(IndexOp::UpdateMeta(Box::new(meta.clone())))?;
+        self.materialize_add(meta) // Uses INSERT OR REPLACE
     }
-    
-    /// Remove photo from index
+
+    /// Remove photo from index, recording a `RemovePhoto` op.
     pub fn remove_photo(&self, id: &str) -> VaultResult<()> {
+        self.append_op(IndexOp::RemovePhoto { id: id.to_string() })?;
+        self.materialize_remove(id)
+    }
+
+    /// Apply a removal to the materialized view without recording an op.
+    fn materialize_remove(&self, id: &str) -> VaultResult<()> {
         let conn = self.conn.lock();
-        
         conn.execute("DELETE FROM tags WHERE photo_id = ?1", params![id])?;
         conn.execute("DELETE FROM photos WHERE id = ?1", params![id])?;
-        
+        Ok(())
+    }
+
+    /// Attach a tag to a photo, recording an `AddTag` op.
+    pub fn add_tag(&self, id: &str, tag: &str) -> VaultResult<()> {
+        self.append_op(IndexOp::AddTag {
+            id: id.to_string(),
+            tag: tag.to_string(),
+        })?;
+        self.materialize_add_tag(id, tag)
+    }
+
+    /// Detach a tag from a photo, recording a `RemoveTag` op.
+    pub fn remove_tag(&self, id: &str, tag: &str) -> VaultResult<()> {
+        self.append_op(IndexOp::RemoveTag {
+            id: id.to_string(),
+            tag: tag.to_string(),
+        })?;
+        self.materialize_remove_tag(id, tag)
+    }
+
+    fn materialize_add_tag(&self, id: &str, tag: &str) -> VaultResult<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (photo_id, tag) VALUES (?1, ?2)",
+            params![id, tag],
+        )?;
+        Ok(())
+    }
+
+    fn materialize_remove_tag(&self, id: &str, tag: &str) -> VaultResult<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            "DELETE FROM tags WHERE photo_id = ?1 AND tag = ?2",
+            params![id, tag],
+        )?;
         Ok(())
     }
     
+    /// Repopulate the derived tag table and flag columns from the authoritative
+    /// encrypted `data` blobs. Used after a restore or when the materialized
+    /// columns may have drifted from the encrypted metadata.
+    pub fn rebuild_derived(&self) -> VaultResult<()> {
+        let metas = self.list_all()?;
+        let conn = self.conn.lock();
+        conn.execute_batch("DELETE FROM tags;")?;
+        for meta in &metas {
+            conn.execute(
+                "UPDATE photos SET is_hidden = ?2, is_favorite = ?3 WHERE id = ?1",
+                params![meta.id, meta.is_hidden as i64, meta.is_favorite as i64],
+            )?;
+            for tag in &meta.tags {
+                conn.execute(
+                    "INSERT OR IGNORE INTO tags (photo_id, tag) VALUES (?1, ?2)",
+                    params![meta.id, tag],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // APPEND-ONLY OPERATION LOG
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Append an immutable, timestamped op and, every
+    /// [`CHECKPOINT_INTERVAL`] ops, snapshot a full-state checkpoint.
+    fn append_op(&self, op: IndexOp) -> VaultResult<()> {
+        let wall_ms = Utc::now().timestamp_millis().max(0) as u64;
+        let ts = self.clock.lock().next(wall_ms);
+        let logged = LoggedOp { ts: ts.clone(), op };
+        let blob = encrypt_xchacha(self.keys.index_key(), &serde_json::to_vec(&logged)?, &[])?
+            .to_bytes();
+
+        let total: u64 = {
+            let conn = self.conn.lock();
+            conn.execute(
+                "INSERT OR IGNORE INTO ops (wall_ms, counter, actor, data) \
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![ts.wall_ms as i64, ts.counter as i64, ts.actor, blob],
+            )?;
+            conn.query_row("SELECT COUNT(*) FROM ops", [], |r| r.get::<_, i64>(0))? as u64
+        };
+
+        if total % CHECKPOINT_INTERVAL == 0 {
+            self.write_checkpoint(&ts)?;
+        }
+        Ok(())
+    }
+
+    /// Persist an encrypted full-state checkpoint tagged with `ts` (the
+    /// timestamp of the last op it includes).
+    fn write_checkpoint(&self, ts: &HybridTimestamp) -> VaultResult<()> {
+        let photos = self.list_all()?;
+        let checkpoint = IndexCheckpoint {
+            ts: ts.clone(),
+            photos,
+        };
+        let blob = encrypt_xchacha(self.keys.index_key(), &serde_json::to_vec(&checkpoint)?, &[])?
+            .to_bytes();
+        let conn = self.conn.lock();
+        conn.execute(
+            "INSERT OR REPLACE INTO checkpoints (wall_ms, counter, actor, data) \
+             VALUES (?1, ?2, ?3, ?4)",
+            params![ts.wall_ms as i64, ts.counter as i64, ts.actor, blob],
+        )?;
+        Ok(())
+    }
+
+    /// Load the newest checkpoint, if any.
+    fn load_newest_checkpoint(&self) -> VaultResult<Option<IndexCheckpoint>> {
+        let conn = self.conn.lock();
+        let blob: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT data FROM checkpoints \
+                 ORDER BY wall_ms DESC, counter DESC, actor DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+        drop(conn);
+        match blob {
+            Some(bytes) => {
+                let encrypted = EncryptedData::parse(&bytes)?;
+                let json = decrypt_xchacha(self.keys.index_key(), &encrypted, &[])?;
+                Ok(Some(serde_json::from_slice(&json)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Load every logged op with a timestamp strictly greater than `after`,
+    /// sorted into deterministic total order.
+    fn ordered_ops_after(&self, after: Option<&HybridTimestamp>) -> VaultResult<Vec<LoggedOp>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("SELECT data FROM ops")?;
+        let rows = stmt.query_map([], |row| row.get::<_, Vec<u8>>(0))?;
+
+        let mut ops = Vec::new();
+        for row in rows.flatten() {
+            if let Ok(encrypted) = EncryptedData::parse(&row) {
+                if let Ok(json) = decrypt_xchacha(self.keys.index_key(), &encrypted, &[]) {
+                    if let Ok(logged) = serde_json::from_slice::<LoggedOp>(&json) {
+                        if after.map(|a| &logged.ts > a).unwrap_or(true) {
+                            ops.push(logged);
+                        }
+                    }
+                }
+            }
+        }
+        ops.sort_by(|a, b| a.ts.cmp(&b.ts));
+        Ok(ops)
+    }
+
+    /// Apply one op to the materialized view (no logging).
+    fn apply(&self, op: &IndexOp) -> VaultResult<()> {
+        match op {
+            IndexOp::AddPhoto(meta) | IndexOp::UpdateMeta(meta) => self.materialize_add(meta),
+            IndexOp::RemovePhoto { id } => self.materialize_remove(id),
+            IndexOp::AddTag { id, tag } => self.materialize_add_tag(id, tag),
+            IndexOp::RemoveTag { id, tag } => self.materialize_remove_tag(id, tag),
+        }
+    }
+
+    /// Rebuild the materialized `photos`/`tags` view from the newest checkpoint
+    /// plus every later op, replaying in timestamp order. Deterministic:
+    /// replaying the same op set always yields identical state.
+    fn replay(&self) -> VaultResult<()> {
+        // Nothing logged yet (e.g. a vault created before the op log existed):
+        // the materialized tables are authoritative, so leave them untouched.
+        {
+            let conn = self.conn.lock();
+            let op_count: i64 = conn.query_row("SELECT COUNT(*) FROM ops", [], |r| r.get(0))?;
+            let cp_count: i64 =
+                conn.query_row("SELECT COUNT(*) FROM checkpoints", [], |r| r.get(0))?;
+            if op_count == 0 && cp_count == 0 {
+                return Ok(());
+            }
+        }
+
+        let checkpoint = self.load_newest_checkpoint()?;
+
+        {
+            let conn = self.conn.lock();
+            conn.execute_batch("DELETE FROM tags; DELETE FROM photos;")?;
+        }
+
+        let base_ts = match &checkpoint {
+            Some(cp) => {
+                for meta in &cp.photos {
+                    self.materialize_add(meta)?;
+                }
+                self.clock.lock().observe(&cp.ts);
+                Some(cp.ts.clone())
+            }
+            None => None,
+        };
+
+        for logged in self.ordered_ops_after(base_ts.as_ref())? {
+            self.clock.lock().observe(&logged.ts);
+            self.apply(&logged.op)?;
+        }
+        Ok(())
+    }
+
+    /// Export the encrypted op log for transfer to another device. The op
+    /// blobs stay encrypted, so a transport only ever sees opaque bytes.
+    pub fn export_log(&self) -> VaultResult<Vec<u8>> {
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare("SELECT wall_ms, counter, actor, data FROM ops")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(OpRow {
+                wall_ms: row.get::<_, i64>(0)? as u64,
+                counter: row.get::<_, i64>(1)? as u64,
+                actor: row.get(2)?,
+                data: row.get(3)?,
+            })
+        })?;
+        let log: Vec<OpRow> = rows.flatten().collect();
+        Ok(serde_json::to_vec(&log)?)
+    }
+
+    /// Merge another device's exported log into this one, interleaving by
+    /// timestamp and re-deriving the materialized view. Returns the number of
+    /// previously-unseen ops that were merged.
+    pub fn merge(&self, other_log: &[u8]) -> VaultResult<usize> {
+        let incoming: Vec<OpRow> = serde_json::from_slice(other_log)?;
+        let mut merged = 0usize;
+        {
+            let conn = self.conn.lock();
+            for row in &incoming {
+                let changed = conn.execute(
+                    "INSERT OR IGNORE INTO ops (wall_ms, counter, actor, data) \
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![row.wall_ms as i64, row.counter as i64, row.actor, row.data],
+                )?;
+                merged += changed;
+            }
+        }
+        // Re-materialize so last-writer-wins is applied deterministically.
+        self.replay()?;
+        Ok(merged)
+    }
+
     /// Count photos
     pub fn count(&self) -> VaultResult<usize> {
         let conn = self.conn.lock();
@@ -238,7 +774,56 @@ impl PhotoIndex {
             .filter(|group| group.len() > 1)
             .collect())
     }
-    
+
+    /// Find near-duplicate photos whose perceptual hashes are within
+    /// `max_distance` bits of each other (typical 5-10 for a 64-bit pHash).
+    ///
+    /// Uses a BK-tree keyed on Hamming distance so lookups prune by the
+    /// triangle inequality instead of comparing every pair, then returns the
+    /// connected components of the near-duplicate graph. `max_distance == 0`
+    /// delegates to the exact [`find_duplicates`](Self::find_duplicates) path.
+    pub fn find_near_duplicates(&self, max_distance: u32) -> VaultResult<Vec<Vec<PhotoMeta>>> {
+        if max_distance == 0 {
+            return self.find_duplicates();
+        }
+
+        // Decode each photo's pHash into its raw bit vector.
+        let mut hashes: Vec<Vec<u8>> = Vec::new();
+        let mut metas: Vec<PhotoMeta> = Vec::new();
+        for photo in self.list_all()? {
+            if let Some(bits) = photo.phash.as_deref().and_then(decode_phash_bits) {
+                hashes.push(bits);
+                metas.push(photo);
+            }
+        }
+
+        // Index the hashes in a BK-tree, then query each for its neighbours.
+        let mut tree = BkTree::default();
+        for (i, hash) in hashes.iter().enumerate() {
+            tree.insert(hash.clone(), i);
+        }
+
+        let mut uf = UnionFind::new(metas.len());
+        for (i, hash) in hashes.iter().enumerate() {
+            for j in tree.query(hash, max_distance) {
+                if j != i {
+                    uf.union(i, j);
+                }
+            }
+        }
+
+        // Gather connected components, keeping only real groups (size > 1).
+        let mut groups: std::collections::HashMap<usize, Vec<PhotoMeta>> =
+            std::collections::HashMap::new();
+        for (i, meta) in metas.into_iter().enumerate() {
+            groups.entry(uf.find(i)).or_default().push(meta);
+        }
+        Ok(groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect())
+    }
+
     /// Get statistics
     pub fn stats(&self) -> VaultResult<IndexStats> {
         let photos = self.list_all()?;
@@ -35,26 +35,35 @@
 
 pub mod crypto;
 pub mod vault;
+pub mod chunk_store;
+pub mod blurhash;
+pub mod oplog;
 pub mod index;
 pub mod thumbs;
 pub mod secure_fs;
 pub mod biometrics;
 pub mod sync_plugin;
 pub mod ai;
+pub mod sharing;
 pub mod error;
 pub mod rotation;
+pub mod maintenance;
 pub mod api;
 pub mod photo_crypto;
 
 #[cfg(feature = "android")]
 pub mod android;
 
+#[cfg(all(feature = "fuse", unix))]
+pub mod fuse;
+
 pub use error::{VaultError, VaultResult};
 pub use vault::PhotoVault;
 pub use index::PhotoIndex;
 pub use thumbs::ThumbnailEngine;
 pub use ai::SelfHealingAI;
 pub use rotation::{RotationManager, RotationStatus};
+pub use maintenance::{MaintenanceWorker, WorkerHandle};
 pub use api::PhotoVaultApi;
 
 /// ALFA Photos Vault version
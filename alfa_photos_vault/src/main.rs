@@ -91,6 +91,92 @@ enum Commands {
         pin: String,
     },
     
+    /// Mint a shareable capability bundle for one or more photos
+    Share {
+        /// Photo IDs to share
+        #[arg(required = true)]
+        ids: Vec<String>,
+
+        /// Output bundle path (JSON)
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Time-to-live in seconds
+        #[arg(long, default_value_t = 86_400)]
+        ttl: i64,
+
+        /// PIN code
+        #[arg(short, long)]
+        pin: String,
+    },
+
+    /// Open a share bundle and decrypt its photos to a directory
+    OpenShare {
+        /// Share bundle path (JSON)
+        bundle: PathBuf,
+
+        /// Output directory
+        output: PathBuf,
+
+        /// PIN code
+        #[arg(short, long)]
+        pin: String,
+    },
+
+    /// Find near-duplicate photos by perceptual hash
+    Dedup {
+        /// PIN code
+        #[arg(short, long)]
+        pin: String,
+
+        /// Hamming-distance threshold (lower = stricter match)
+        #[arg(short, long)]
+        threshold: Option<u32>,
+    },
+
+    /// Rotate encryption keys and re-encrypt photos to the new epoch
+    Rotate {
+        /// PIN code
+        #[arg(short, long)]
+        pin: String,
+    },
+
+    /// Watch a folder and auto-import new files, re-verifying on an interval
+    Watch {
+        /// Directory to watch for new files
+        dir: PathBuf,
+
+        /// PIN code
+        #[arg(short, long)]
+        pin: String,
+
+        /// Only import files whose name matches this glob (e.g. "*.jpg")
+        #[arg(long, default_value = "*")]
+        pattern: String,
+
+        /// Seconds between directory scans for new files
+        #[arg(long, default_value_t = 2)]
+        poll_every_n_seconds: u64,
+
+        /// Seconds between index integrity sweeps
+        #[arg(long, default_value_t = 300)]
+        reindex_every_n_seconds: u64,
+
+        /// Delete the source file after import instead of moving it aside
+        #[arg(long)]
+        delete_source: bool,
+    },
+
+    /// Mount the vault as a read-only FUSE filesystem
+    Mount {
+        /// Mount point directory
+        mountpoint: PathBuf,
+
+        /// PIN code
+        #[arg(short, long)]
+        pin: String,
+    },
+
     /// Demo mode (create sample vault)
     Demo,
 }
@@ -220,6 +306,177 @@ fn run(cli: Cli) -> VaultResult<()> {
             println!("Overhead:         {:.1}%", (encrypted as f64 / total.max(1) as f64 - 1.0) * 100.0);
         }
         
+        Commands::Share { ids, output, ttl, pin } => {
+            println!("🔗 Creating share for {} photo(s)...", ids.len());
+            let vault = PhotoVault::open(&cli.vault)?;
+            vault.unlock(&pin)?;
+
+            let bundle = vault.create_share(&ids, ttl)?;
+            let json = serde_json::to_string_pretty(&bundle)?;
+            std::fs::write(&output, json)?;
+            println!("✅ Share bundle written to: {}", output.display());
+            println!("   Expires (unix): {}", bundle.token.expiry_unix);
+        }
+
+        Commands::OpenShare { bundle, output, pin } => {
+            println!("📂 Opening share bundle: {}", bundle.display());
+            let vault = PhotoVault::open(&cli.vault)?;
+            vault.unlock(&pin)?;
+
+            let data = std::fs::read_to_string(&bundle)?;
+            let bundle: alfa_photos_vault::sharing::ShareBundle = serde_json::from_str(&data)?;
+            let photos = vault.open_share(&bundle)?;
+
+            std::fs::create_dir_all(&output)?;
+            for (id, bytes) in &photos {
+                let path = output.join(format!("{}.bin", id));
+                std::fs::write(&path, bytes)?;
+                println!("   {} → {}", id, path.display());
+            }
+            println!("✅ Extracted {} photo(s)", photos.len());
+        }
+
+        Commands::Dedup { pin, threshold } => {
+            let vault = PhotoVault::open(&cli.vault)?;
+            vault.unlock(&pin)?;
+
+            let clusters = vault.find_duplicates(threshold)?;
+            if clusters.is_empty() {
+                println!("✨ No near-duplicates found");
+            } else {
+                let reclaimable: u64 = clusters.iter().map(|c| c.reclaimable_bytes).sum();
+                println!("🔁 Found {} duplicate group(s):", clusters.len());
+                println!("{:-<60}", "");
+                for (i, cluster) in clusters.iter().enumerate() {
+                    println!(
+                        "Group {} ({} photos, {} KB reclaimable):",
+                        i + 1,
+                        cluster.photos.len(),
+                        cluster.reclaimable_bytes / 1024
+                    );
+                    for photo in &cluster.photos {
+                        println!("   {} - {} ({} bytes)", photo.id, photo.original_name, photo.original_size);
+                    }
+                }
+                println!("{:-<60}", "");
+                println!("💾 Total reclaimable: {} MB", reclaimable / 1024 / 1024);
+            }
+        }
+
+        Commands::Rotate { pin } => {
+            println!("🔑 Rotating vault keys...");
+            let vault = PhotoVault::open(&cli.vault)?;
+            vault.unlock(&pin)?;
+
+            let report = vault.rotate_keys()?;
+
+            println!("✅ Rotation complete!");
+            println!("   New epoch:           {}", report.new_epoch);
+            println!("   Photos re-encrypted: {}", report.photos_reencrypted);
+            println!("   Thumbs re-encrypted: {}", report.thumbs_reencrypted);
+            println!("   Bytes rewritten:     {}", report.bytes_rewritten);
+        }
+
+        Commands::Watch {
+            dir,
+            pin,
+            pattern,
+            poll_every_n_seconds,
+            reindex_every_n_seconds,
+            delete_source,
+        } => {
+            use std::sync::atomic::{AtomicBool, Ordering};
+            use std::sync::Arc;
+            use std::time::{Duration, Instant};
+
+            println!("👁️  Watching {} for new files matching '{}'", dir.display(), pattern);
+            let vault = PhotoVault::open(&cli.vault)?;
+            vault.unlock(&pin)?;
+            println!("✅ Vault unlocked. Press Ctrl-C to stop.");
+
+            // Files imported successfully are moved here unless --delete-source.
+            let imported_dir = dir.join(".imported");
+
+            let running = Arc::new(AtomicBool::new(true));
+            {
+                let running = running.clone();
+                ctrlc::set_handler(move || running.store(false, Ordering::SeqCst))
+                    .map_err(|e| alfa_photos_vault::VaultError::AiError(e.to_string()))?;
+            }
+
+            let poll = Duration::from_secs(poll_every_n_seconds.max(1));
+            let reindex = Duration::from_secs(reindex_every_n_seconds.max(1));
+            let mut last_reindex = Instant::now();
+
+            while running.load(Ordering::SeqCst) {
+                // Immediate(ish) imports: pull in anything new that matches.
+                let entries = std::fs::read_dir(&dir).ok().into_iter().flatten().flatten();
+                for entry in entries {
+                    let path = entry.path();
+                    if !path.is_file() {
+                        continue;
+                    }
+                    let name = match path.file_name().and_then(|n| n.to_str()) {
+                        Some(n) => n.to_string(),
+                        None => continue,
+                    };
+                    if name.starts_with('.') || !glob_match(&pattern, &name) {
+                        continue;
+                    }
+
+                    match vault.import_photo(&path, &name) {
+                        Ok(id) => {
+                            println!("📥 Imported {} → {}", name, id);
+                            if delete_source {
+                                let _ = std::fs::remove_file(&path);
+                            } else {
+                                let _ = std::fs::create_dir_all(&imported_dir);
+                                let _ = std::fs::rename(&path, imported_dir.join(&name));
+                            }
+                        }
+                        Err(e) => eprintln!("⚠️  Failed to import {}: {}", name, e),
+                    }
+                }
+
+                // Periodic integrity sweep, reusing the self-healing report.
+                if last_reindex.elapsed() >= reindex {
+                    let report = vault.reset()?;
+                    println!(
+                        "🩺 Reindex: {} thumbs cleared, {} index errors, {} AI fixes{}",
+                        report.thumbs_cleared,
+                        report.index_errors,
+                        report.ai_fixes,
+                        if report.is_healthy() { " (healthy)" } else { "" },
+                    );
+                    last_reindex = Instant::now();
+                }
+
+                std::thread::sleep(poll);
+            }
+
+            vault.lock();
+            println!("🔒 Stopped and re-locked.");
+        }
+
+        Commands::Mount { mountpoint, pin } => {
+            #[cfg(all(feature = "fuse", unix))]
+            {
+                use std::sync::Arc;
+                println!("🗂️  Mounting vault at: {}", mountpoint.display());
+                let vault = PhotoVault::open(&cli.vault)?;
+                vault.unlock(&pin)?;
+                println!("✅ Mounted read-only. Press Ctrl-C to unmount.");
+                alfa_photos_vault::fuse::mount(Arc::new(vault), &mountpoint)?;
+                println!("🔒 Unmounted and re-locked.");
+            }
+            #[cfg(not(all(feature = "fuse", unix)))]
+            {
+                let _ = (mountpoint, pin);
+                eprintln!("This build was compiled without FUSE support (enable the `fuse` feature on Unix).");
+                std::process::exit(1);
+            }
+        }
+
         Commands::Demo => {
             println!("🎮 ALFA Photos Vault - Demo Mode");
             println!("{:-<40}", "");
@@ -244,6 +501,40 @@ fn run(cli: Cli) -> VaultResult<()> {
             println!("  alfa-photos --vault ./demo_vault stats -p 1234");
         }
     }
-    
+
     Ok(())
 }
+
+/// Case-insensitive glob match supporting `*` (any run) and `?` (one char).
+///
+/// Deliberately tiny — the watch folder only ever needs patterns like `*.jpg`
+/// or `IMG_????.heic`, so pulling in a full glob dependency isn't warranted.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pat: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+    let txt: Vec<char> = name.to_ascii_lowercase().chars().collect();
+
+    // Iterative backtracking matcher: `star` remembers the last `*` position to
+    // retry from, `mark` the text position it was first tried against.
+    let (mut p, mut t) = (0usize, 0usize);
+    let (mut star, mut mark): (Option<usize>, usize) = (None, 0);
+    while t < txt.len() {
+        if p < pat.len() && (pat[p] == '?' || pat[p] == txt[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pat.len() && pat[p] == '*' {
+            star = Some(p);
+            mark = t;
+            p += 1;
+        } else if let Some(s) = star {
+            p = s + 1;
+            mark += 1;
+            t = mark;
+        } else {
+            return false;
+        }
+    }
+    while p < pat.len() && pat[p] == '*' {
+        p += 1;
+    }
+    p == pat.len()
+}
@@ -0,0 +1,294 @@
+//! ALFA Photos Vault - Background Maintenance Worker
+//!
+//! Set-and-forget vault upkeep: a dedicated thread that drives the
+//! [`SelfHealingAI`] learner and [`RotationManager`] on a cadence so the
+//! application never has to remember to poll `heal()` or `needs_rotation()`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use parking_lot::{Condvar, Mutex, RwLock};
+
+use crate::ai::SelfHealingAI;
+use crate::error::VaultResult;
+use crate::rotation::RotationManager;
+
+/// Worker scheduling configuration.
+#[derive(Debug, Clone)]
+pub struct MaintenanceConfig {
+    /// How often the loop wakes to evaluate pending jobs.
+    pub tick_interval: Duration,
+    /// How often `learn()` is run.
+    pub learn_every: Duration,
+    /// How often `heal()` is run.
+    pub heal_every: Duration,
+    /// Whether an overdue rotation triggers `rotate()` automatically. When
+    /// disabled the worker still fires the warning callback.
+    pub auto_rotate: bool,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            tick_interval: Duration::from_secs(60),
+            learn_every: Duration::from_secs(60 * 60),
+            heal_every: Duration::from_secs(24 * 60 * 60),
+            auto_rotate: true,
+        }
+    }
+}
+
+/// Outcome of a single job, recorded for status queries.
+#[derive(Debug, Clone, Default)]
+pub struct JobStatus {
+    /// Timestamp of the last successful run, if any.
+    pub last_run: Option<DateTime<Utc>>,
+    /// Error string from the most recent failed run, cleared on success.
+    pub last_error: Option<String>,
+}
+
+/// Snapshot of the worker's per-job bookkeeping.
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceStatus {
+    pub learn: JobStatus,
+    pub heal: JobStatus,
+    pub rotate: JobStatus,
+}
+
+/// Callback fired when a rotation is due soon but not yet performed.
+pub type WarnCallback = Arc<dyn Fn(i64) + Send + Sync>;
+
+/// Background maintenance worker.
+///
+/// Holds shared references to the two subsystems it services and tracks when
+/// each job last ran so the loop can space them out independently of the tick
+/// interval.
+pub struct MaintenanceWorker {
+    ai: Arc<SelfHealingAI>,
+    rotation: Arc<RotationManager>,
+    config: MaintenanceConfig,
+    status: Arc<RwLock<MaintenanceStatus>>,
+    warn_callback: Option<WarnCallback>,
+    /// Wall-clock of the previous learn/heal runs, tracked as chrono instants
+    /// so cadence survives across `run_once` calls made from tests.
+    last_learn: RwLock<Option<DateTime<Utc>>>,
+    last_heal: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl MaintenanceWorker {
+    /// Create a worker with the default schedule.
+    pub fn new(ai: Arc<SelfHealingAI>, rotation: Arc<RotationManager>) -> Self {
+        Self::with_config(ai, rotation, MaintenanceConfig::default())
+    }
+
+    /// Create a worker with an explicit schedule.
+    pub fn with_config(
+        ai: Arc<SelfHealingAI>,
+        rotation: Arc<RotationManager>,
+        config: MaintenanceConfig,
+    ) -> Self {
+        Self {
+            ai,
+            rotation,
+            config,
+            status: Arc::new(RwLock::new(MaintenanceStatus::default())),
+            warn_callback: None,
+            last_learn: RwLock::new(None),
+            last_heal: RwLock::new(None),
+        }
+    }
+
+    /// Register a callback invoked (with the days remaining) when a rotation is
+    /// within the warning window but auto-rotation did not run.
+    pub fn on_rotation_warning(&mut self, callback: WarnCallback) {
+        self.warn_callback = Some(callback);
+    }
+
+    /// Current status of each job.
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.read().clone()
+    }
+
+    /// Run every job whose cadence has elapsed exactly once.
+    ///
+    /// Exposed for tests and for callers who prefer to drive maintenance from
+    /// their own scheduler. Errors are recorded per-job rather than propagated
+    /// so one failing subsystem never stalls the others.
+    pub fn run_once(&self) {
+        let now = Utc::now();
+
+        if due(&self.last_learn.read(), now, self.config.learn_every) {
+            self.record("learn", now, self.ai.learn().map(|_| ()));
+            *self.last_learn.write() = Some(now);
+        }
+
+        if due(&self.last_heal.read(), now, self.config.heal_every) {
+            self.record("heal", now, self.ai.heal().map(|_| ()));
+            *self.last_heal.write() = Some(now);
+        }
+
+        if self.rotation.needs_rotation() {
+            if self.config.auto_rotate {
+                self.record("rotate", now, self.rotation.rotate().map(|_| ()));
+            } else if let Some(cb) = &self.warn_callback {
+                cb(self.rotation.days_until_rotation());
+            }
+        } else if self.rotation.should_warn() {
+            if let Some(cb) = &self.warn_callback {
+                cb(self.rotation.days_until_rotation());
+            }
+        }
+    }
+
+    /// Record the outcome of a job into the shared status.
+    fn record(&self, job: &str, now: DateTime<Utc>, result: VaultResult<()>) {
+        let mut status = self.status.write();
+        let slot = match job {
+            "learn" => &mut status.learn,
+            "heal" => &mut status.heal,
+            _ => &mut status.rotate,
+        };
+        match result {
+            Ok(()) => {
+                slot.last_run = Some(now);
+                slot.last_error = None;
+            }
+            Err(e) => {
+                slot.last_error = Some(e.to_string());
+                log::warn!("maintenance job '{}' failed: {}", job, e);
+            }
+        }
+    }
+
+    /// Spawn the background loop, returning a handle that stops it on `stop()`.
+    pub fn start(self) -> WorkerHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let wake = Arc::new((Mutex::new(()), Condvar::new()));
+        let status = Arc::clone(&self.status);
+
+        let thread_stop = Arc::clone(&stop);
+        let thread_wake = Arc::clone(&wake);
+        let tick = self.config.tick_interval;
+
+        let handle = thread::Builder::new()
+            .name("alfa-maintenance".to_string())
+            .spawn(move || {
+                while !thread_stop.load(Ordering::Acquire) {
+                    self.run_once();
+
+                    // Sleep for one tick, but wake early when asked to stop.
+                    let (lock, cvar) = &*thread_wake;
+                    let mut guard = lock.lock();
+                    if !thread_stop.load(Ordering::Acquire) {
+                        cvar.wait_for(&mut guard, tick);
+                    }
+                }
+            })
+            .expect("spawn maintenance thread");
+
+        WorkerHandle {
+            stop,
+            wake,
+            handle: Some(handle),
+            status,
+        }
+    }
+}
+
+/// Whether a job whose last run was `last` is due again after `interval`.
+fn due(last: &Option<DateTime<Utc>>, now: DateTime<Utc>, interval: Duration) -> bool {
+    match last {
+        None => true,
+        Some(prev) => now.signed_duration_since(*prev).to_std().unwrap_or_default() >= interval,
+    }
+}
+
+/// Handle to a running [`MaintenanceWorker`]; stops and joins the thread on
+/// `stop()` or on drop.
+pub struct WorkerHandle {
+    stop: Arc<AtomicBool>,
+    wake: Arc<(Mutex<()>, Condvar)>,
+    handle: Option<JoinHandle<()>>,
+    status: Arc<RwLock<MaintenanceStatus>>,
+}
+
+impl WorkerHandle {
+    /// Current status of each job.
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.read().clone()
+    }
+
+    /// Signal the loop to stop and join the thread cleanly.
+    pub fn stop(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        self.wake.1.notify_all();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for WorkerHandle {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::VaultKey;
+    use crate::rotation::RotationPolicy;
+
+    fn worker(dir: &std::path::Path, config: MaintenanceConfig) -> MaintenanceWorker {
+        let ai = Arc::new(SelfHealingAI::new(dir).unwrap());
+        let rotation = Arc::new(RotationManager::load_or_create(dir).unwrap());
+        rotation.set_master_key(VaultKey::generate());
+        MaintenanceWorker::with_config(ai, rotation, config)
+    }
+
+    #[test]
+    fn test_run_once_learns_and_heals() {
+        let dir = tempfile::tempdir().unwrap();
+        let w = worker(dir.path(), MaintenanceConfig::default());
+        w.run_once();
+
+        let status = w.status();
+        assert!(status.learn.last_run.is_some());
+        assert!(status.heal.last_run.is_some());
+    }
+
+    #[test]
+    fn test_run_once_rotates_when_due() {
+        let dir = tempfile::tempdir().unwrap();
+        let w = worker(dir.path(), MaintenanceConfig::default());
+        // Force an immediately-overdue rotation policy.
+        w.rotation
+            .update_policy(RotationPolicy { rotation_interval_days: 0, ..Default::default() })
+            .unwrap();
+
+        let before = w.rotation.current_epoch();
+        w.run_once();
+        assert_eq!(w.rotation.current_epoch(), before + 1);
+        assert!(w.status().rotate.last_run.is_some());
+    }
+
+    #[test]
+    fn test_start_stop_joins_cleanly() {
+        let dir = tempfile::tempdir().unwrap();
+        let w = worker(dir.path(), MaintenanceConfig {
+            tick_interval: Duration::from_millis(10),
+            ..Default::default()
+        });
+        let handle = w.start();
+        thread::sleep(Duration::from_millis(50));
+        handle.stop();
+    }
+}
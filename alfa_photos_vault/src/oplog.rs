@@ -0,0 +1,435 @@
+//! ALFA Photos Vault - Encrypted Operation Log
+//!
+//! A server-less sync layer: every mutating operation is modeled as an
+//! immutable [`Op`] ordered by a [`HybridTimestamp`] and appended to an
+//! encrypted, append-only log. Two devices sharing a vault converge by
+//! exchanging ops through a [`BlobStore`] and replaying them deterministically
+//! (last-writer-wins per field, deletes tombstone the photo id). Periodic
+//! [`Checkpoint`]s snapshot the resolved state so replay never has to start
+//! from the beginning of time.
+//!
+//! Each op and checkpoint blob is encrypted individually with the vault's data
+//! key before it reaches a [`BlobStore`], so a sync backend only ever sees
+//! opaque bytes.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VaultResult;
+use crate::vault::PhotoMeta;
+
+/// Write a checkpoint at least every this many appended ops.
+pub const CHECKPOINT_INTERVAL: u64 = 64;
+
+/// Hybrid logical timestamp: wall-clock millis, a per-actor counter that breaks
+/// ties within the same millisecond, and the actor id as a final tiebreaker so
+/// ordering is total and identical on every device.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct HybridTimestamp {
+    /// Wall-clock milliseconds since the Unix epoch.
+    pub wall_ms: u64,
+    /// Monotonic per-actor counter.
+    pub counter: u64,
+    /// Originating actor id.
+    pub actor: String,
+}
+
+/// Monotonic hybrid-logical clock for a single actor.
+pub struct HybridClock {
+    actor: String,
+    last_wall: u64,
+    counter: u64,
+}
+
+impl HybridClock {
+    /// Create a clock for `actor`.
+    pub fn new(actor: impl Into<String>) -> Self {
+        Self {
+            actor: actor.into(),
+            last_wall: 0,
+            counter: 0,
+        }
+    }
+
+    /// Issue the next timestamp given the current wall-clock reading in millis.
+    ///
+    /// The wall component never moves backward; within the same (or an earlier)
+    /// millisecond the counter increments so successive stamps stay ordered.
+    pub fn next(&mut self, wall_ms: u64) -> HybridTimestamp {
+        if wall_ms > self.last_wall {
+            self.last_wall = wall_ms;
+            self.counter = 0;
+        } else {
+            self.counter += 1;
+        }
+        HybridTimestamp {
+            wall_ms: self.last_wall,
+            counter: self.counter,
+            actor: self.actor.clone(),
+        }
+    }
+
+    /// Advance the clock past a timestamp observed from another actor so our
+    /// next stamp is causally after it.
+    pub fn observe(&mut self, ts: &HybridTimestamp) {
+        if ts.wall_ms > self.last_wall {
+            self.last_wall = ts.wall_ms;
+            self.counter = ts.counter;
+        } else if ts.wall_ms == self.last_wall {
+            self.counter = self.counter.max(ts.counter);
+        }
+    }
+}
+
+/// A single mutating operation's payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OpPayload {
+    /// Import a photo, carrying its full metadata.
+    ImportPhoto(Box<PhotoMeta>),
+    /// Delete a photo (tombstones the id).
+    DeletePhoto { id: String },
+    /// Add a tag to a photo.
+    AddTag { id: String, tag: String },
+    /// Remove a tag from a photo.
+    RemoveTag { id: String, tag: String },
+    /// Set the favorite flag.
+    SetFavorite { id: String, value: bool },
+    /// Set the hidden flag.
+    SetHidden { id: String, value: bool },
+}
+
+impl OpPayload {
+    /// The photo id this payload targets.
+    pub fn photo_id(&self) -> &str {
+        match self {
+            OpPayload::ImportPhoto(meta) => &meta.id,
+            OpPayload::DeletePhoto { id }
+            | OpPayload::AddTag { id, .. }
+            | OpPayload::RemoveTag { id, .. }
+            | OpPayload::SetFavorite { id, .. }
+            | OpPayload::SetHidden { id, .. } => id,
+        }
+    }
+}
+
+/// An immutable, totally-ordered operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Op {
+    /// Ordering timestamp.
+    pub ts: HybridTimestamp,
+    /// Originating actor id.
+    pub actor_id: String,
+    /// What the op does.
+    pub payload: OpPayload,
+}
+
+/// A full snapshot of the resolved index state at a point in the op stream.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Timestamp of the last op folded into this checkpoint.
+    pub ts: Option<HybridTimestamp>,
+    /// Live photos keyed by id.
+    pub photos: Vec<PhotoMeta>,
+    /// Tombstoned photo ids.
+    pub tombstones: Vec<String>,
+}
+
+/// The resolved state produced by replaying ops on top of a checkpoint.
+#[derive(Debug, Default)]
+pub struct ResolvedState {
+    photos: BTreeMap<String, PhotoMeta>,
+    tombstones: HashSet<String>,
+}
+
+impl ResolvedState {
+    /// Start from a checkpoint (or empty when `None`).
+    pub fn from_checkpoint(checkpoint: Option<&Checkpoint>) -> Self {
+        let mut state = Self::default();
+        if let Some(cp) = checkpoint {
+            for meta in &cp.photos {
+                state.photos.insert(meta.id.clone(), meta.clone());
+            }
+            state.tombstones.extend(cp.tombstones.iter().cloned());
+        }
+        state
+    }
+
+    /// Apply one op. A deleted id is tombstoned so a later-but-concurrent
+    /// tag-add cannot resurrect it.
+    pub fn apply(&mut self, op: &Op) {
+        let id = op.payload.photo_id();
+        if self.tombstones.contains(id) && !matches!(op.payload, OpPayload::DeletePhoto { .. }) {
+            return;
+        }
+        match &op.payload {
+            OpPayload::ImportPhoto(meta) => {
+                self.photos.entry(meta.id.clone()).or_insert_with(|| (**meta).clone());
+            }
+            OpPayload::DeletePhoto { id } => {
+                self.photos.remove(id);
+                self.tombstones.insert(id.clone());
+            }
+            OpPayload::AddTag { id, tag } => {
+                if let Some(meta) = self.photos.get_mut(id) {
+                    if !meta.tags.contains(tag) {
+                        meta.tags.push(tag.clone());
+                    }
+                }
+            }
+            OpPayload::RemoveTag { id, tag } => {
+                if let Some(meta) = self.photos.get_mut(id) {
+                    meta.tags.retain(|t| t != tag);
+                }
+            }
+            OpPayload::SetFavorite { id, value } => {
+                if let Some(meta) = self.photos.get_mut(id) {
+                    meta.is_favorite = *value;
+                }
+            }
+            OpPayload::SetHidden { id, value } => {
+                if let Some(meta) = self.photos.get_mut(id) {
+                    meta.is_hidden = *value;
+                }
+            }
+        }
+    }
+
+    /// Fold an ordered slice of ops into the state.
+    pub fn apply_all<'a>(&mut self, ops: impl IntoIterator<Item = &'a Op>) {
+        for op in ops {
+            self.apply(op);
+        }
+    }
+
+    /// Live photos in id order.
+    pub fn photos(&self) -> impl Iterator<Item = &PhotoMeta> {
+        self.photos.values()
+    }
+
+    /// Tombstoned ids.
+    pub fn tombstones(&self) -> impl Iterator<Item = &String> {
+        self.tombstones.iter()
+    }
+
+    /// Materialize a checkpoint tagged with `ts`.
+    pub fn to_checkpoint(&self, ts: Option<HybridTimestamp>) -> Checkpoint {
+        Checkpoint {
+            ts,
+            photos: self.photos.values().cloned().collect(),
+            tombstones: self.tombstones.iter().cloned().collect(),
+        }
+    }
+}
+
+/// The local, in-memory view of the op stream for one actor: its clock and
+/// the full set of ops it has seen (local and replayed-from-remote).
+pub struct OpLog {
+    clock: HybridClock,
+    actor_id: String,
+    ops: Vec<Op>,
+    seen: HashSet<(u64, u64, String)>,
+}
+
+impl OpLog {
+    /// Create an empty log for `actor_id`.
+    pub fn new(actor_id: impl Into<String>) -> Self {
+        let actor_id = actor_id.into();
+        Self {
+            clock: HybridClock::new(actor_id.clone()),
+            actor_id,
+            ops: Vec::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Rebuild a log from previously-persisted ops.
+    pub fn load(actor_id: impl Into<String>, ops: Vec<Op>) -> Self {
+        let mut log = Self::new(actor_id);
+        log.merge(ops);
+        log
+    }
+
+    /// The actor id this log writes under.
+    pub fn actor_id(&self) -> &str {
+        &self.actor_id
+    }
+
+    /// Record a new local op stamped at `wall_ms`.
+    pub fn record(&mut self, wall_ms: u64, payload: OpPayload) -> Op {
+        let ts = self.clock.next(wall_ms);
+        let op = Op {
+            ts: ts.clone(),
+            actor_id: self.actor_id.clone(),
+            payload,
+        };
+        self.seen.insert((ts.wall_ms, ts.counter, ts.actor.clone()));
+        self.ops.push(op.clone());
+        op
+    }
+
+    /// Merge ops observed from elsewhere, skipping ones already seen and
+    /// advancing the clock past them. Returns the number of new ops added.
+    pub fn merge(&mut self, incoming: impl IntoIterator<Item = Op>) -> usize {
+        let mut added = 0;
+        for op in incoming {
+            let key = (op.ts.wall_ms, op.ts.counter, op.ts.actor.clone());
+            if self.seen.insert(key) {
+                self.clock.observe(&op.ts);
+                self.ops.push(op);
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// All known ops, sorted into total timestamp order.
+    pub fn ordered(&self) -> Vec<Op> {
+        let mut ops = self.ops.clone();
+        ops.sort_by(|a, b| a.ts.cmp(&b.ts));
+        ops
+    }
+
+    /// Ops with a timestamp strictly greater than `after`, in order.
+    pub fn ops_since(&self, after: Option<&HybridTimestamp>) -> Vec<Op> {
+        self.ordered()
+            .into_iter()
+            .filter(|op| after.map(|t| &op.ts > t).unwrap_or(true))
+            .collect()
+    }
+}
+
+/// A pluggable blob backend addressed by opaque string keys. Backends only
+/// ever see individually-encrypted blobs.
+pub trait BlobStore {
+    /// List keys under `prefix`.
+    fn list(&self, prefix: &str) -> VaultResult<Vec<String>>;
+    /// Fetch the blob stored at `key`.
+    fn fetch(&self, key: &str) -> VaultResult<Vec<u8>>;
+    /// Store `data` at `key` (overwriting any existing blob).
+    fn put(&self, key: &str, data: &[u8]) -> VaultResult<()>;
+}
+
+/// A [`BlobStore`] backed by a local directory.
+pub struct LocalDirBlobStore {
+    root: PathBuf,
+}
+
+impl LocalDirBlobStore {
+    /// Create a store rooted at `root`, creating the directory if needed.
+    pub fn new(root: impl Into<PathBuf>) -> VaultResult<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+}
+
+impl BlobStore for LocalDirBlobStore {
+    fn list(&self, prefix: &str) -> VaultResult<Vec<String>> {
+        let mut keys = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if let Some(name) = entry.file_name().to_str() {
+                if name.starts_with(prefix) {
+                    keys.push(name.to_string());
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn fetch(&self, key: &str) -> VaultResult<Vec<u8>> {
+        Ok(std::fs::read(self.root.join(key))?)
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> VaultResult<()> {
+        std::fs::write(self.root.join(key), data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(wall: u64, counter: u64, actor: &str) -> HybridTimestamp {
+        HybridTimestamp {
+            wall_ms: wall,
+            counter,
+            actor: actor.into(),
+        }
+    }
+
+    fn meta(id: &str) -> PhotoMeta {
+        PhotoMeta {
+            id: id.into(),
+            original_name: String::new(),
+            encrypted_size: 0,
+            original_size: 0,
+            mime_type: "image/jpeg".into(),
+            imported_at: chrono::Utc::now(),
+            created_at: None,
+            camera_make: None,
+            camera_model: None,
+            gps_latitude: None,
+            gps_longitude: None,
+            orientation: None,
+            hmac: [0u8; 32],
+            tags: Vec::new(),
+            is_hidden: false,
+            is_favorite: false,
+            phash: None,
+            dhash: None,
+            blurhash: None,
+            key_id: None,
+            chunks: Vec::new(),
+            key_epoch: 0,
+            gps_removed: false,
+            streamed: false,
+        }
+    }
+
+    #[test]
+    fn test_clock_is_monotonic() {
+        let mut clock = HybridClock::new("a");
+        let t1 = clock.next(100);
+        let t2 = clock.next(100); // same millisecond
+        let t3 = clock.next(50); // wall went backwards
+        assert!(t1 < t2);
+        assert!(t2 < t3);
+    }
+
+    #[test]
+    fn test_total_ordering_across_actors() {
+        assert!(ts(1, 0, "a") < ts(1, 0, "b"));
+        assert!(ts(1, 5, "z") < ts(2, 0, "a"));
+    }
+
+    #[test]
+    fn test_delete_tombstones_against_concurrent_tag() {
+        let mut state = ResolvedState::default();
+        state.apply(&Op {
+            ts: ts(1, 0, "a"),
+            actor_id: "a".into(),
+            payload: OpPayload::ImportPhoto(Box::new(meta("p1"))),
+        });
+        state.apply(&Op {
+            ts: ts(2, 0, "a"),
+            actor_id: "a".into(),
+            payload: OpPayload::DeletePhoto { id: "p1".into() },
+        });
+        // A concurrent tag-add ordered after the delete must not resurrect it.
+        state.apply(&Op {
+            ts: ts(3, 0, "b"),
+            actor_id: "b".into(),
+            payload: OpPayload::AddTag {
+                id: "p1".into(),
+                tag: "x".into(),
+            },
+        });
+        assert_eq!(state.photos().count(), 0);
+        assert_eq!(state.tombstones().count(), 1);
+    }
+}
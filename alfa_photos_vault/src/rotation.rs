@@ -3,14 +3,18 @@
 //! Automatic key rotation with 90-day policy (configurable).
 //! Integrates with ALFA_KEYVAULT for coordinated rotation.
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc, Duration};
 use serde::{Deserialize, Serialize};
 use parking_lot::RwLock;
 
-use crate::crypto::KeyManager;
+use crate::crypto::{derive_epoch_key, EncryptionMethod, VaultKey, KEY_LEN};
 use crate::error::{VaultError, VaultResult};
 
+/// AEAD method used to wrap per-epoch data keys under the master key.
+const WRAP_METHOD: EncryptionMethod = EncryptionMethod::Aes256Gcm;
+
 /// Rotation policy configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RotationPolicy {
@@ -35,9 +39,15 @@ impl Default for RotationPolicy {
     }
 }
 
+/// Current on-disk schema version for `rotation.json`.
+const ROTATION_SCHEMA_VERSION: u32 = 1;
+
 /// Rotation state persisted to disk
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RotationState {
+    /// On-disk schema version (0 in pre-versioned files, migrated on load).
+    #[serde(default)]
+    pub schema_version: u32,
     /// Current epoch number
     pub current_epoch: u64,
     /// Last rotation timestamp
@@ -48,6 +58,12 @@ pub struct RotationState {
     pub policy: RotationPolicy,
     /// Previous epoch timestamps (for recovery)
     pub epoch_history: Vec<EpochRecord>,
+    /// Per-epoch data keys, each wrapped (AEAD-encrypted) under the master
+    /// key and keyed by epoch number. Only the most recent `keep_epochs`
+    /// (plus the current epoch) are retained so superseded data stays
+    /// decryptable without keeping keys forever.
+    #[serde(default)]
+    pub epoch_keys: BTreeMap<u64, Vec<u8>>,
 }
 
 /// Record of a past epoch
@@ -65,11 +81,13 @@ impl RotationState {
         let next = now + Duration::days(policy.rotation_interval_days as i64);
         
         Self {
+            schema_version: ROTATION_SCHEMA_VERSION,
             current_epoch: 1,
             last_rotation: now,
             next_rotation: next,
             policy,
             epoch_history: Vec::new(),
+            epoch_keys: BTreeMap::new(),
         }
     }
     
@@ -123,6 +141,9 @@ pub struct RotationManager {
     state_path: PathBuf,
     /// Current state
     state: RwLock<RotationState>,
+    /// Vault master key, attached after unlock; required to derive, wrap and
+    /// unwrap per-epoch keys.
+    master: RwLock<Option<VaultKey>>,
 }
 
 impl RotationManager {
@@ -132,7 +153,22 @@ impl RotationManager {
         
         let state = if state_path.exists() {
             let data = std::fs::read(&state_path)?;
-            serde_json::from_slice(&data)
+            let value: serde_json::Value = serde_json::from_slice(&data)
+                .map_err(|e| VaultError::VaultCorrupted(format!("Rotation state: {}", e)))?;
+
+            let from = value
+                .get("schema_version")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32)
+                .unwrap_or(0);
+            if from > ROTATION_SCHEMA_VERSION {
+                return Err(VaultError::VaultCorrupted(format!(
+                    "Rotation state schema version {from} is newer than supported {ROTATION_SCHEMA_VERSION}"
+                )));
+            }
+
+            let migrated = migrate_rotation(from, value);
+            serde_json::from_value(migrated)
                 .map_err(|e| VaultError::VaultCorrupted(format!("Rotation state: {}", e)))?
         } else {
             RotationState::new(RotationPolicy::default())
@@ -141,6 +177,7 @@ impl RotationManager {
         let manager = Self {
             state_path,
             state: RwLock::new(state),
+            master: RwLock::new(None),
         };
         
         manager.save()?;
@@ -156,7 +193,18 @@ impl RotationManager {
         if let Some(parent) = self.state_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        
+
+        // Preserve the prior file before rewriting, so a botched upgrade is
+        // recoverable.
+        if self.state_path.exists() {
+            if let Some(name) = self.state_path.file_name() {
+                let bak = self
+                    .state_path
+                    .with_file_name(format!("{}.bak", name.to_string_lossy()));
+                std::fs::copy(&self.state_path, bak)?;
+            }
+        }
+
         std::fs::write(&self.state_path, data)?;
         Ok(())
     }
@@ -181,19 +229,81 @@ impl RotationManager {
         self.state.read().days_until_rotation()
     }
     
-    /// Perform rotation
+    /// Attach the vault master key so rotation can derive and wrap per-epoch
+    /// keys. Called once the vault is unlocked.
+    pub fn set_master_key(&self, master: VaultKey) {
+        *self.master.write() = Some(master);
+    }
+
+    fn master_key(&self) -> VaultResult<VaultKey> {
+        self.master
+            .read()
+            .clone()
+            .ok_or(VaultError::VaultLocked)
+    }
+
+    /// Perform rotation.
+    ///
+    /// Advances the epoch, derives a fresh data key for it (HKDF from the
+    /// master key salted with the epoch number), wraps that key under the
+    /// master key, and stores it in `epoch_keys`. The oldest wrapped keys are
+    /// dropped in lock-step with `epoch_history` so only the current epoch and
+    /// the retained history remain decryptable.
     pub fn rotate(&self) -> VaultResult<u64> {
+        let master = self.master_key()?;
+
         let new_epoch = {
             let mut state = self.state.write();
-            state.rotate()
+            let new_epoch = state.rotate();
+
+            // Derive and wrap the data key for the freshly-minted epoch.
+            let epoch_key = derive_epoch_key(&master, new_epoch)?;
+            let wrapped = crate::crypto::encrypt(WRAP_METHOD, &master, epoch_key.expose())?;
+            state.epoch_keys.insert(new_epoch, wrapped);
+
+            // Keep wrapped keys for the current epoch and everything still in
+            // the (already-trimmed) history; drop the rest together.
+            let mut retain: std::collections::BTreeSet<u64> =
+                state.epoch_history.iter().map(|r| r.epoch).collect();
+            retain.insert(new_epoch);
+            state.epoch_keys.retain(|epoch, _| retain.contains(epoch));
+
+            new_epoch
         };
-        
+
         self.save()?;
-        
+
         log::info!("Key rotation complete. New epoch: {}", new_epoch);
-        
+
         Ok(new_epoch)
     }
+
+    /// Return the unwrapped data key for `epoch`.
+    ///
+    /// Uses the persisted wrapped key when present, otherwise re-derives it
+    /// from the master key (so epochs that predate wrapped-key storage, such as
+    /// the initial epoch, remain decryptable).
+    pub fn key_for_epoch(&self, epoch: u64) -> VaultResult<Vec<u8>> {
+        let master = self.master_key()?;
+        let wrapped = self.state.read().epoch_keys.get(&epoch).cloned();
+        match wrapped {
+            Some(blob) => crate::crypto::decrypt(&master, &blob),
+            None => Ok(derive_epoch_key(&master, epoch)?.expose().to_vec()),
+        }
+    }
+
+    /// Re-encrypt a blob from an old epoch to the current epoch key.
+    ///
+    /// Decrypts `data` with the `from_epoch` data key and re-encrypts it with
+    /// the current epoch key using the shared AEAD in [`crate::crypto`].
+    pub fn reencrypt_to_current(&self, from_epoch: u64, data: &[u8]) -> VaultResult<Vec<u8>> {
+        let old_key = to_vault_key(&self.key_for_epoch(from_epoch)?)?;
+        let plaintext = crate::crypto::decrypt(&old_key, data)?;
+
+        let current = self.state.read().current_epoch;
+        let new_key = to_vault_key(&self.key_for_epoch(current)?)?;
+        crate::crypto::encrypt(WRAP_METHOD, &new_key, &plaintext)
+    }
     
     /// Get rotation status
     pub fn status(&self) -> RotationStatus {
@@ -224,6 +334,34 @@ impl RotationManager {
     }
 }
 
+/// Upgrade an older `rotation.json` layout to the current schema.
+///
+/// Version 0 is the pre-versioned format: it lacks `schema_version` and
+/// `epoch_keys`. Both are defaulted in place; later migrations are added here
+/// as the schema evolves.
+fn migrate_rotation(from: u32, mut value: serde_json::Value) -> serde_json::Value {
+    if from == 0 {
+        if let Some(obj) = value.as_object_mut() {
+            obj.entry("epoch_keys")
+                .or_insert_with(|| serde_json::json!({}));
+            obj.insert(
+                "schema_version".to_string(),
+                serde_json::json!(ROTATION_SCHEMA_VERSION),
+            );
+        }
+    }
+    value
+}
+
+/// Build a [`VaultKey`] from raw unwrapped key bytes.
+fn to_vault_key(bytes: &[u8]) -> VaultResult<VaultKey> {
+    let arr: [u8; KEY_LEN] = bytes.try_into().map_err(|_| VaultError::InvalidKeyLength {
+        expected: KEY_LEN,
+        actual: bytes.len(),
+    })?;
+    Ok(VaultKey::new(arr))
+}
+
 /// Rotation status for display
 #[derive(Debug, Clone, Serialize)]
 pub struct RotationStatus {
@@ -269,4 +407,93 @@ mod tests {
         assert_eq!(state.epoch_history.len(), 2);
         assert_eq!(state.current_epoch, 6);
     }
+
+    #[test]
+    fn test_rotate_wraps_and_unwraps_epoch_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let mgr = RotationManager::load_or_create(dir.path()).unwrap();
+        mgr.set_master_key(VaultKey::generate());
+
+        let epoch = mgr.rotate().unwrap();
+        let key = mgr.key_for_epoch(epoch).unwrap();
+        assert_eq!(key.len(), KEY_LEN);
+
+        // The unwrapped key matches a direct derivation from the master.
+        let direct = derive_epoch_key(&mgr.master_key().unwrap(), epoch).unwrap();
+        assert_eq!(key, direct.expose().to_vec());
+    }
+
+    #[test]
+    fn test_reencrypt_to_current_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let mgr = RotationManager::load_or_create(dir.path()).unwrap();
+        mgr.set_master_key(VaultKey::generate());
+
+        let old_epoch = mgr.rotate().unwrap();
+        let old_key = to_vault_key(&mgr.key_for_epoch(old_epoch).unwrap()).unwrap();
+        let blob = crate::crypto::encrypt(WRAP_METHOD, &old_key, b"secret payload").unwrap();
+
+        // Rotate again, then migrate the old blob to the current epoch.
+        mgr.rotate().unwrap();
+        let migrated = mgr.reencrypt_to_current(old_epoch, &blob).unwrap();
+
+        let cur_key = to_vault_key(&mgr.key_for_epoch(mgr.current_epoch()).unwrap()).unwrap();
+        assert_eq!(crate::crypto::decrypt(&cur_key, &migrated).unwrap(), b"secret payload");
+    }
+
+    #[test]
+    fn test_old_epoch_keys_pruned_with_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let mgr = RotationManager::load_or_create(dir.path()).unwrap();
+        mgr.set_master_key(VaultKey::generate());
+        mgr.update_policy(RotationPolicy { keep_epochs: 2, ..Default::default() }).unwrap();
+
+        for _ in 0..5 {
+            mgr.rotate().unwrap();
+        }
+
+        // Wrapped keys are retained only for the current epoch plus history.
+        let state = mgr.state.read();
+        assert!(state.epoch_keys.len() <= state.policy.keep_epochs as usize + 1);
+        assert!(state.epoch_keys.contains_key(&state.current_epoch));
+    }
+
+    #[test]
+    fn test_legacy_rotation_state_migrates() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("db");
+        std::fs::create_dir_all(&db).unwrap();
+        // Pre-versioned file: no schema_version, no epoch_keys.
+        let legacy = r#"{
+            "current_epoch": 3,
+            "last_rotation": "2020-01-01T00:00:00Z",
+            "next_rotation": "2020-04-01T00:00:00Z",
+            "policy": {"rotation_interval_days":90,"auto_rotate":true,"warning_days":7,"keep_epochs":3},
+            "epoch_history": []
+        }"#;
+        std::fs::write(db.join("rotation.json"), legacy).unwrap();
+
+        let mgr = RotationManager::load_or_create(dir.path()).unwrap();
+        let state = mgr.state.read();
+        assert_eq!(state.current_epoch, 3);
+        assert_eq!(state.schema_version, ROTATION_SCHEMA_VERSION);
+        assert!(state.epoch_keys.is_empty());
+    }
+
+    #[test]
+    fn test_future_rotation_version_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("db");
+        std::fs::create_dir_all(&db).unwrap();
+        let future = format!(
+            r#"{{"schema_version": {}, "current_epoch": 1, "last_rotation": "2020-01-01T00:00:00Z", "next_rotation": "2020-04-01T00:00:00Z", "policy": {{"rotation_interval_days":90,"auto_rotate":true,"warning_days":7,"keep_epochs":3}}, "epoch_history": [], "epoch_keys": {{}}}}"#,
+            ROTATION_SCHEMA_VERSION + 1
+        );
+        std::fs::write(db.join("rotation.json"), future).unwrap();
+
+        assert!(matches!(
+            RotationManager::load_or_create(dir.path()),
+            Err(VaultError::VaultCorrupted(_))
+        ));
+    }
 }
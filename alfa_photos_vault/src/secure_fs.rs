@@ -4,24 +4,102 @@
 
 use std::path::{Path, PathBuf};
 use std::fs::{self, File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 
+use crate::crypto::{compute_hmac, VaultKey};
 use crate::error::{VaultError, VaultResult};
 
+/// Container magic for integrity-protected files.
+const CONTAINER_MAGIC: &[u8; 4] = b"ASF1";
+/// Container format version.
+const CONTAINER_VERSION: u8 = 1;
+/// Header layout: magic(4) + version(1) + ciphertext length(8, big-endian).
+const HEADER_LEN: usize = 4 + 1 + 8;
+/// HMAC-SHA256 tag length.
+const TAG_LEN: usize = 32;
+
+/// Secure deletion strategy.
+///
+/// On modern storage (SSD wear-levelling, journaling, COW filesystems such as
+/// APFS/Btrfs/ZFS) in-place overwrites do not reliably reach the physical
+/// blocks that held the data, so the only dependable option is `CryptoShred`:
+/// the file is encrypted, so erasing the per-file key renders the ciphertext
+/// unrecoverable. The overwrite modes are retained for legacy block devices.
+///
+/// Recommended per filesystem:
+/// - ext4/xfs on a plain HDD: `MultiPass`
+/// - SSD / APFS / Btrfs / ZFS / any COW or flash media: `CryptoShred`
+/// - throwaway or already-wiped storage: `ZeroFill` or `None`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SecureDeleteMode {
+    /// Just unlink, no overwrite.
+    None,
+    /// Single zero-fill pass (legacy default).
+    #[default]
+    ZeroFill,
+    /// Three passes: random → complement → zeros.
+    MultiPass,
+    /// Unlink only; assumes the associated key material has been erased.
+    CryptoShred,
+}
+
+/// One overwrite pass pattern.
+enum Pass {
+    Random,
+    Ones,
+    Zeros,
+}
+
 /// Secure Filesystem Handler
 pub struct SecureFs {
     /// Root directory
     root: PathBuf,
+    /// Secure deletion strategy applied by `delete_file`
+    delete_mode: SecureDeleteMode,
+    /// Optional MAC key enabling the authenticated container format
+    mac_key: Option<VaultKey>,
+    /// Hook invoked with the relative path when an integrity check fails.
+    ///
+    /// Intended to be wired to `VaultBrain` so a tamper attempt is recorded as
+    /// an `AccessEventType::ThreatDetected` event and escalates the threat level.
+    on_tamper: Option<Box<dyn Fn(&str) + Send + Sync>>,
 }
 
 impl SecureFs {
     /// Create new SecureFs with root directory
     pub fn new(root: &Path) -> Self {
+        // Drop any `.tmp` files left behind by a crash mid-write.
+        cleanup_orphaned_tmp(root);
         Self {
             root: root.to_path_buf(),
+            delete_mode: SecureDeleteMode::default(),
+            mac_key: None,
+            on_tamper: None,
         }
     }
-    
+
+    /// Create new SecureFs with an explicit secure-deletion strategy
+    pub fn with_delete_mode(root: &Path, delete_mode: SecureDeleteMode) -> Self {
+        let mut fs = Self::new(root);
+        fs.delete_mode = delete_mode;
+        fs
+    }
+
+    /// Create new SecureFs that authenticates every file with `mac_key`
+    pub fn new_with_key(root: &Path, mac_key: VaultKey) -> Self {
+        let mut fs = Self::new(root);
+        fs.mac_key = Some(mac_key);
+        fs
+    }
+
+    /// Register a hook fired on integrity failure (tamper detection)
+    pub fn set_tamper_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_tamper = Some(Box::new(hook));
+    }
+
     /// Get full path for a relative file
     fn full_path(&self, relative: &str) -> PathBuf {
         self.root.join(relative)
@@ -36,64 +114,200 @@ impl SecureFs {
             fs::create_dir_all(parent)?;
         }
         
+        // When a MAC key is configured, wrap the ciphertext in an authenticated
+        // container (header + body + tag); otherwise persist the bytes verbatim.
+        let payload = match &self.mac_key {
+            Some(key) => seal_container(key, data),
+            None => data.to_vec(),
+        };
+
         // Write to temp file first (atomic write)
         let temp_path = path.with_extension("tmp");
-        
+
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
             .truncate(true)
             .open(&temp_path)?;
-        
-        file.write_all(data)?;
+
+        file.write_all(&payload)?;
         file.sync_all()?;
         
         // Rename to final path (atomic on most filesystems)
         fs::rename(&temp_path, &path)?;
-        
+
+        // fsync the parent directory so the rename itself is durable; without
+        // this the directory entry can be lost on a crash on ext4/xfs.
+        if let Some(parent) = path.parent() {
+            if let Ok(dir) = File::open(parent) {
+                let _ = dir.sync_all();
+            }
+        }
+
         Ok(())
     }
     
-    /// Read encrypted file
+    /// Read encrypted file, verifying integrity when a MAC key is configured
     pub fn read_file(&self, relative_path: &str) -> VaultResult<Vec<u8>> {
         let path = self.full_path(relative_path);
-        
+
         if !path.exists() {
             return Err(VaultError::FileNotFound(path.display().to_string()));
         }
-        
+
         let mut file = File::open(&path)?;
         let mut data = Vec::new();
         file.read_to_end(&mut data)?;
-        
-        Ok(data)
+
+        match &self.mac_key {
+            Some(key) => self.open_container(key, relative_path, &data),
+            None => Ok(data),
+        }
+    }
+
+    /// Verify a file's integrity without returning its contents
+    pub fn verify_file(&self, relative_path: &str) -> VaultResult<()> {
+        self.read_file(relative_path).map(|_| ())
+    }
+
+    /// Stream-encrypt `reader` into a file, never buffering the whole plaintext.
+    ///
+    /// The payload is written in independently-authenticated 64 KiB chunks via
+    /// the STREAM construction (see [`crate::crypto::encrypt_stream`]), so
+    /// multi-gigabyte videos encrypt with bounded memory. The write is atomic:
+    /// data lands in a `.tmp` file that is fsynced and renamed into place, with
+    /// the parent directory fsynced so the rename survives a crash.
+    pub fn write_file_stream<R: Read>(
+        &self,
+        relative_path: &str,
+        key: &VaultKey,
+        reader: R,
+    ) -> VaultResult<()> {
+        let path = self.full_path(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let temp_path = path.with_extension("tmp");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)?;
+
+        crate::crypto::encrypt_stream(key, reader, &mut file)?;
+        file.sync_all()?;
+
+        fs::rename(&temp_path, &path)?;
+        if let Some(parent) = path.parent() {
+            if let Ok(dir) = File::open(parent) {
+                let _ = dir.sync_all();
+            }
+        }
+        Ok(())
+    }
+
+    /// Stream-decrypt a file written by [`write_file_stream`] into `writer`,
+    /// rejecting truncated streams without holding the full plaintext in RAM.
+    pub fn read_file_stream<W: Write>(
+        &self,
+        relative_path: &str,
+        key: &VaultKey,
+        writer: W,
+    ) -> VaultResult<()> {
+        let path = self.full_path(relative_path);
+        if !path.exists() {
+            return Err(VaultError::FileNotFound(path.display().to_string()));
+        }
+        let file = File::open(&path)?;
+        crate::crypto::decrypt_stream(key, file, writer)
+    }
+
+    /// Begin a chunk-at-a-time encrypted write to `relative_path`, for callers
+    /// driven by externally-arriving pieces (e.g. `ImportSession`, fed from
+    /// Kotlin) that can't hand over a single [`Read`] the way
+    /// [`write_file_stream`] wants. Each [`StreamWriteHandle::write_chunk`]
+    /// appends already-encrypted bytes straight to the backing `.tmp` file, so
+    /// the caller never buffers more than one chunk; [`StreamWriteHandle::finish`]
+    /// fsyncs and atomically renames it into place exactly like
+    /// [`write_file_stream`] does.
+    pub fn begin_stream_write(&self, relative_path: &str) -> VaultResult<StreamWriteHandle> {
+        let final_path = self.full_path(relative_path);
+        if let Some(parent) = final_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = final_path.with_extension("tmp");
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)?;
+        Ok(StreamWriteHandle {
+            file: Some(file),
+            temp_path,
+            final_path,
+        })
+    }
+
+    /// Parse and authenticate a container, firing the tamper hook on mismatch
+    fn open_container(
+        &self,
+        key: &VaultKey,
+        relative_path: &str,
+        raw: &[u8],
+    ) -> VaultResult<Vec<u8>> {
+        let fail = || {
+            if let Some(hook) = &self.on_tamper {
+                hook(relative_path);
+            }
+            VaultError::IntegrityFailure(relative_path.to_string())
+        };
+
+        if raw.len() < HEADER_LEN + TAG_LEN {
+            return Err(fail());
+        }
+        let (header, rest) = raw.split_at(HEADER_LEN);
+        if &header[0..4] != CONTAINER_MAGIC || header[4] != CONTAINER_VERSION {
+            return Err(fail());
+        }
+        let declared = u64::from_be_bytes(header[5..13].try_into().unwrap()) as usize;
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+        if ciphertext.len() != declared {
+            return Err(fail());
+        }
+
+        let expected = compute_hmac(key, &raw[..HEADER_LEN + ciphertext.len()]);
+        if !ct_eq(&expected, tag) {
+            return Err(fail());
+        }
+
+        Ok(ciphertext.to_vec())
     }
     
-    /// Delete file
+    /// Delete file using the configured [`SecureDeleteMode`]
     pub fn delete_file(&self, relative_path: &str) -> VaultResult<()> {
         let path = self.full_path(relative_path);
-        
-        if path.exists() {
-            // Secure delete: overwrite with zeros first
-            if let Ok(metadata) = fs::metadata(&path) {
-                let size = metadata.len() as usize;
-                if size > 0 {
-                    if let Ok(mut file) = OpenOptions::new().write(true).open(&path) {
-                        let zeros = vec![0u8; size.min(1024 * 1024)]; // Max 1MB chunks
-                        let mut remaining = size;
-                        while remaining > 0 {
-                            let to_write = remaining.min(zeros.len());
-                            let _ = file.write_all(&zeros[..to_write]);
-                            remaining -= to_write;
-                        }
-                        let _ = file.sync_all();
-                    }
-                }
+
+        if !path.exists() {
+            return Ok(());
+        }
+
+        match self.delete_mode {
+            SecureDeleteMode::None => {}
+            SecureDeleteMode::ZeroFill => {
+                let _ = overwrite_file(&path, &[Pass::Zeros]);
+            }
+            SecureDeleteMode::MultiPass => {
+                let _ = overwrite_file(&path, &[Pass::Random, Pass::Ones, Pass::Zeros]);
+            }
+            SecureDeleteMode::CryptoShred => {
+                // No data overwrite: unreliable on SSD/COW media. The caller is
+                // expected to have already erased the per-file key, which makes
+                // the ciphertext unrecoverable regardless of residual blocks.
             }
-            
-            fs::remove_file(&path)?;
         }
-        
+
+        fs::remove_file(&path)?;
         Ok(())
     }
     
@@ -153,6 +367,137 @@ impl SecureFs {
     }
 }
 
+/// Handle for an in-progress write started by [`SecureFs::begin_stream_write`].
+/// Dropping it before [`finish`](Self::finish) discards the partial `.tmp`
+/// file instead of leaving broken ciphertext behind.
+pub struct StreamWriteHandle {
+    file: Option<File>,
+    temp_path: PathBuf,
+    final_path: PathBuf,
+}
+
+impl StreamWriteHandle {
+    /// Append already-encrypted bytes directly to the backing `.tmp` file.
+    pub fn write_chunk(&mut self, bytes: &[u8]) -> VaultResult<()> {
+        let file = self.file.as_mut().ok_or_else(|| {
+            VaultError::IoError(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "stream write already finished",
+            ))
+        })?;
+        file.write_all(bytes).map_err(VaultError::IoError)
+    }
+
+    /// Fsync and atomically rename the `.tmp` file into place, fsyncing the
+    /// parent directory so the rename survives a crash (same durability as
+    /// [`SecureFs::write_file_stream`]).
+    pub fn finish(mut self) -> VaultResult<()> {
+        let file = self.file.take().ok_or_else(|| {
+            VaultError::IoError(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "stream write already finished",
+            ))
+        })?;
+        file.sync_all()?;
+        fs::rename(&self.temp_path, &self.final_path)?;
+        if let Some(parent) = self.final_path.parent() {
+            if let Ok(dir) = File::open(parent) {
+                let _ = dir.sync_all();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StreamWriteHandle {
+    fn drop(&mut self) {
+        if self.file.is_some() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// Build an authenticated container: header + ciphertext + HMAC tag.
+fn seal_container(key: &VaultKey, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + data.len() + TAG_LEN);
+    out.extend_from_slice(CONTAINER_MAGIC);
+    out.push(CONTAINER_VERSION);
+    out.extend_from_slice(&(data.len() as u64).to_be_bytes());
+    out.extend_from_slice(data);
+    let tag = compute_hmac(key, &out); // MAC over header + ciphertext
+    out.extend_from_slice(&tag);
+    out
+}
+
+/// Constant-time byte comparison.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+/// Overwrite a file's contents with the given passes, syncing after each.
+fn overwrite_file(path: &Path, passes: &[Pass]) -> VaultResult<()> {
+    let size = fs::metadata(path)?.len() as usize;
+    if size == 0 {
+        return Ok(());
+    }
+
+    const CHUNK: usize = 1024 * 1024;
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let mut buf = vec![0u8; size.min(CHUNK)];
+
+    for pass in passes {
+        file.seek(SeekFrom::Start(0))?;
+        let mut remaining = size;
+        while remaining > 0 {
+            let n = remaining.min(buf.len());
+            fill_pass(pass, &mut buf[..n]);
+            file.write_all(&buf[..n])?;
+            remaining -= n;
+        }
+        file.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// Fill a buffer with the pattern for one pass.
+fn fill_pass(pass: &Pass, buf: &mut [u8]) {
+    match pass {
+        Pass::Random => {
+            use rand::RngCore;
+            rand::thread_rng().fill_bytes(buf);
+        }
+        Pass::Ones => buf.fill(0xFF),
+        Pass::Zeros => buf.fill(0x00),
+    }
+}
+
+/// Remove `.tmp` files left behind by an interrupted atomic write.
+fn cleanup_orphaned_tmp(root: &Path) {
+    if !root.is_dir() {
+        return;
+    }
+    let entries = match fs::read_dir(root) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            cleanup_orphaned_tmp(&path);
+        } else if path.extension().and_then(|e| e.to_str()) == Some("tmp") {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,4 +520,69 @@ mod tests {
         fs.delete_file("test/data.enc").unwrap();
         assert!(!fs.exists("test/data.enc"));
     }
+
+    #[test]
+    fn test_multipass_delete() {
+        let dir = tempdir().unwrap();
+        let fs = SecureFs::with_delete_mode(dir.path(), SecureDeleteMode::MultiPass);
+
+        fs.write_file("secret.enc", &vec![0xAB; 4096]).unwrap();
+        fs.delete_file("secret.enc").unwrap();
+        assert!(!fs.exists("secret.enc"));
+    }
+
+    #[test]
+    fn test_integrity_roundtrip_and_tamper() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let dir = tempdir().unwrap();
+        let tampered = Arc::new(AtomicBool::new(false));
+        let mut fs = SecureFs::new_with_key(dir.path(), VaultKey::generate());
+        let flag = tampered.clone();
+        fs.set_tamper_hook(move |_path| flag.store(true, Ordering::SeqCst));
+
+        fs.write_file("photo.enc", b"ciphertext-bytes").unwrap();
+        assert_eq!(fs.read_file("photo.enc").unwrap(), b"ciphertext-bytes");
+        fs.verify_file("photo.enc").unwrap();
+
+        // Flip a byte in the stored container and confirm detection.
+        let path = dir.path().join("photo.enc");
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert!(matches!(
+            fs.read_file("photo.enc"),
+            Err(VaultError::IntegrityFailure(_))
+        ));
+        assert!(tampered.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_stream_file_roundtrip() {
+        let dir = tempdir().unwrap();
+        let fs = SecureFs::new(dir.path());
+        let key = VaultKey::generate();
+
+        // Larger than one chunk so the STREAM counter advances.
+        let plaintext = vec![0x37u8; crate::crypto::STREAM_CHUNK_SIZE * 2 + 512];
+        fs.write_file_stream("video/clip.enc", &key, plaintext.as_slice())
+            .unwrap();
+
+        let mut decrypted = Vec::new();
+        fs.read_file_stream("video/clip.enc", &key, &mut decrypted)
+            .unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_orphaned_tmp_cleanup() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("leftover.tmp"), b"junk").unwrap();
+
+        let _fs = SecureFs::new(dir.path());
+        assert!(!dir.path().join("leftover.tmp").exists());
+    }
 }
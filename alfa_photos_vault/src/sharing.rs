@@ -0,0 +1,173 @@
+//! ALFA Photos Vault - Capability-token sharing
+//!
+//! Self-contained, time-limited capability tokens that grant read access to a
+//! specific set of photos. All key material is derived from the vault master
+//! via HKDF, so sharing introduces no new long-lived secret to store.
+//!
+//! A token binds a random 16-byte id, the shared photo ids, an expiry and a
+//! version, authenticated with an HMAC-SHA256 tag. The matching photos are
+//! re-encrypted under a per-token share key derived from the same id, so a
+//! bundle can be handed to a recipient and re-opened by the daemon (which
+//! holds the master) after MAC and expiry checks pass.
+
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{compute_hmac, derive_subkey, VaultKey};
+use crate::error::{VaultError, VaultResult};
+
+/// Token format version.
+pub const SHARE_VERSION: u8 = 1;
+
+/// HKDF `info` used to derive a token's data (re-encryption) key.
+const SHARE_KEY_INFO: &[u8] = b"ALFA:SHARE:v1";
+/// HKDF `info` used to derive a token's authentication key.
+const SHARE_MAC_INFO: &[u8] = b"ALFA:SHARE-MAC:v1";
+
+/// A signed, expiring capability granting read access to a set of photos.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareToken {
+    /// Token format version.
+    pub version: u8,
+    /// Random per-token identifier; also the HKDF context for both subkeys.
+    pub token_id: [u8; 16],
+    /// Photo ids this token authorizes.
+    pub photo_ids: Vec<String>,
+    /// Absolute expiry as a Unix timestamp (seconds).
+    pub expiry_unix: i64,
+    /// HMAC-SHA256 tag over the canonical fields, keyed by the MAC subkey.
+    pub tag: [u8; 32],
+}
+
+/// A token together with the photos it authorizes, re-encrypted under its
+/// share key. This is what gets handed to a recipient.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareBundle {
+    pub token: ShareToken,
+    /// `(photo_id, ciphertext)` pairs encrypted under the share key.
+    pub blobs: Vec<(String, Vec<u8>)>,
+}
+
+/// Derive the per-token share (data) key from the master.
+pub fn share_key(master: &VaultKey, token_id: &[u8; 16]) -> VaultResult<VaultKey> {
+    derive_subkey(master, token_id, SHARE_KEY_INFO)
+}
+
+/// Derive the per-token authentication key from the master.
+fn mac_key(master: &VaultKey, token_id: &[u8; 16]) -> VaultResult<VaultKey> {
+    derive_subkey(master, token_id, SHARE_MAC_INFO)
+}
+
+/// Canonical byte encoding of the authenticated fields (everything but the tag).
+fn canonical(version: u8, token_id: &[u8; 16], photo_ids: &[String], expiry_unix: i64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.push(version);
+    buf.extend_from_slice(token_id);
+    buf.extend_from_slice(&expiry_unix.to_be_bytes());
+    buf.extend_from_slice(&(photo_ids.len() as u32).to_be_bytes());
+    for id in photo_ids {
+        buf.extend_from_slice(&(id.len() as u32).to_be_bytes());
+        buf.extend_from_slice(id.as_bytes());
+    }
+    buf
+}
+
+impl ShareToken {
+    /// Mint a token for `photo_ids` valid until `expiry_unix`.
+    pub fn mint(
+        master: &VaultKey,
+        photo_ids: Vec<String>,
+        expiry_unix: i64,
+    ) -> VaultResult<Self> {
+        use rand::RngCore;
+        let mut token_id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut token_id);
+
+        let mac = mac_key(master, &token_id)?;
+        let tag = compute_hmac(&mac, &canonical(SHARE_VERSION, &token_id, &photo_ids, expiry_unix));
+
+        Ok(Self {
+            version: SHARE_VERSION,
+            token_id,
+            photo_ids,
+            expiry_unix,
+            tag,
+        })
+    }
+
+    /// Verify the MAC and expiry against `master`, returning the share key.
+    ///
+    /// The MAC is checked before expiry so a tampered token is rejected with
+    /// [`VaultError::HmacVerificationFailed`] regardless of its clock field.
+    pub fn verify(&self, master: &VaultKey, now_unix: i64) -> VaultResult<VaultKey> {
+        if self.version != SHARE_VERSION {
+            return Err(VaultError::InvalidShareParameters(format!(
+                "unsupported share token version {}",
+                self.version
+            )));
+        }
+
+        let mac = mac_key(master, &self.token_id)?;
+        let expected = compute_hmac(
+            &mac,
+            &canonical(self.version, &self.token_id, &self.photo_ids, self.expiry_unix),
+        );
+        // Constant-time comparison: every byte is folded in, with no early exit.
+        let diff = expected
+            .iter()
+            .zip(self.tag.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if diff != 0 {
+            return Err(VaultError::HmacVerificationFailed);
+        }
+
+        if now_unix >= self.expiry_unix {
+            return Err(VaultError::InvalidShareParameters("share token expired".into()));
+        }
+
+        share_key(master, &self.token_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mint_and_verify_round_trip() {
+        let master = VaultKey::generate();
+        let token = ShareToken::mint(&master, vec!["a".into(), "b".into()], 10_000).unwrap();
+
+        let key = token.verify(&master, 9_000).unwrap();
+        let key2 = share_key(&master, &token.token_id).unwrap();
+        assert_eq!(key.expose(), key2.expose());
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let master = VaultKey::generate();
+        let token = ShareToken::mint(&master, vec!["a".into()], 1_000).unwrap();
+        assert!(matches!(
+            token.verify(&master, 1_000),
+            Err(VaultError::InvalidShareParameters(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_token_rejected() {
+        let master = VaultKey::generate();
+        let mut token = ShareToken::mint(&master, vec!["a".into()], 10_000).unwrap();
+        token.photo_ids.push("sneaky".into());
+        assert!(matches!(
+            token.verify(&master, 9_000),
+            Err(VaultError::HmacVerificationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_wrong_master_rejected() {
+        let master = VaultKey::generate();
+        let other = VaultKey::generate();
+        let token = ShareToken::mint(&master, vec!["a".into()], 10_000).unwrap();
+        assert!(token.verify(&other, 9_000).is_err());
+    }
+}
@@ -3,12 +3,38 @@
 //! Optional sync to external services (Ente, Nextcloud, NAS).
 //! ALWAYS encrypted - external service only sees blobs.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+
+use crate::crypto::{
+    compute_hmac, decrypt, derive_subkey, encrypt_xchacha, verify_hmac, EncryptedData, VaultKey,
+};
 use crate::error::{VaultError, VaultResult};
 
+/// Target per-level false-positive rate for the sync manifest's Bloom filters.
+const MANIFEST_FP_RATE: f64 = 0.01;
+
+/// Upload chunk size for resumable WebDAV PUTs (4 MiB).
+const WEBDAV_CHUNK: usize = 4 * 1024 * 1024;
+
+/// Filename for the on-disk `resume_offsets` checkpoint, relative to the
+/// configured state directory (see [`SyncPlugin::set_state_dir`]).
+const RESUME_OFFSETS_FILE: &str = "resume_offsets.json";
+
+/// HKDF context separating the share key from every other derived key.
+const SHARE_KEY_CONTEXT: &[u8] = b"ALFA:SYNC:SHARE";
+/// HKDF info string for the capability-token signing key.
+const SHARE_KEY_INFO: &[u8] = b"capability-token";
+/// Length of the HMAC-SHA256 tag carried by a capability token.
+const CAP_TAG_LEN: usize = 32;
+
 /// Sync provider type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SyncProvider {
@@ -79,6 +105,18 @@ pub struct SyncStatus {
 pub struct SyncPlugin {
     config: Option<SyncConfig>,
     status: SyncStatus,
+    /// Vault key used to decrypt `encrypted_credentials` for remote auth; never
+    /// persisted, so plaintext passwords never touch disk.
+    vault_key: Option<VaultKey>,
+    /// Last confirmed upload offset per `file_id`, so a WebDAV PUT interrupted
+    /// mid-file resumes from where it left off instead of restarting at byte 0
+    /// on the next `sync_file` retry. Cleared once the file's HMAC sidecar is
+    /// confirmed uploaded. Mirrored to `state_dir` (when set) so the
+    /// checkpoint also survives a process restart, not just a retry.
+    resume_offsets: HashMap<String, usize>,
+    /// Directory `resume_offsets` is persisted to, set via [`set_state_dir`](Self::set_state_dir).
+    /// `None` keeps the prior in-memory-only behaviour.
+    state_dir: Option<PathBuf>,
 }
 
 impl SyncPlugin {
@@ -93,9 +131,35 @@ impl SyncPlugin {
                 total_files: 0,
                 last_error: None,
             },
+            vault_key: None,
+            resume_offsets: HashMap::new(),
+            state_dir: None,
         }
     }
-    
+
+    /// Provide the vault key so remote credentials can be decrypted in memory.
+    pub fn set_vault_key(&mut self, key: VaultKey) {
+        self.vault_key = Some(key);
+    }
+
+    /// Persist `resume_offsets` under `dir` from now on, loading any
+    /// checkpoint a previous process left behind. Without this, resume
+    /// offsets only survive a retry within the same process; with it, they
+    /// also survive a crash or restart of the host process.
+    pub fn set_state_dir(&mut self, dir: &Path) {
+        self.resume_offsets = load_resume_offsets(dir);
+        self.state_dir = Some(dir.to_path_buf());
+    }
+
+    /// Mirror `resume_offsets` to `state_dir`, if configured. Best-effort:
+    /// a write failure here just falls back to the in-memory checkpoint for
+    /// the rest of this process.
+    fn persist_resume_offsets(&self) {
+        if let Some(dir) = &self.state_dir {
+            let _ = save_resume_offsets(dir, &self.resume_offsets);
+        }
+    }
+
     /// Configure sync
     pub fn configure(&mut self, config: SyncConfig) -> VaultResult<()> {
         // Validate config
@@ -126,20 +190,20 @@ impl SyncPlugin {
     
     /// Sync a single file (already encrypted)
     pub async fn sync_file(&mut self, file_id: &str, encrypted_data: &[u8]) -> VaultResult<()> {
-        let config = self.config.as_ref()
+        let config = self.config.clone()
             .ok_or_else(|| VaultError::PluginNotConfigured("Sync not configured".into()))?;
-        
+
         self.status.syncing = true;
-        
+
         let result = match config.provider {
             SyncProvider::UsbDrive | SyncProvider::LocalNas => {
                 self.sync_to_local(file_id, encrypted_data, &config.remote_path).await
             }
             SyncProvider::Ente => {
-                self.sync_to_ente(file_id, encrypted_data, config).await
+                self.sync_to_ente(file_id, encrypted_data, &config).await
             }
             SyncProvider::Nextcloud | SyncProvider::CustomWebDav => {
-                self.sync_to_webdav(file_id, encrypted_data, config).await
+                self.sync_to_webdav(file_id, encrypted_data, &config).await
             }
         };
         
@@ -154,6 +218,60 @@ impl SyncPlugin {
         result
     }
     
+    /// Sync an already-encrypted file by streaming it in fixed chunks, so a
+    /// multi-gigabyte video never has to be held in RAM at once. The reader is
+    /// expected to yield a stream produced by [`crate::crypto::encrypt_stream`];
+    /// it is copied to the backend [`crate::crypto::STREAM_CHUNK_SIZE`] bytes at
+    /// a time. Only the local provider streams today; remote backends fall back
+    /// to their (unimplemented) buffered paths.
+    pub async fn sync_file_stream<R: std::io::Read>(
+        &mut self,
+        file_id: &str,
+        mut reader: R,
+    ) -> VaultResult<()> {
+        let config = self
+            .config
+            .as_ref()
+            .ok_or_else(|| VaultError::PluginNotConfigured("Sync not configured".into()))?;
+
+        if !matches!(
+            config.provider,
+            SyncProvider::UsbDrive | SyncProvider::LocalNas
+        ) {
+            // Buffer and delegate: remote backends are still stubs.
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            return self.sync_file(file_id, &data).await;
+        }
+
+        let path = Path::new(&config.remote_path).join(format!("{}.enc", file_id));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        self.status.syncing = true;
+        let result = (|| {
+            let mut out = std::fs::File::create(&path)?;
+            let mut buf = vec![0u8; crate::crypto::STREAM_CHUNK_SIZE];
+            loop {
+                let n = reader.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                std::io::Write::write_all(&mut out, &buf[..n])?;
+            }
+            std::io::Write::flush(&mut out)?;
+            Ok(())
+        })();
+        self.status.syncing = false;
+
+        match &result {
+            Ok(()) => self.status.files_synced += 1,
+            Err(e) => self.status.last_error = Some(e.to_string()),
+        }
+        result
+    }
+
     /// Sync to local path (USB/NAS)
     async fn sync_to_local(&self, file_id: &str, data: &[u8], base_path: &str) -> VaultResult<()> {
         let path = Path::new(base_path).join(format!("{}.enc", file_id));
@@ -175,10 +293,227 @@ impl SyncPlugin {
         Err(VaultError::PluginNotConfigured("Ente sync not implemented".into()))
     }
     
-    /// Sync to WebDAV (Nextcloud, etc.)
-    async fn sync_to_webdav(&self, _file_id: &str, _data: &[u8], _config: &SyncConfig) -> VaultResult<()> {
-        // TODO: Implement WebDAV client
-        Err(VaultError::PluginNotConfigured("WebDAV sync not implemented".into()))
+    /// Sync to WebDAV (Nextcloud, custom), with resumable chunked upload and an
+    /// HMAC sidecar for integrity. The encrypted blob is PUT in `WEBDAV_CHUNK`
+    /// ranges so an interrupted connection resumes from the last confirmed
+    /// offset instead of restarting; the confirmed offset is persisted in
+    /// `resume_offsets` after every successful range so a later `sync_file`
+    /// retry for the same `file_id` picks up where the previous attempt left
+    /// off rather than re-uploading from byte 0. That checkpoint survives a
+    /// process restart, not just a same-process retry, when a state
+    /// directory has been configured via [`SyncPlugin::set_state_dir`]. Its
+    /// `compute_hmac` tag is uploaded next to it as `{file_id}.enc.hmac` and
+    /// re-checked on download.
+    async fn sync_to_webdav(
+        &mut self,
+        file_id: &str,
+        data: &[u8],
+        config: &SyncConfig,
+    ) -> VaultResult<()> {
+        let client = reqwest::Client::new();
+        let base = self.webdav_base(config)?;
+        let auth = self.webdav_auth_header(config)?;
+
+        // Ensure the collection exists (MKCOL is idempotent; existing → 405).
+        let mkcol = reqwest::Method::from_bytes(b"MKCOL").unwrap();
+        let resp = client
+            .request(mkcol, &base)
+            .header(reqwest::header::AUTHORIZATION, &auth)
+            .send()
+            .await
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?;
+        if !resp.status().is_success() && resp.status().as_u16() != 405 {
+            return Err(VaultError::SyncFailed(format!(
+                "MKCOL failed: {}",
+                resp.status()
+            )));
+        }
+
+        let blob_url = format!("{base}/{file_id}.enc");
+        let total = data.len();
+        // Resume from the last confirmed offset for this file, if any.
+        let mut offset = self
+            .resume_offsets
+            .get(file_id)
+            .copied()
+            .unwrap_or(0)
+            .min(total);
+        while offset < total {
+            let end = (offset + WEBDAV_CHUNK).min(total);
+            let range = format!("bytes {}-{}/{}", offset, end - 1, total);
+            let resp = client
+                .put(&blob_url)
+                .header(reqwest::header::AUTHORIZATION, &auth)
+                .header(reqwest::header::CONTENT_RANGE, range)
+                .body(data[offset..end].to_vec())
+                .send()
+                .await
+                .map_err(|e| VaultError::SyncFailed(e.to_string()))?;
+            if !resp.status().is_success() {
+                // Leave `offset` unadvanced so a retry re-PUTs this range.
+                self.resume_offsets.insert(file_id.to_string(), offset);
+                self.persist_resume_offsets();
+                return Err(VaultError::SyncFailed(format!(
+                    "PUT range {offset} failed: {}",
+                    resp.status()
+                )));
+            }
+            offset = end;
+            self.resume_offsets.insert(file_id.to_string(), offset);
+            self.persist_resume_offsets();
+        }
+
+        // Integrity sidecar: HMAC over the exact bytes we uploaded.
+        let tag = compute_hmac(self.mac_key()?, data);
+        client
+            .put(format!("{blob_url}.hmac"))
+            .header(reqwest::header::AUTHORIZATION, &auth)
+            .body(tag.to_vec())
+            .send()
+            .await
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?
+            .error_for_status()
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?;
+
+        // Fully uploaded and verified: drop the resume checkpoint.
+        self.resume_offsets.remove(file_id);
+        self.persist_resume_offsets();
+
+        Ok(())
+    }
+
+    /// Download a blob from WebDAV and verify its HMAC sidecar before returning.
+    /// Any failure, including a `verify_hmac` mismatch, is also recorded in
+    /// [`SyncStatus::last_error`] so callers polling `status()` see it even if
+    /// they don't inspect the returned `Result`.
+    pub async fn fetch_from_webdav(&mut self, file_id: &str) -> VaultResult<Vec<u8>> {
+        let result = self.fetch_from_webdav_inner(file_id).await;
+        if let Err(ref e) = result {
+            self.status.last_error = Some(e.to_string());
+        }
+        result
+    }
+
+    async fn fetch_from_webdav_inner(&self, file_id: &str) -> VaultResult<Vec<u8>> {
+        let config = self
+            .config
+            .as_ref()
+            .ok_or_else(|| VaultError::PluginNotConfigured("Sync not configured".into()))?;
+        let client = reqwest::Client::new();
+        let base = self.webdav_base(config)?;
+        let auth = self.webdav_auth_header(config)?;
+        let blob_url = format!("{base}/{file_id}.enc");
+
+        let data = client
+            .get(&blob_url)
+            .header(reqwest::header::AUTHORIZATION, &auth)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?
+            .bytes()
+            .await
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?;
+
+        let sidecar = client
+            .get(format!("{blob_url}.hmac"))
+            .header(reqwest::header::AUTHORIZATION, &auth)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?
+            .bytes()
+            .await
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?;
+
+        let expected: [u8; 32] = sidecar
+            .as_ref()
+            .try_into()
+            .map_err(|_| VaultError::IntegrityFailure(file_id.to_string()))?;
+        if !verify_hmac(self.mac_key()?, &data, &expected) {
+            return Err(VaultError::IntegrityFailure(file_id.to_string()));
+        }
+        Ok(data.to_vec())
+    }
+
+    /// List the `{id}.enc` blobs present on the remote via PROPFIND depth 1.
+    pub async fn list_remote(&self) -> VaultResult<Vec<String>> {
+        let config = self
+            .config
+            .as_ref()
+            .ok_or_else(|| VaultError::PluginNotConfigured("Sync not configured".into()))?;
+        let client = reqwest::Client::new();
+        let base = self.webdav_base(config)?;
+        let auth = self.webdav_auth_header(config)?;
+
+        let propfind = reqwest::Method::from_bytes(b"PROPFIND").unwrap();
+        let body = client
+            .request(propfind, &base)
+            .header(reqwest::header::AUTHORIZATION, &auth)
+            .header("Depth", "1")
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?
+            .text()
+            .await
+            .map_err(|e| VaultError::SyncFailed(e.to_string()))?;
+
+        // Extract file ids from the <d:href> entries ending in `.enc`.
+        let mut ids = Vec::new();
+        for href in body.split("<d:href>").skip(1) {
+            if let Some(end) = href.find("</d:href>") {
+                let path = &href[..end];
+                if let Some(name) = path.trim_end_matches('/').rsplit('/').next() {
+                    if let Some(id) = name.strip_suffix(".enc") {
+                        ids.push(id.to_string());
+                    }
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Base URL of the remote collection (`server_url` + `remote_path`).
+    fn webdav_base(&self, config: &SyncConfig) -> VaultResult<String> {
+        let server = config
+            .server_url
+            .as_deref()
+            .ok_or_else(|| VaultError::PluginNotConfigured("Server URL required".into()))?;
+        Ok(format!(
+            "{}/{}",
+            server.trim_end_matches('/'),
+            config.remote_path.trim_matches('/')
+        ))
+    }
+
+    /// Build a HTTP Basic auth header, decrypting the password from
+    /// `encrypted_credentials` in memory so it never lands on disk in the clear.
+    fn webdav_auth_header(&self, config: &SyncConfig) -> VaultResult<String> {
+        use base64::Engine;
+        let key = self
+            .vault_key
+            .as_ref()
+            .ok_or_else(|| VaultError::PluginNotConfigured("Vault key not set".into()))?;
+        let enc = config
+            .encrypted_credentials
+            .as_ref()
+            .ok_or_else(|| VaultError::PluginNotConfigured("Credentials required".into()))?;
+        let password = decrypt(key, enc)?;
+        let user = config.username.as_deref().unwrap_or("");
+        let mut pair = format!("{user}:");
+        pair.push_str(&String::from_utf8_lossy(&password));
+        Ok(format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(pair.as_bytes())
+        ))
+    }
+
+    /// The key used for sidecar HMACs (the configured vault key).
+    fn mac_key(&self) -> VaultResult<&VaultKey> {
+        self.vault_key
+            .as_ref()
+            .ok_or_else(|| VaultError::PluginNotConfigured("Vault key not set".into()))
     }
     
     /// Full sync (all files)
@@ -213,9 +548,273 @@ impl SyncPlugin {
         if let Some(ref mut config) = self.config {
             config.last_sync = Some(Utc::now());
         }
-        
+
         Ok(report)
     }
+
+    /// Delta sync: skip any file already recorded in the remote `manifest`, so
+    /// only ids the remote is missing are uploaded. With zero false negatives
+    /// the manifest never causes a needed upload to be skipped.
+    pub async fn full_sync_delta<F>(
+        &mut self,
+        manifest: &FilterCascade,
+        get_files: F,
+    ) -> VaultResult<SyncReport>
+    where
+        F: Fn() -> Vec<(String, Vec<u8>)>,
+    {
+        let pending: Vec<(String, Vec<u8>)> = get_files()
+            .into_iter()
+            .filter(|(id, _)| !manifest.contains(id))
+            .collect();
+        self.full_sync(move || pending.clone()).await
+    }
+}
+
+impl SyncPlugin {
+    /// Build a capability-token issuer bound to this plugin's vault key. The
+    /// share key is HKDF-derived, so tokens are unforgeable without the master
+    /// key yet never expose it.
+    pub fn capability_issuer(&self) -> VaultResult<CapabilityIssuer> {
+        let key = self
+            .vault_key
+            .as_ref()
+            .ok_or_else(|| VaultError::PluginNotConfigured("Vault key not set".into()))?;
+        CapabilityIssuer::from_vault_key(key)
+    }
+
+    /// Fetch a single blob using a capability token instead of full config
+    /// access. The token is validated (signature, expiry, revocation floor)
+    /// before the remote is contacted, and it scopes access to exactly its
+    /// `file_id`.
+    pub async fn fetch_with_token(
+        &mut self,
+        issuer: &CapabilityIssuer,
+        token: &str,
+    ) -> VaultResult<Vec<u8>> {
+        let file_id = issuer.validate(token)?;
+        self.fetch_from_webdav(&file_id).await
+    }
+}
+
+/// Mints and validates time-limited HMAC capability tokens that grant read
+/// access to a single `{file_id}.enc` blob.
+///
+/// A token is `base64url(file_id || expiry_be || HMAC-SHA256(share_key,
+/// file_id || expiry_be))`. Validation recomputes the tag in constant time,
+/// rejects expired tokens, and rejects tokens whose expiry predates the
+/// `revocation_floor` — bumping that floor invalidates every outstanding token
+/// at once.
+pub struct CapabilityIssuer {
+    share_key: VaultKey,
+    revocation_floor: u64,
+}
+
+impl CapabilityIssuer {
+    /// Derive the share key from the vault key via HKDF.
+    pub fn from_vault_key(vault_key: &VaultKey) -> VaultResult<Self> {
+        let share_key = derive_subkey(vault_key, SHARE_KEY_CONTEXT, SHARE_KEY_INFO)?;
+        Ok(Self {
+            share_key,
+            revocation_floor: 0,
+        })
+    }
+
+    /// Revoke all tokens expiring at or before `floor` (a unix timestamp).
+    /// Monotonic: the floor only ever moves forward.
+    pub fn set_revocation_floor(&mut self, floor: u64) {
+        self.revocation_floor = self.revocation_floor.max(floor);
+    }
+
+    /// Mint a token for `file_id` valid until `expiry` (unix seconds).
+    pub fn issue(&self, file_id: &str, expiry: u64) -> String {
+        use base64::Engine;
+        let mut signed = Vec::with_capacity(file_id.len() + 8);
+        signed.extend_from_slice(file_id.as_bytes());
+        signed.extend_from_slice(&expiry.to_be_bytes());
+
+        let tag = compute_hmac(&self.share_key, &signed);
+        signed.extend_from_slice(&tag);
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&signed)
+    }
+
+    /// Mint a token valid for `ttl_secs` from now.
+    pub fn issue_for(&self, file_id: &str, ttl_secs: u64) -> String {
+        let expiry = Utc::now().timestamp().max(0) as u64 + ttl_secs;
+        self.issue(file_id, expiry)
+    }
+
+    /// Validate a token and return the `file_id` it authorizes.
+    pub fn validate(&self, token: &str) -> VaultResult<String> {
+        use base64::Engine;
+        let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| VaultError::InvalidShareParameters("malformed token".into()))?;
+        if raw.len() < 8 + CAP_TAG_LEN {
+            return Err(VaultError::InvalidShareParameters("token too short".into()));
+        }
+
+        let (signed, tag) = raw.split_at(raw.len() - CAP_TAG_LEN);
+        let expected = compute_hmac(&self.share_key, signed);
+        if !ct_eq(&expected, tag) {
+            return Err(VaultError::InvalidShareParameters("bad token signature".into()));
+        }
+
+        let (id_bytes, expiry_bytes) = signed.split_at(signed.len() - 8);
+        let expiry = u64::from_be_bytes(expiry_bytes.try_into().unwrap());
+        let now = Utc::now().timestamp().max(0) as u64;
+        if expiry < now {
+            return Err(VaultError::InvalidShareParameters("token expired".into()));
+        }
+        if expiry <= self.revocation_floor {
+            return Err(VaultError::InvalidShareParameters("token revoked".into()));
+        }
+
+        String::from_utf8(id_bytes.to_vec())
+            .map_err(|_| VaultError::InvalidShareParameters("non-utf8 file id".into()))
+    }
+}
+
+/// A single Bloom filter level of the [`FilterCascade`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BloomLevel {
+    /// Bit array, packed into 64-bit words.
+    bits: Vec<u64>,
+    /// Number of bits (≤ `bits.len() * 64`).
+    m_bits: u64,
+    /// Number of hash probes per element.
+    k: u32,
+}
+
+impl BloomLevel {
+    /// Size a filter for `n` elements at the target false-positive rate.
+    fn new(n: usize, p: f64) -> Self {
+        let n = n.max(1) as f64;
+        let m = (-n * p.ln() / std::f64::consts::LN_2.powi(2)).ceil().max(1.0) as u64;
+        let k = ((m as f64 / n) * std::f64::consts::LN_2).round().max(1.0) as u32;
+        Self {
+            bits: vec![0u64; ((m + 63) / 64) as usize],
+            m_bits: m,
+            k,
+        }
+    }
+
+    /// Two independent 64-bit hashes of `id`, salted by the cascade level.
+    fn hashes(id: &str, level: usize) -> (u64, u64) {
+        let mut h1 = DefaultHasher::new();
+        (level, id).hash(&mut h1);
+        let mut h2 = DefaultHasher::new();
+        (id, level, 0x9e37_79b9_7f4a_7c15u64).hash(&mut h2);
+        (h1.finish(), h2.finish() | 1)
+    }
+
+    fn insert(&mut self, id: &str, level: usize) {
+        let (a, b) = Self::hashes(id, level);
+        for i in 0..self.k as u64 {
+            let idx = (a.wrapping_add(i.wrapping_mul(b)) % self.m_bits) as usize;
+            self.bits[idx / 64] |= 1u64 << (idx % 64);
+        }
+    }
+
+    fn contains(&self, id: &str, level: usize) -> bool {
+        let (a, b) = Self::hashes(id, level);
+        (0..self.k as u64).all(|i| {
+            let idx = (a.wrapping_add(i.wrapping_mul(b)) % self.m_bits) as usize;
+            self.bits[idx / 64] & (1u64 << (idx % 64)) != 0
+        })
+    }
+}
+
+/// A Bloom-filter cascade encoding exactly which file ids the remote holds.
+///
+/// Level 0 is a Bloom filter over the present set `R`; every deleted id that
+/// false-positives there is re-encoded at level 1; every present id that then
+/// false-positives at level 1 goes to level 2, and so on until no false
+/// positives remain. Because the odd levels cancel the even levels' errors, a
+/// lookup has **zero false negatives** for membership in `R`. The whole
+/// structure is a few kilobytes, so a client downloads it and decides locally
+/// which ids still need uploading instead of transferring the full id list.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FilterCascade {
+    levels: Vec<BloomLevel>,
+}
+
+impl FilterCascade {
+    /// Build a cascade from the present set `remote` and the absent set
+    /// `deleted` (ids known not to be on the remote).
+    pub fn build(remote: &[String], deleted: &[String]) -> Self {
+        let mut levels = Vec::new();
+        // `encode` is the set this level stores; `check` is the opposite-parity
+        // set whose members might false-positive into it.
+        let mut encode: Vec<String> = remote.to_vec();
+        let mut check: Vec<String> = deleted.to_vec();
+
+        while !encode.is_empty() {
+            let level = levels.len();
+            let mut filter = BloomLevel::new(encode.len(), MANIFEST_FP_RATE);
+            for id in &encode {
+                filter.insert(id, level);
+            }
+            let false_positives: Vec<String> = check
+                .iter()
+                .filter(|id| filter.contains(id, level))
+                .cloned()
+                .collect();
+            levels.push(filter);
+
+            if false_positives.is_empty() {
+                break;
+            }
+            // Next level encodes the false positives; only the current encode
+            // set could collide with it, so that becomes the new check set.
+            check = encode;
+            encode = false_positives;
+        }
+
+        Self { levels }
+    }
+
+    /// Test membership in the present set `R`. Walks the levels and returns at
+    /// the first one whose filter does *not* contain `id`: even levels encode
+    /// `R`, so a miss there means absent; odd levels encode the deleted set, so
+    /// a miss there means present.
+    pub fn contains(&self, id: &str) -> bool {
+        for (level, filter) in self.levels.iter().enumerate() {
+            if !filter.contains(id, level) {
+                return level % 2 == 1;
+            }
+        }
+        // Contained in every level: containment at the final level is genuine
+        // (it has no false positives), so parity of the last index decides —
+        // an even last index encodes the present set R.
+        !self.levels.is_empty() && (self.levels.len() - 1) % 2 == 0
+    }
+
+    /// Serialize and encrypt the manifest with XChaCha20-Poly1305 for storage
+    /// next to the remote data.
+    pub fn to_encrypted(&self, key: &VaultKey) -> VaultResult<Vec<u8>> {
+        let json = serde_json::to_vec(self)?;
+        Ok(encrypt_xchacha(key, &json, &[])?.to_bytes())
+    }
+
+    /// Decrypt and parse a manifest produced by [`to_encrypted`].
+    pub fn from_encrypted(key: &VaultKey, data: &[u8]) -> VaultResult<Self> {
+        let parsed = EncryptedData::parse(data)?;
+        let json = crate::crypto::decrypt_xchacha(key, &parsed, &[])?;
+        serde_json::from_slice(&json).map_err(Into::into)
+    }
+}
+
+/// Constant-time byte comparison for token-tag checks.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
 }
 
 impl Default for SyncPlugin {
@@ -224,6 +823,38 @@ impl Default for SyncPlugin {
     }
 }
 
+/// Load the `resume_offsets` checkpoint left behind under `dir`, if any.
+/// Missing or unparseable state is treated as "no checkpoint" rather than
+/// an error, since a fresh upload from byte 0 is always safe.
+fn load_resume_offsets(dir: &Path) -> HashMap<String, usize> {
+    fs::read(dir.join(RESUME_OFFSETS_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Atomically persist `offsets` under `dir` (tmp file + fsync + rename +
+/// parent-directory fsync), the same durability pattern `SecureFs::write_file`
+/// uses, so a crash mid-write never leaves a corrupt checkpoint behind.
+fn save_resume_offsets(dir: &Path, offsets: &HashMap<String, usize>) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(RESUME_OFFSETS_FILE);
+    let tmp = path.with_extension("tmp");
+    let body = serde_json::to_vec(offsets).map_err(std::io::Error::other)?;
+    let mut f = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&tmp)?;
+    f.write_all(&body)?;
+    f.sync_all()?;
+    fs::rename(&tmp, &path)?;
+    if let Ok(d) = File::open(dir) {
+        let _ = d.sync_all();
+    }
+    Ok(())
+}
+
 /// Sync report
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncReport {
@@ -238,3 +869,62 @@ impl SyncReport {
         self.failed == 0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capability_token_roundtrip_and_revocation() {
+        let issuer = CapabilityIssuer::from_vault_key(&VaultKey::generate()).unwrap();
+        let future = Utc::now().timestamp() as u64 + 3600;
+
+        let token = issuer.issue("photo-7", future);
+        assert_eq!(issuer.validate(&token).unwrap(), "photo-7");
+
+        // Expired token is rejected.
+        let expired = issuer.issue("photo-7", 1);
+        assert!(issuer.validate(&expired).is_err());
+
+        // A tampered file id breaks the signature.
+        let mut other = issuer.issue("photo-7", future);
+        other.insert(0, 'Z');
+        assert!(issuer.validate(&other).is_err());
+
+        // Bumping the revocation floor invalidates outstanding tokens.
+        let mut revoked = CapabilityIssuer::from_vault_key(&VaultKey::generate()).unwrap();
+        let t = revoked.issue("photo-7", future);
+        revoked.set_revocation_floor(future + 1);
+        assert!(revoked.validate(&t).is_err());
+    }
+
+    #[test]
+    fn test_filter_cascade_no_false_negatives() {
+        let remote: Vec<String> = (0..500).map(|i| format!("present-{i}")).collect();
+        let deleted: Vec<String> = (0..500).map(|i| format!("absent-{i}")).collect();
+
+        let cascade = FilterCascade::build(&remote, &deleted);
+
+        // Every present id must be reported present (zero false negatives).
+        for id in &remote {
+            assert!(cascade.contains(id), "false negative for {id}");
+        }
+        // Every deleted id must be reported absent.
+        for id in &deleted {
+            assert!(!cascade.contains(id), "false positive for {id}");
+        }
+    }
+
+    #[test]
+    fn test_filter_cascade_encrypted_roundtrip() {
+        let remote: Vec<String> = (0..64).map(|i| format!("p{i}")).collect();
+        let cascade = FilterCascade::build(&remote, &[]);
+        let key = VaultKey::generate();
+
+        let blob = cascade.to_encrypted(&key).unwrap();
+        let restored = FilterCascade::from_encrypted(&key, &blob).unwrap();
+        for id in &remote {
+            assert!(restored.contains(id));
+        }
+    }
+}
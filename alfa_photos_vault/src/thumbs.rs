@@ -26,23 +26,49 @@ impl ThumbnailEngine {
     }
     
     /// Generate thumbnail from image data
+    ///
+    /// The `image` crate can panic (not merely error) on crafted or truncated
+    /// input, so the whole decode/resize/encode path is run inside
+    /// `catch_unwind` and a caught panic is reported as a thumbnail failure
+    /// rather than unwinding into a caller that may hold vault locks.
     pub fn generate(&self, image_data: &[u8]) -> VaultResult<Vec<u8>> {
-        // Load image
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| VaultError::ThumbnailFailed(e.to_string()))?;
-        
-        // Generate thumbnail
-        let thumb = self.resize_to_thumbnail(&img);
-        
-        // Encode as JPEG (smaller than PNG)
-        let mut output = Vec::new();
-        let mut cursor = Cursor::new(&mut output);
-        
-        thumb
-            .write_to(&mut cursor, image::ImageFormat::Jpeg)
-            .map_err(|e| VaultError::ThumbnailFailed(e.to_string()))?;
-        
-        Ok(output)
+        self.generate_oriented(image_data, 1)
+    }
+
+    /// Generate a thumbnail, first applying the given EXIF orientation tag
+    /// (1-8) so the result displays upright regardless of how the camera
+    /// stored the pixels. Orientation `1` (or any unknown value) is a no-op.
+    ///
+    /// Shares the panic-safety guarantees of [`generate`](Self::generate).
+    pub fn generate_oriented(&self, image_data: &[u8], orientation: u16) -> VaultResult<Vec<u8>> {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            // Load image
+            let img = image::load_from_memory(image_data)
+                .map_err(|e| VaultError::ThumbnailFailed(e.to_string()))?;
+
+            // Straighten per EXIF before cropping/scaling.
+            let img = apply_orientation(img, orientation);
+
+            // Generate thumbnail
+            let thumb = self.resize_to_thumbnail(&img);
+
+            // Encode as JPEG (smaller than PNG)
+            let mut output = Vec::new();
+            let mut cursor = Cursor::new(&mut output);
+
+            thumb
+                .write_to(&mut cursor, image::ImageFormat::Jpeg)
+                .map_err(|e| VaultError::ThumbnailFailed(e.to_string()))?;
+
+            Ok(output)
+        }));
+
+        match result {
+            Ok(inner) => inner,
+            Err(_) => Err(VaultError::ThumbnailFailed(
+                "decoder panicked on untrusted image data".into(),
+            )),
+        }
     }
     
     /// Resize image to thumbnail (maintaining aspect ratio)
@@ -65,6 +91,98 @@ impl ThumbnailEngine {
         cropped.resize_exact(self.size, self.size, FilterType::Lanczos3)
     }
     
+    /// Generate a thumbnail and, alongside it, a 64-bit perceptual hash.
+    ///
+    /// The hash is a difference hash (dHash) of the same decoded image, so
+    /// near-identical shots collapse to a small Hamming distance. Returns the
+    /// encoded thumbnail bytes and the packed hash. A decode failure in the
+    /// hash step is non-fatal — the thumbnail is still returned with `None`.
+    pub fn generate_with_hash(
+        &self,
+        image_data: &[u8],
+        orientation: u16,
+    ) -> VaultResult<(Vec<u8>, Option<u64>)> {
+        let thumb = self.generate_oriented(image_data, orientation)?;
+        let hash = self.dhash(image_data).ok();
+        Ok((thumb, hash))
+    }
+
+    /// Compute a 64-bit difference hash (dHash) of the encoded image.
+    ///
+    /// The image is reduced to greyscale and resized to 9×8 with a triangle
+    /// filter; for each of the 8 rows the 8 left-to-right adjacent-pixel
+    /// comparisons emit one bit (`pixel[x] > pixel[x+1]`), packing into a
+    /// `u64`. Runs under `catch_unwind` like the rest of the decode path.
+    pub fn dhash(&self, image_data: &[u8]) -> VaultResult<u64> {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let small = image::load_from_memory(image_data)
+                .map_err(|e| VaultError::ThumbnailFailed(e.to_string()))?
+                .grayscale()
+                .resize_exact(9, 8, FilterType::Triangle)
+                .to_luma8();
+
+            let mut hash = 0u64;
+            let mut bit = 0;
+            for y in 0..8u32 {
+                for x in 0..8u32 {
+                    if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                        hash |= 1 << bit;
+                    }
+                    bit += 1;
+                }
+            }
+            Ok(hash)
+        }));
+
+        match result {
+            Ok(inner) => inner,
+            Err(_) => Err(VaultError::ThumbnailFailed(
+                "decoder panicked on untrusted image data".into(),
+            )),
+        }
+    }
+
+    /// Strip identifying metadata from an image and return an upright,
+    /// re-encoded full image alongside its thumbnail.
+    ///
+    /// The source is decoded, straightened per its embedded EXIF `Orientation`
+    /// tag, and re-encoded as JPEG. Re-encoding drops the entire EXIF block —
+    /// GPS coordinates, device serials, and capture timestamps included — so the
+    /// returned full-image bytes carry no location data and already display
+    /// upright (orientation `1`). Returns `(clean_full_image, thumbnail)`.
+    ///
+    /// Shares the panic-safety guarantees of [`generate`](Self::generate).
+    pub fn generate_clean(&self, image_data: &[u8]) -> VaultResult<(Vec<u8>, Vec<u8>)> {
+        let orientation = read_orientation(image_data);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let img = image::load_from_memory(image_data)
+                .map_err(|e| VaultError::ThumbnailFailed(e.to_string()))?;
+
+            // Bake the rotation into the pixels so nothing downstream needs the
+            // (now discarded) orientation tag.
+            let img = apply_orientation(img, orientation);
+
+            let mut full = Vec::new();
+            img.write_to(&mut Cursor::new(&mut full), image::ImageFormat::Jpeg)
+                .map_err(|e| VaultError::ThumbnailFailed(e.to_string()))?;
+
+            let thumb = self.resize_to_thumbnail(&img);
+            let mut thumb_out = Vec::new();
+            thumb
+                .write_to(&mut Cursor::new(&mut thumb_out), image::ImageFormat::Jpeg)
+                .map_err(|e| VaultError::ThumbnailFailed(e.to_string()))?;
+
+            Ok((full, thumb_out))
+        }));
+
+        match result {
+            Ok(inner) => inner,
+            Err(_) => Err(VaultError::ThumbnailFailed(
+                "decoder panicked on untrusted image data".into(),
+            )),
+        }
+    }
+
     /// Generate high-quality thumbnail (for preview)
     pub fn generate_preview(&self, image_data: &[u8], max_dimension: u32) -> VaultResult<Vec<u8>> {
         let img = image::load_from_memory(image_data)
@@ -94,6 +212,45 @@ impl ThumbnailEngine {
     }
 }
 
+/// Read the EXIF `Orientation` tag (1-8) from encoded image data, defaulting to
+/// `1` (upright) when there is no readable EXIF block. Never panics on crafted
+/// input — the parse runs under `catch_unwind`.
+fn read_orientation(image_data: &[u8]) -> u16 {
+    let parsed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        exif::Reader::new()
+            .read_from_container(&mut Cursor::new(image_data))
+            .ok()
+    }))
+    .ok()
+    .flatten();
+
+    parsed
+        .and_then(|e| {
+            e.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|f| f.value.get_uint(0))
+        })
+        .map(|v| v as u16)
+        .filter(|&v| (1..=8).contains(&v))
+        .unwrap_or(1)
+}
+
+/// Apply an EXIF orientation tag (1-8) to an image, returning an upright copy.
+///
+/// The eight tags combine a rotation with an optional mirror; anything outside
+/// the documented range is treated as "already upright".
+fn apply_orientation(img: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,4 +273,51 @@ mod tests {
         let decoded = image::load_from_memory(&thumb).unwrap();
         assert_eq!(decoded.dimensions(), (256, 256));
     }
+
+    #[test]
+    fn test_dhash_matches_identical_and_differs_on_change() {
+        let encode = |img: &image::DynamicImage| {
+            let mut buf = Vec::new();
+            img.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Png).unwrap();
+            buf
+        };
+        let engine = ThumbnailEngine::new(&PathBuf::from("/tmp"), 256);
+
+        let gray = image::DynamicImage::new_rgb8(64, 64);
+        let a = engine.dhash(&encode(&gray)).unwrap();
+        let a2 = engine.dhash(&encode(&gray)).unwrap();
+        assert_eq!(a, a2, "identical images must hash equally");
+
+        // A gradient that darkens left to right sets every `pixel[x] > pixel[x+1]`
+        // bit, while the flat image sets none, so the hashes differ.
+        let mut grad = image::RgbImage::new(64, 64);
+        for (x, _y, px) in grad.enumerate_pixels_mut() {
+            *px = image::Rgb([(255 - x * 4) as u8, 0, 0]);
+        }
+        let b = engine.dhash(&encode(&image::DynamicImage::ImageRgb8(grad))).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_generate_clean_returns_upright_jpeg_and_thumb() {
+        let img = image::DynamicImage::new_rgb8(800, 600);
+        let mut buffer = Vec::new();
+        img.write_to(&mut Cursor::new(&mut buffer), image::ImageFormat::Png).unwrap();
+
+        let engine = ThumbnailEngine::new(&PathBuf::from("/tmp"), 256);
+        let (full, thumb) = engine.generate_clean(&buffer).unwrap();
+
+        // Both outputs are valid JPEGs; the re-encode carries no EXIF block.
+        assert!(image::load_from_memory(&full).is_ok());
+        assert_eq!(image::load_from_memory(&thumb).unwrap().dimensions(), (256, 256));
+        assert!(read_orientation(&full) == 1);
+    }
+
+    #[test]
+    fn test_orientation_rotation_swaps_dimensions() {
+        // Orientation 6 (rotate 90°) turns a 800x600 landscape into 600x800.
+        let img = image::DynamicImage::new_rgb8(800, 600);
+        let rotated = apply_orientation(img, 6);
+        assert_eq!(rotated.dimensions(), (600, 800));
+    }
 }
@@ -8,12 +8,15 @@ use parking_lot::RwLock;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use zeroize::Zeroize;
 
-use crate::crypto::{KeyManager, VaultKey, encrypt_aes_gcm, decrypt_aes_gcm, compute_hmac, verify_hmac, EncryptedData};
+use crate::crypto::{KeyManager, VaultKey, encrypt_aes_gcm, decrypt_aes_gcm, encrypt_committing, decrypt_committing, compute_hmac, verify_hmac, EncryptedData, EncryptionMethod};
+use crate::oplog::{Op, OpPayload, BlobStore, ResolvedState, CHECKPOINT_INTERVAL};
 use crate::index::PhotoIndex;
 use crate::thumbs::ThumbnailEngine;
 use crate::ai::SelfHealingAI;
 use crate::secure_fs::SecureFs;
+use crate::rotation::RotationManager;
 use crate::error::{VaultError, VaultResult};
 
 /// Vault state
@@ -24,6 +27,66 @@ pub enum VaultState {
     Lockdown,
 }
 
+/// Current vault header format version.
+const VAULT_HEADER_VERSION: u8 = 1;
+
+/// Argon2id memory cost (KiB) for the PIN-wrapping key.
+const PIN_ARGON_MEM: u32 = 65536;
+/// Argon2id time cost for the PIN-wrapping key.
+const PIN_ARGON_TIME: u32 = 3;
+/// Argon2id parallelism for the PIN-wrapping key.
+const PIN_ARGON_PAR: u32 = 4;
+
+/// Default Hamming-distance threshold below which two dHashes are treated as
+/// the same shot by [`PhotoVault::find_duplicates`].
+pub const DEFAULT_DHASH_THRESHOLD: u32 = 5;
+
+/// Unencrypted vault header holding the KDF salt/parameters and the vault
+/// master key wrapped under both the PIN and the recovery phrase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultHeader {
+    /// Header format version.
+    version: u8,
+    /// Per-vault random Argon2 salt.
+    salt: Vec<u8>,
+    /// Argon2id memory cost (KiB).
+    argon_mem_kib: u32,
+    /// Argon2id time cost.
+    argon_time: u32,
+    /// Argon2id parallelism.
+    argon_par: u32,
+    /// Vault master key wrapped under the PIN-derived key.
+    vmk_pin: Vec<u8>,
+    /// Vault master key wrapped under the recovery-phrase-derived key.
+    vmk_phrase: Vec<u8>,
+    /// Recovery entropy wrapped under the master (exportable only when unlocked).
+    phrase_entropy: Vec<u8>,
+}
+
+/// PBKDF2-HMAC-SHA512 producing a single 64-byte block (dkLen == hLen).
+fn pbkdf2_hmac_sha512(password: &[u8], salt: &[u8], rounds: u32) -> [u8; 64] {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha512;
+    type HmacSha512 = Hmac<Sha512>;
+
+    // U_1 = PRF(password, salt || INT(1)).
+    let mut mac = HmacSha512::new_from_slice(password).expect("HMAC accepts any key length");
+    mac.update(salt);
+    mac.update(&1u32.to_be_bytes());
+    let mut u: [u8; 64] = mac.finalize().into_bytes().into();
+    let mut result = u;
+
+    for _ in 1..rounds {
+        let mut mac = HmacSha512::new_from_slice(password).expect("HMAC accepts any key length");
+        mac.update(&u);
+        u = mac.finalize().into_bytes().into();
+        for (r, x) in result.iter_mut().zip(u.iter()) {
+            *r ^= x;
+        }
+    }
+    result
+}
+
 /// Vault configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaultConfig {
@@ -41,6 +104,20 @@ pub struct VaultConfig {
     pub ai_enabled: bool,
     /// Failed attempts before lockdown
     pub max_failed_attempts: u8,
+    /// Enable content-defined chunking with cross-photo deduplication
+    #[serde(default = "default_true")]
+    pub dedup_enabled: bool,
+    /// AEAD cipher used for newly written photos, thumbnails and the manifest
+    #[serde(default)]
+    pub encryption_method: EncryptionMethod,
+    /// Stable per-vault actor id used to tag operation-log entries
+    #[serde(default)]
+    pub actor_id: String,
+}
+
+/// Serde default for boolean config fields added after initial release.
+fn default_true() -> bool {
+    true
 }
 
 impl Default for VaultConfig {
@@ -53,6 +130,9 @@ impl Default for VaultConfig {
             thumb_size: 256,
             ai_enabled: true,
             max_failed_attempts: 5,
+            dedup_enabled: true,
+            encryption_method: EncryptionMethod::default(),
+            actor_id: String::new(),
         }
     }
 }
@@ -74,6 +154,21 @@ pub struct PhotoMeta {
     pub imported_at: DateTime<Utc>,
     /// Original creation date (from EXIF)
     pub created_at: Option<DateTime<Utc>>,
+    /// Camera manufacturer (from EXIF), e.g. "Apple"
+    #[serde(default)]
+    pub camera_make: Option<String>,
+    /// Camera model (from EXIF), e.g. "iPhone 14 Pro"
+    #[serde(default)]
+    pub camera_model: Option<String>,
+    /// GPS latitude in decimal degrees (from EXIF, negative = south)
+    #[serde(default)]
+    pub gps_latitude: Option<f64>,
+    /// GPS longitude in decimal degrees (from EXIF, negative = west)
+    #[serde(default)]
+    pub gps_longitude: Option<f64>,
+    /// EXIF orientation tag (1-8, 1 = upright); applied when rendering
+    #[serde(default)]
+    pub orientation: Option<u16>,
     /// HMAC for integrity
     pub hmac: [u8; 32],
     /// Tags (user-defined)
@@ -84,6 +179,75 @@ pub struct PhotoMeta {
     pub is_favorite: bool,
     /// Perceptual hash (for duplicate detection)
     pub phash: Option<String>,
+    /// 64-bit difference hash (dHash) for near-duplicate detection by Hamming
+    /// distance; computed by the thumbnail engine at import.
+    #[serde(default)]
+    pub dhash: Option<u64>,
+    /// BlurHash placeholder string for instant (thumbnail-free) rendering
+    #[serde(default)]
+    pub blurhash: Option<String>,
+    /// Named key that encrypted this photo (None = legacy per-file master key)
+    #[serde(default)]
+    pub key_id: Option<String>,
+    /// Ordered content-defined chunk hashes (empty = legacy single-blob file)
+    #[serde(default)]
+    pub chunks: Vec<String>,
+    /// Rotation epoch whose key currently encrypts this photo. `0` means the
+    /// legacy master-derived file key; a non-zero value selects an
+    /// epoch-scoped key via [`derive_epoch_key`]. Advanced by
+    /// [`PhotoVault::rotate_keys`].
+    #[serde(default)]
+    pub key_epoch: u64,
+    /// `true` if the source carried GPS coordinates that were stripped before
+    /// the photo was persisted. A privacy vault records the removal rather than
+    /// silently retaining (or silently discarding) geotags.
+    #[serde(default)]
+    pub gps_removed: bool,
+    /// `true` if this photo was written by the chunked [`PhotoVault::import_chunk`]
+    /// pipeline and is stored in the STREAM construction (see
+    /// [`crate::crypto::encrypt_stream`]) rather than the single-blob container
+    /// read by [`crate::crypto::decrypt`]. Streamed photos have no thumbnail,
+    /// perceptual hash, or dedup entry.
+    #[serde(default)]
+    pub streamed: bool,
+}
+
+/// A named key entry, persisted beside the vault with its AEAD key wrapped
+/// under the master (PIN-derived) key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultKeyEntry {
+    /// Stable key id.
+    id: String,
+    /// Human-readable name.
+    name: String,
+    /// Mount this key automatically on unlock.
+    automount: bool,
+    /// Number of photos encrypted under this key.
+    photo_count: u64,
+    /// The album AEAD key, encrypted under the master key.
+    wrapped_key: Vec<u8>,
+}
+
+/// Registry of named keys persisted (encrypted) beside the vault.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct KeyRegistry {
+    entries: Vec<VaultKeyEntry>,
+    default_key: Option<String>,
+}
+
+/// Public metadata for a named key.
+#[derive(Debug, Clone)]
+pub struct KeyInfo {
+    /// Key id.
+    pub id: String,
+    /// Human-readable name.
+    pub name: String,
+    /// Whether the key is currently mounted.
+    pub mounted: bool,
+    /// Whether the key automounts on unlock.
+    pub automount: bool,
+    /// Number of photos encrypted under this key.
+    pub photo_count: u64,
 }
 
 /// Photo Vault - Main entry point
@@ -106,6 +270,14 @@ pub struct PhotoVault {
     fs: SecureFs,
     /// Failed unlock attempts
     failed_attempts: RwLock<u8>,
+    /// Named key registry (persisted, encrypted under master)
+    key_registry: RwLock<KeyRegistry>,
+    /// Mounted album keys (runtime only, zeroized on unmount/lock)
+    mounted_keys: RwLock<std::collections::HashMap<String, VaultKey>>,
+    /// Content-addressed chunk store (only when unlocked)
+    chunks: RwLock<Option<crate::chunk_store::ChunkStore>>,
+    /// Operation log for multi-device sync (only when unlocked)
+    oplog: RwLock<Option<crate::oplog::OpLog>>,
 }
 
 impl PhotoVault {
@@ -113,8 +285,21 @@ impl PhotoVault {
     // INITIALIZATION
     // ═══════════════════════════════════════════════════════════════════════
     
-    /// Create a new vault at the given path
+    /// Create a new vault at the given path using the default AEAD cipher.
     pub fn create<P: AsRef<Path>>(path: P, pin: &str) -> VaultResult<Self> {
+        Self::create_with_method(path, pin, EncryptionMethod::default())
+    }
+
+    /// Create a new vault at the given path with an explicit AEAD cipher.
+    ///
+    /// The chosen method is stored in the manifest and tagged into every blob,
+    /// so photos, thumbnails and the manifest remain decryptable even if a
+    /// later vault default differs.
+    pub fn create_with_method<P: AsRef<Path>>(
+        path: P,
+        pin: &str,
+        method: EncryptionMethod,
+    ) -> VaultResult<Self> {
         let root = path.as_ref().to_path_buf();
         
         if root.exists() {
@@ -127,22 +312,75 @@ impl PhotoVault {
         std::fs::create_dir_all(root.join("thumbs"))?;
         std::fs::create_dir_all(root.join("db"))?;
         
-        // Generate master seed from PIN
-        let seed = Self::derive_seed_from_pin(pin)?;
-        let keys = Arc::new(KeyManager::from_master_seed(&seed)?);
-        
-        // Create and save encrypted config
-        let config = VaultConfig::default();
         let fs = SecureFs::new(&root);
-        
-        // Encrypt and save manifest
+
+        // Per-vault random Argon2 salt so identical PINs never collide.
+        use rand::RngCore;
+        let mut salt = vec![0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+
+        // The actual vault master key is random and wrapped under both the
+        // PIN-derived key and a BIP39 recovery-phrase-derived key.
+        let mut vmk = [0u8; 64];
+        rand::thread_rng().fill_bytes(&mut vmk);
+        let keys = Arc::new(KeyManager::from_master_seed(&vmk)?);
+
+        // Generate a 24-word recovery phrase from fresh 256-bit entropy.
+        let mut entropy = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut entropy);
+        let mnemonic = crate::crypto::Mnemonic::from_seed(&entropy)?;
+
+        let pin_kek = Self::derive_pin_kek(pin, &salt, PIN_ARGON_MEM, PIN_ARGON_TIME, PIN_ARGON_PAR)?;
+        let phrase_kek = Self::derive_phrase_kek(&mnemonic.phrase())?;
+
+        let header = VaultHeader {
+            version: VAULT_HEADER_VERSION,
+            salt,
+            argon_mem_kib: PIN_ARGON_MEM,
+            argon_time: PIN_ARGON_TIME,
+            argon_par: PIN_ARGON_PAR,
+            // PIN/phrase-derived keys are low-entropy, so the VMK wrap uses the
+            // key-committing envelope rather than plain AEAD: it closes the
+            // partitioning-oracle attack a low-entropy key would otherwise be
+            // open to (see `crate::crypto::encrypt_committing`).
+            vmk_pin: encrypt_committing(EncryptionMethod::Aes256Gcm, &pin_kek, &vmk)?,
+            vmk_phrase: encrypt_committing(EncryptionMethod::Aes256Gcm, &phrase_kek, &vmk)?,
+            // The recovery entropy itself is only exportable while unlocked.
+            phrase_entropy: encrypt_aes_gcm(keys.index_key(), &entropy, &[])?.to_bytes(),
+        };
+        fs.write_file("header.json", &serde_json::to_vec(&header)?)?;
+        vmk.zeroize();
+        entropy.zeroize();
+
+        // Create and save encrypted config
+        let config = VaultConfig {
+            encryption_method: method,
+            actor_id: Uuid::new_v4().to_string(),
+            ..VaultConfig::default()
+        };
+
+        // Encrypt and save manifest (self-describing tagged blob).
         let manifest = serde_json::to_vec(&config)?;
-        let encrypted = encrypt_aes_gcm(keys.index_key(), &manifest)?;
-        fs.write_file("manifest.enc", &encrypted.to_bytes())?;
-        
+        fs.write_file(
+            "manifest.enc",
+            &crate::crypto::encrypt(method, keys.index_key(), &manifest)?,
+        )?;
+        
+        // Initialize an empty named-key registry.
+        let registry = KeyRegistry::default();
+        let registry_bytes = serde_json::to_vec(&registry)?;
+        let encrypted_registry = encrypt_aes_gcm(keys.index_key(), &registry_bytes, &[])?;
+        fs.write_file("keys.enc", &encrypted_registry.to_bytes())?;
+
+        // Initialize the content-addressed chunk store.
+        let chunks = crate::chunk_store::ChunkStore::open(&root, &keys)?;
+
+        // Initialize an empty operation log tagged with this vault's actor id.
+        let oplog = crate::oplog::OpLog::new(config.actor_id.clone());
+
         // Initialize index
         let index = PhotoIndex::create(&root, &keys)?;
-        
+
         // Initialize thumbnail engine
         let thumbs = ThumbnailEngine::new(&root, config.thumb_size);
         
@@ -163,9 +401,13 @@ impl PhotoVault {
             ai: RwLock::new(ai),
             fs,
             failed_attempts: RwLock::new(0),
+            key_registry: RwLock::new(registry),
+            mounted_keys: RwLock::new(std::collections::HashMap::new()),
+            chunks: RwLock::new(Some(chunks)),
+            oplog: RwLock::new(Some(oplog)),
         })
     }
-    
+
     /// Open an existing vault
     pub fn open<P: AsRef<Path>>(path: P) -> VaultResult<Self> {
         let root = path.as_ref().to_path_buf();
@@ -187,9 +429,13 @@ impl PhotoVault {
             ai: RwLock::new(None),
             fs,
             failed_attempts: RwLock::new(0),
+            key_registry: RwLock::new(KeyRegistry::default()),
+            mounted_keys: RwLock::new(std::collections::HashMap::new()),
+            chunks: RwLock::new(None),
+            oplog: RwLock::new(None),
         })
     }
-    
+
     // ═══════════════════════════════════════════════════════════════════════
     // UNLOCK / LOCK
     // ═══════════════════════════════════════════════════════════════════════
@@ -201,54 +447,187 @@ impl PhotoVault {
             return Err(VaultError::TooManyAttempts);
         }
         
-        // Derive keys from PIN
-        let seed = Self::derive_seed_from_pin(pin)?;
-        let keys = Arc::new(KeyManager::from_master_seed(&seed)?);
-        
-        // Try to decrypt manifest
-        let manifest_enc = self.fs.read_file("manifest.enc")?;
-        let encrypted = EncryptedData::from_bytes_aes(&manifest_enc)?;
-        
-        match decrypt_aes_gcm(keys.index_key(), &encrypted) {
-            Ok(manifest_data) => {
-                // Parse config
-                let config: VaultConfig = serde_json::from_slice(&manifest_data)?;
-                
-                // Initialize components
-                let index = PhotoIndex::open(&self.root, &keys)?;
-                let thumbs = ThumbnailEngine::new(&self.root, config.thumb_size);
-                let ai = if config.ai_enabled {
-                    Some(SelfHealingAI::load(&self.root)?)
-                } else {
-                    None
-                };
-                
-                // Update state
-                *self.config.write() = config;
-                *self.keys.write() = Some(keys);
-                *self.index.write() = Some(index);
-                *self.thumbs.write() = Some(thumbs);
-                *self.ai.write() = ai;
-                *self.state.write() = VaultState::Unlocked;
-                *self.failed_attempts.write() = 0;
-                
-                Ok(())
+        // Derive the PIN key using the per-vault salt, then unwrap the VMK.
+        let header = self.read_header()?;
+        let pin_kek = Self::derive_pin_kek(
+            pin,
+            &header.salt,
+            header.argon_mem_kib,
+            header.argon_time,
+            header.argon_par,
+        )?;
+
+        match decrypt_committing(&pin_kek, &header.vmk_pin) {
+            Ok(mut vmk) => {
+                let keys = Arc::new(KeyManager::from_master_seed(&vmk)?);
+                vmk.zeroize();
+                self.finalize_unlock(keys)
             }
             Err(_) => {
                 // Wrong PIN
                 let mut attempts = self.failed_attempts.write();
                 *attempts += 1;
-                
+
                 let max = self.config.read().max_failed_attempts;
                 if *attempts >= max {
                     *self.state.write() = VaultState::Lockdown;
                     return Err(VaultError::TooManyAttempts);
                 }
-                
+
                 Err(VaultError::InvalidPin)
             }
         }
     }
+
+    /// Unlock the vault with the BIP39 recovery phrase instead of the PIN.
+    pub fn unlock_with_phrase(&self, words: &str) -> VaultResult<()> {
+        if *self.state.read() == VaultState::Lockdown {
+            return Err(VaultError::TooManyAttempts);
+        }
+        let header = self.read_header()?;
+        let mut vmk = self.unwrap_vmk_with_phrase(words, &header)?;
+        let keys = Arc::new(KeyManager::from_master_seed(&vmk)?);
+        vmk.zeroize();
+        self.finalize_unlock(keys)
+    }
+
+    /// Set a new PIN using the recovery phrase (for a forgotten PIN).
+    pub fn reset_pin_with_phrase(&self, words: &str, new_pin: &str) -> VaultResult<()> {
+        let mut header = self.read_header()?;
+        let vmk = self.unwrap_vmk_with_phrase(words, &header)?;
+
+        use rand::RngCore;
+        let mut salt = vec![0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let pin_kek = Self::derive_pin_kek(new_pin, &salt, PIN_ARGON_MEM, PIN_ARGON_TIME, PIN_ARGON_PAR)?;
+
+        header.salt = salt;
+        header.argon_mem_kib = PIN_ARGON_MEM;
+        header.argon_time = PIN_ARGON_TIME;
+        header.argon_par = PIN_ARGON_PAR;
+        header.vmk_pin = encrypt_committing(EncryptionMethod::Aes256Gcm, &pin_kek, &vmk)?;
+
+        self.fs.write_file("header.json", &serde_json::to_vec(&header)?)?;
+        // Recovering the PIN also clears any lockdown.
+        *self.failed_attempts.write() = 0;
+        if *self.state.read() == VaultState::Lockdown {
+            *self.state.write() = VaultState::Locked;
+        }
+        Ok(())
+    }
+
+    /// Change the vault PIN by re-wrapping the master key under a key derived
+    /// from `new_pin`.
+    ///
+    /// The photo data is never re-encrypted — only the wrapped-key blob in the
+    /// header changes. `old_pin` is verified by unwrapping the VMK first; a
+    /// wrong old PIN or a blank new PIN is rejected before anything is written,
+    /// and the header rewrite is atomic (temp file + rename) so an interrupted
+    /// change can never brick the vault.
+    pub fn change_pin(&self, old_pin: &str, new_pin: &str) -> VaultResult<()> {
+        if new_pin.is_empty() {
+            return Err(VaultError::InvalidPin);
+        }
+
+        let mut header = self.read_header()?;
+
+        // Verify the old PIN by unwrapping the VMK with its derived key.
+        let old_kek = Self::derive_pin_kek(
+            old_pin,
+            &header.salt,
+            header.argon_mem_kib,
+            header.argon_time,
+            header.argon_par,
+        )?;
+        let mut vmk = decrypt_committing(&old_kek, &header.vmk_pin).map_err(|_| VaultError::InvalidPin)?;
+
+        // Re-wrap the VMK under a fresh salt + key derived from the new PIN.
+        use rand::RngCore;
+        let mut salt = vec![0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let new_kek =
+            Self::derive_pin_kek(new_pin, &salt, PIN_ARGON_MEM, PIN_ARGON_TIME, PIN_ARGON_PAR)?;
+
+        header.salt = salt;
+        header.argon_mem_kib = PIN_ARGON_MEM;
+        header.argon_time = PIN_ARGON_TIME;
+        header.argon_par = PIN_ARGON_PAR;
+        header.vmk_pin = encrypt_committing(EncryptionMethod::Aes256Gcm, &new_kek, &vmk)?;
+        vmk.zeroize();
+
+        self.fs.write_file("header.json", &serde_json::to_vec(&header)?)?;
+        Ok(())
+    }
+
+    /// Export the 24-word recovery phrase (only while unlocked).
+    pub fn export_recovery_phrase(&self) -> VaultResult<String> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+        let header = self.read_header()?;
+        let wrapped = EncryptedData::parse(&header.phrase_entropy)?;
+        let entropy = decrypt_aes_gcm(keys.index_key(), &wrapped, &[])?;
+        let mnemonic = crate::crypto::Mnemonic::from_seed(&entropy)?;
+        Ok(mnemonic.phrase())
+    }
+
+    /// Clone the master key for epoch-key rotation (only while unlocked).
+    pub fn master_key(&self) -> VaultResult<VaultKey> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+        Ok(keys.master().clone())
+    }
+
+    /// Decrypt the manifest and bring the vault online with `keys`.
+    fn finalize_unlock(&self, keys: Arc<KeyManager>) -> VaultResult<()> {
+        let manifest_enc = self.fs.read_file("manifest.enc")?;
+        let manifest_data = crate::crypto::decrypt(keys.index_key(), &manifest_enc)?;
+        let config: VaultConfig = serde_json::from_slice(&manifest_data)?;
+
+        let index = PhotoIndex::open(&self.root, &keys)?;
+        let chunk_store = crate::chunk_store::ChunkStore::open(&self.root, &keys)?;
+        let thumbs = ThumbnailEngine::new(&self.root, config.thumb_size);
+        let ai = if config.ai_enabled {
+            Some(SelfHealingAI::load(&self.root)?)
+        } else {
+            None
+        };
+
+        // Restore the local operation log (for multi-device sync).
+        let actor_id = config.actor_id.clone();
+        let ops = self.load_ops(keys.index_key())?;
+        let oplog = crate::oplog::OpLog::load(actor_id, ops);
+
+        *self.config.write() = config;
+        *self.keys.write() = Some(keys);
+        *self.index.write() = Some(index);
+        *self.chunks.write() = Some(chunk_store);
+        *self.thumbs.write() = Some(thumbs);
+        *self.ai.write() = ai;
+        *self.oplog.write() = Some(oplog);
+        *self.state.write() = VaultState::Unlocked;
+        *self.failed_attempts.write() = 0;
+
+        // Load the named-key registry and automount flagged keys.
+        self.load_registry()?;
+        self.automount_keys();
+
+        Ok(())
+    }
+
+    /// Unwrap the vault master key with a recovery phrase.
+    fn unwrap_vmk_with_phrase(&self, words: &str, header: &VaultHeader) -> VaultResult<Vec<u8>> {
+        let phrase_kek = Self::derive_phrase_kek(words)?;
+        decrypt_committing(&phrase_kek, &header.vmk_phrase)
+            .map_err(|_| VaultError::DecryptionFailed("invalid recovery phrase".into()))
+    }
+
+    /// Read and parse the unencrypted vault header.
+    fn read_header(&self) -> VaultResult<VaultHeader> {
+        let bytes = self.fs.read_file("header.json")?;
+        serde_json::from_slice(&bytes).map_err(VaultError::from)
+    }
     
     /// Lock vault (zeroize all keys)
     pub fn lock(&self) {
@@ -256,6 +635,10 @@ impl PhotoVault {
         *self.index.write() = None;
         *self.thumbs.write() = None;
         *self.ai.write() = None;
+        *self.chunks.write() = None;
+        *self.oplog.write() = None;
+        // Dropping the mounted keys zeroizes their material.
+        self.mounted_keys.write().clear();
         *self.state.write() = VaultState::Locked;
     }
     
@@ -268,51 +651,284 @@ impl PhotoVault {
     // PHOTO OPERATIONS
     // ═══════════════════════════════════════════════════════════════════════
     
-    /// Import a photo into the vault
+    /// Import a photo into the vault under the default mounted key.
     pub fn import_photo(&self, source: &Path, original_name: &str) -> VaultResult<String> {
+        self.import_photo_to(source, original_name, None)
+    }
+
+    /// Import a photo, optionally encrypting it under a specific named key.
+    ///
+    /// When `key_id` is `None` the default mounted key is used, falling back to
+    /// the legacy per-file master key when no named key is mounted.
+    pub fn import_photo_to(
+        &self,
+        source: &Path,
+        original_name: &str,
+        key_id: Option<&str>,
+    ) -> VaultResult<String> {
+        // Read the source into memory and hand off to the byte importer so the
+        // encryption path is identical whether the photo came from a file or
+        // from an in-memory buffer.
+        let plaintext = std::fs::read(source)?;
+        self.import_bytes_to(&plaintext, original_name, key_id)
+    }
+
+    /// Import a photo straight from an in-memory buffer, never writing the
+    /// plaintext to disk. Used by the Android/JNI binding so decrypted bytes
+    /// handed down from Kotlin are encrypted in place.
+    pub fn import_photo_bytes(&self, data: &[u8], name: &str) -> VaultResult<String> {
+        self.import_bytes_to(data, name, None)
+    }
+
+    /// Begin a chunked, bounded-memory import. Bytes are fed in with
+    /// [`import_chunk`](Self::import_chunk) and sealed with
+    /// [`import_end`](Self::import_end), letting a caller (e.g. Kotlin) stream a
+    /// large photo or video without ever materializing the whole array — and
+    /// without touching `/data/local/tmp` or any other plaintext staging file.
+    ///
+    /// Chunked imports trade the EXIF/thumbnail/phash/dedup pipeline of
+    /// [`import_bytes_to`](Self::import_bytes_to) for that bounded memory: the
+    /// stored photo is a [`PhotoMeta::streamed`] entry with no thumbnail, no
+    /// perceptual hashes, and no content-addressed dedup, encrypted straight to
+    /// disk chunk-by-chunk via [`crate::crypto::StreamEncryptor`] as bytes
+    /// arrive.
+    pub fn import_begin(&self, name: &str, key_id: Option<&str>) -> VaultResult<ImportSession> {
         self.ensure_unlocked()?;
-        
         let keys = self.keys.read();
         let keys = keys.as_ref().unwrap();
-        
-        // Read source file
-        let plaintext = std::fs::read(source)?;
-        let original_size = plaintext.len() as u64;
-        
+
+        let id = Uuid::new_v4().to_string();
+        let effective_key = match key_id {
+            Some(k) => Some(k.to_string()),
+            None => self.default_mounted_key(),
+        };
+        let file_key = match &effective_key {
+            Some(kid) => {
+                let mounted = self.mounted_keys.read();
+                let album = mounted
+                    .get(kid)
+                    .ok_or_else(|| VaultError::KeyNotMounted(kid.clone()))?;
+                crate::crypto::derive_key(album.expose(), id.as_bytes(), crate::crypto::contexts::FILE_KEY)?
+            }
+            None => keys.derive_file_key(&id)?,
+        };
+
+        let (encryptor, prefix) = crate::crypto::StreamEncryptor::new(&file_key)?;
+        let photo_path = format!("photos/{}.enc", id);
+        let mut handle = self.fs.begin_stream_write(&photo_path)?;
+        handle.write_chunk(&prefix)?;
+
+        Ok(ImportSession {
+            id,
+            original_name: name.to_string(),
+            key_id: effective_key,
+            encryptor: Some(encryptor),
+            handle: Some(handle),
+            total_len: 0,
+            sniff: Vec::new(),
+        })
+    }
+
+    /// Append a plaintext chunk to an in-progress import, sealing and writing
+    /// out every full [`crate::crypto::StreamEncryptor`] chunk it completes
+    /// without ever holding more than one chunk of plaintext in memory.
+    pub fn import_chunk(&self, session: &mut ImportSession, data: &[u8]) -> VaultResult<()> {
+        if session.sniff.len() < 16 {
+            let take = (16 - session.sniff.len()).min(data.len());
+            session.sniff.extend_from_slice(&data[..take]);
+        }
+        session.total_len += data.len() as u64;
+
+        let encryptor = session
+            .encryptor
+            .as_mut()
+            .ok_or(VaultError::VaultLocked)?;
+        let sealed = encryptor.push(data)?;
+        session
+            .handle
+            .as_mut()
+            .ok_or(VaultError::VaultLocked)?
+            .write_chunk(&sealed)
+    }
+
+    /// Finalize a chunked import: seal the trailing partial chunk, fsync and
+    /// rename the encrypted file into place, and record a minimal
+    /// [`PhotoMeta`] for it (no EXIF, thumbnail, phash or dedup, matching the
+    /// leaner pipeline documented on [`import_begin`](Self::import_begin)).
+    pub fn import_end(&self, mut session: ImportSession) -> VaultResult<String> {
+        let encryptor = session.encryptor.take().ok_or(VaultError::VaultLocked)?;
+        let mut handle = session.handle.take().ok_or(VaultError::VaultLocked)?;
+
+        let sealed = encryptor.finish()?;
+        handle.write_chunk(&sealed)?;
+        handle.finish()?;
+
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+
+        let mime_type = Self::detect_mime(&session.sniff);
+        let photo_path = format!("photos/{}.enc", session.id);
+        let encrypted_size = self.fs.file_size(&photo_path).unwrap_or(session.total_len);
+
+        let meta = PhotoMeta {
+            id: session.id.clone(),
+            original_name: session.original_name.clone(),
+            encrypted_size,
+            original_size: session.total_len,
+            mime_type,
+            imported_at: Utc::now(),
+            created_at: None,
+            camera_make: None,
+            camera_model: None,
+            gps_latitude: None,
+            gps_longitude: None,
+            orientation: None,
+            hmac: [0u8; 32],
+            tags: Vec::new(),
+            is_hidden: false,
+            is_favorite: false,
+            phash: None,
+            dhash: None,
+            blurhash: None,
+            key_id: session.key_id.clone(),
+            chunks: Vec::new(),
+            key_epoch: 0,
+            gps_removed: false,
+            streamed: true,
+        };
+
+        if let Some(ref mut index) = *self.index.write() {
+            index.add_photo(&meta)?;
+        }
+        self.append_op(keys, OpPayload::ImportPhoto(Box::new(meta.clone())))?;
+
+        if let Some(kid) = &session.key_id {
+            let mut registry = self.key_registry.write();
+            if let Some(entry) = registry.entries.iter_mut().find(|e| &e.id == kid) {
+                entry.photo_count += 1;
+            }
+            let snapshot = registry.clone();
+            drop(registry);
+            let _ = self.persist_registry(keys, &snapshot);
+        }
+
+        Ok(session.id.clone())
+    }
+
+    /// Core import path shared by the file and in-memory entry points.
+    fn import_bytes_to(
+        &self,
+        plaintext: &[u8],
+        original_name: &str,
+        key_id: Option<&str>,
+    ) -> VaultResult<String> {
+        self.ensure_unlocked()?;
+
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+
+        // Parse EXIF (best-effort) for capture date, camera, GPS and
+        // orientation before we strip it from the stored bytes.
+        let exif = Self::extract_exif(plaintext);
+        let gps_present = exif.gps_latitude.is_some() || exif.gps_longitude.is_some();
+
+        // Strip identifying metadata by re-encoding decodable images upright;
+        // non-images (e.g. video) and undecodable blobs are stored verbatim.
+        let cleaned = self
+            .thumbs
+            .read()
+            .as_ref()
+            .and_then(|t| t.generate_clean(plaintext).ok());
+        let (stored, stored_thumb, gps_removed): (&[u8], Option<Vec<u8>>, bool) = match &cleaned {
+            Some((full, thumb)) => (full.as_slice(), Some(thumb.clone()), gps_present),
+            None => (plaintext, None, false),
+        };
+
+        let original_size = stored.len() as u64;
+
         // Generate unique ID
         let id = Uuid::new_v4().to_string();
-        
-        // Derive file-specific key
-        let file_key = keys.derive_file_key(&id)?;
-        
-        // Encrypt
-        let encrypted = encrypt_aes_gcm(&file_key, &plaintext)?;
-        let encrypted_bytes = encrypted.to_bytes();
-        let encrypted_size = encrypted_bytes.len() as u64;
-        
-        // Compute HMAC
-        let hmac = compute_hmac(keys.hmac_key(), &encrypted_bytes);
-        
+
+        // Resolve which named key (if any) should encrypt this photo, then
+        // derive the file-specific key from the chosen parent.
+        let effective_key = match key_id {
+            Some(k) => Some(k.to_string()),
+            None => self.default_mounted_key(),
+        };
+        // Named-key photos stay single-blob under their album key so their
+        // security depends on the mounted key. Unkeyed photos use the
+        // content-addressed chunk store for cross-photo deduplication.
+        let use_chunks = effective_key.is_none() && self.config.read().dedup_enabled;
+
+        let (encrypted_size, hmac, chunk_hashes) = if use_chunks {
+            let mut store_guard = self.chunks.write();
+            let store = store_guard.as_mut().ok_or(VaultError::VaultLocked)?;
+            let hashes = store.store(keys, stored)?;
+            // Chunks are individually AEAD-authenticated, so no whole-file HMAC.
+            (original_size, [0u8; 32], hashes)
+        } else {
+            let file_key = match &effective_key {
+                Some(kid) => {
+                    let mounted = self.mounted_keys.read();
+                    let album = mounted
+                        .get(kid)
+                        .ok_or_else(|| VaultError::KeyNotMounted(kid.clone()))?;
+                    crate::crypto::derive_key(album.expose(), id.as_bytes(), crate::crypto::contexts::FILE_KEY)?
+                }
+                None => keys.derive_file_key(&id)?,
+            };
+            let method = self.config.read().encryption_method;
+            let encrypted_bytes = crate::crypto::encrypt(method, &file_key, stored)?;
+            let hmac = compute_hmac(keys.hmac_key(), &encrypted_bytes);
+            let photo_path = format!("photos/{}.enc", id);
+            self.fs.write_file(&photo_path, &encrypted_bytes)?;
+            (encrypted_bytes.len() as u64, hmac, Vec::new())
+        };
+
         // Detect MIME type
-        let mime_type = Self::detect_mime(&plaintext);
-        
+        let mime_type = Self::detect_mime(stored);
+
         // Generate perceptual hash for duplicate detection
-        let phash = self.compute_phash(&plaintext);
-        
-        // Save encrypted file
-        let photo_path = format!("photos/{}.enc", id);
-        self.fs.write_file(&photo_path, &encrypted_bytes)?;
-        
-        // Generate and save thumbnail
+        let phash = self.compute_phash(stored);
+
+        // Cleaned images are already upright, so their thumbnail needs no
+        // further rotation; otherwise fall back to the source orientation.
+        let orientation = if cleaned.is_some() {
+            1
+        } else {
+            exif.orientation.unwrap_or(1)
+        };
+
+        // Save the thumbnail (reusing the one produced while stripping metadata
+        // when available) and capture the difference hash of the stored bytes.
+        let mut dhash = None;
         if let Some(ref thumbs) = *self.thumbs.read() {
-            if let Ok(thumb_data) = thumbs.generate(&plaintext) {
+            let thumb_data = match &stored_thumb {
+                Some(thumb) => {
+                    dhash = thumbs.dhash(stored).ok();
+                    Some(thumb.clone())
+                }
+                None => match thumbs.generate_with_hash(stored, orientation) {
+                    Ok((thumb, hash)) => {
+                        dhash = hash;
+                        Some(thumb)
+                    }
+                    Err(_) => None,
+                },
+            };
+            if let Some(thumb_data) = thumb_data {
+                let method = self.config.read().encryption_method;
                 let thumb_key = keys.derive_thumb_key(&id)?;
-                let encrypted_thumb = encrypt_aes_gcm(&thumb_key, &thumb_data)?;
+                let encrypted_thumb = crate::crypto::encrypt(method, &thumb_key, &thumb_data)?;
                 let thumb_path = format!("thumbs/{}.enc", id);
-                self.fs.write_file(&thumb_path, &encrypted_thumb.to_bytes())?;
+                self.fs.write_file(&thumb_path, &encrypted_thumb)?;
             }
         }
-        
+
+        // Compute a BlurHash placeholder from the decoded image (best-effort,
+        // panic-safe like the rest of the decode path).
+        let blurhash = Self::compute_blurhash(stored);
+
         // Create metadata
         let meta = PhotoMeta {
             id: id.clone(),
@@ -321,24 +937,53 @@ impl PhotoVault {
             original_size,
             mime_type,
             imported_at: Utc::now(),
-            created_at: None, // TODO: Extract from EXIF
+            created_at: exif.created_at,
+            camera_make: exif.camera_make,
+            camera_model: exif.camera_model,
+            // Location data is never retained once stripped from the bytes.
+            gps_latitude: if gps_removed { None } else { exif.gps_latitude },
+            gps_longitude: if gps_removed { None } else { exif.gps_longitude },
+            // A cleaned image is stored upright, so it needs no orientation tag.
+            orientation: if cleaned.is_some() { None } else { exif.orientation },
             hmac,
             tags: Vec::new(),
             is_hidden: false,
             is_favorite: false,
             phash,
+            dhash,
+            blurhash,
+            key_id: effective_key.clone(),
+            chunks: chunk_hashes,
+            key_epoch: 0,
+            gps_removed,
+            streamed: false,
         };
-        
+
         // Add to index
         if let Some(ref mut index) = *self.index.write() {
             index.add_photo(&meta)?;
         }
+
+        // Record the import in the operation log for multi-device sync.
+        self.append_op(keys, OpPayload::ImportPhoto(Box::new(meta.clone())))?;
+
+        // Bump the per-key photo count.
+        if let Some(kid) = &effective_key {
+            let mut registry = self.key_registry.write();
+            if let Some(entry) = registry.entries.iter_mut().find(|e| &e.id == kid) {
+                entry.photo_count += 1;
+            }
+            let snapshot = registry.clone();
+            drop(registry);
+            let _ = self.persist_registry(keys, &snapshot);
+        }
         
-        // Notify AI
+        // Notify AI and fingerprint the image for duplicate detection.
         if let Some(ref mut ai) = *self.ai.write() {
             ai.on_photo_imported(&id);
+            let _ = ai.hash_photo(&id, plaintext);
         }
-        
+
         Ok(id)
     }
     
@@ -351,22 +996,48 @@ impl PhotoVault {
         
         // Get metadata from index
         let meta = self.get_photo_meta(id)?;
-        
-        // Read encrypted file
+
+        // Chunked photos are reassembled from the content-addressed store.
+        if !meta.chunks.is_empty() {
+            let store_guard = self.chunks.read();
+            let store = store_guard.as_ref().ok_or(VaultError::VaultLocked)?;
+            return store.read(keys, &meta.chunks);
+        }
+
+        // Decrypt with the named key that encrypted it, or the legacy/epoch path.
+        let file_key = match &meta.key_id {
+            Some(kid) => {
+                let mounted = self.mounted_keys.read();
+                let album = mounted
+                    .get(kid)
+                    .ok_or_else(|| VaultError::KeyNotMounted(kid.clone()))?;
+                crate::crypto::derive_key(album.expose(), id.as_bytes(), crate::crypto::contexts::FILE_KEY)?
+            }
+            None => Self::epoch_file_key(keys, id, meta.key_epoch)?,
+        };
+
         let photo_path = format!("photos/{}.enc", id);
+
+        // Photos written by the chunked import pipeline are stored in the
+        // STREAM construction, authenticated per-chunk rather than under a
+        // single whole-file HMAC.
+        if meta.streamed {
+            let mut plaintext = Vec::new();
+            self.fs.read_file_stream(&photo_path, &file_key, &mut plaintext)?;
+            return Ok(plaintext);
+        }
+
+        // Read encrypted file
         let encrypted_bytes = self.fs.read_file(&photo_path)?;
-        
+
         // Verify HMAC
         if !verify_hmac(keys.hmac_key(), &encrypted_bytes, &meta.hmac) {
             return Err(VaultError::HmacVerificationFailed);
         }
-        
-        // Decrypt
-        let file_key = keys.derive_file_key(id)?;
-        let encrypted = EncryptedData::from_bytes_aes(&encrypted_bytes)?;
-        decrypt_aes_gcm(&file_key, &encrypted)
+
+        crate::crypto::decrypt(&file_key, &encrypted_bytes)
     }
-    
+
     /// Get decrypted thumbnail by ID
     pub fn get_thumbnail(&self, id: &str) -> VaultResult<Vec<u8>> {
         self.ensure_unlocked()?;
@@ -376,12 +1047,111 @@ impl PhotoVault {
         
         let thumb_path = format!("thumbs/{}.enc", id);
         let encrypted_bytes = self.fs.read_file(&thumb_path)?;
-        
-        let thumb_key = keys.derive_thumb_key(id)?;
-        let encrypted = EncryptedData::from_bytes_aes(&encrypted_bytes)?;
-        decrypt_aes_gcm(&thumb_key, &encrypted)
+
+        let epoch = self.get_photo_meta(id).map(|m| m.key_epoch).unwrap_or(0);
+        let thumb_key = Self::epoch_thumb_key(keys, id, epoch)?;
+        crate::crypto::decrypt(&thumb_key, &encrypted_bytes)
     }
-    
+
+    /// Derive the per-file photo key for a given rotation epoch.
+    ///
+    /// Epoch `0` is the legacy master-derived file key; a non-zero epoch keys
+    /// the file under [`derive_epoch_key`] so old backups can be re-encrypted
+    /// forward for forward secrecy.
+    fn epoch_file_key(keys: &KeyManager, id: &str, epoch: u64) -> VaultResult<VaultKey> {
+        if epoch == 0 {
+            keys.derive_file_key(id)
+        } else {
+            let ek = crate::crypto::derive_epoch_key(keys.master(), epoch)?;
+            crate::crypto::derive_key(ek.expose(), id.as_bytes(), crate::crypto::contexts::FILE_KEY)
+        }
+    }
+
+    /// Derive the per-file thumbnail key for a given rotation epoch.
+    fn epoch_thumb_key(keys: &KeyManager, id: &str, epoch: u64) -> VaultResult<VaultKey> {
+        if epoch == 0 {
+            keys.derive_thumb_key(id)
+        } else {
+            let ek = crate::crypto::derive_epoch_key(keys.master(), epoch)?;
+            crate::crypto::derive_key(ek.expose(), id.as_bytes(), crate::crypto::contexts::THUMBS)
+        }
+    }
+
+    /// Rotate encryption keys, re-encrypting every eligible photo and thumbnail
+    /// from its current epoch key to the freshly-advanced epoch.
+    ///
+    /// The rotation is resumable: each photo's `key_epoch` is persisted to the
+    /// index immediately after its blobs are rewritten, so an interrupted run
+    /// re-processes only the entries still on a stale epoch. Chunked, streamed
+    /// and named-key photos are left untouched.
+    pub fn rotate_keys(&self) -> VaultResult<RotateReport> {
+        self.ensure_unlocked()?;
+
+        let master = self.master_key()?;
+        let manager = RotationManager::load_or_create(&self.root)?;
+        manager.set_master_key(master);
+        let new_epoch = manager.rotate()?;
+
+        let mut report = RotateReport {
+            new_epoch,
+            ..Default::default()
+        };
+
+        let method = self.config.read().encryption_method;
+        let photos = self.list_photos()?;
+        for mut meta in photos {
+            // Only legacy/epoch single-blob photos participate; chunked,
+            // streamed and named-key photos are skipped.
+            if meta.key_id.is_some()
+                || !meta.chunks.is_empty()
+                || meta.streamed
+                || meta.key_epoch == new_epoch
+            {
+                continue;
+            }
+
+            // Decrypt under the current epoch before locking, mirroring the
+            // share path so we never hold the key lock across blob I/O.
+            let plaintext = self.get_photo(&meta.id)?;
+            let thumb = self.get_thumbnail(&meta.id).ok();
+
+            let (file_key, thumb_key, hmac_key) = {
+                let keys_guard = self.keys.read();
+                let keys = keys_guard.as_ref().ok_or(VaultError::VaultLocked)?;
+                (
+                    Self::epoch_file_key(keys, &meta.id, new_epoch)?,
+                    Self::epoch_thumb_key(keys, &meta.id, new_epoch)?,
+                    keys.hmac_key().clone(),
+                )
+            };
+
+            // Re-encrypt the photo blob under the new epoch key.
+            let encrypted = crate::crypto::encrypt(method, &file_key, &plaintext)?;
+            let new_hmac = compute_hmac(&hmac_key, &encrypted);
+            self.fs.write_file(&format!("photos/{}.enc", meta.id), &encrypted)?;
+            report.photos_reencrypted += 1;
+            report.bytes_rewritten += encrypted.len() as u64;
+
+            // Re-encrypt the thumbnail when present.
+            if let Some(thumb) = thumb {
+                let encrypted_thumb = crate::crypto::encrypt(method, &thumb_key, &thumb)?;
+                self.fs.write_file(&format!("thumbs/{}.enc", meta.id), &encrypted_thumb)?;
+                report.thumbs_reencrypted += 1;
+                report.bytes_rewritten += encrypted_thumb.len() as u64;
+            }
+
+            // Persist the new epoch per-photo so an interrupted run resumes
+            // cleanly, re-processing only the entries still on a stale epoch.
+            meta.key_epoch = new_epoch;
+            meta.hmac = new_hmac;
+            if let Some(ref index) = *self.index.read() {
+                index.update_photo(&meta)?;
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Get photo metadata
     pub fn get_photo_meta(&self, id: &str) -> VaultResult<PhotoMeta> {
         self.ensure_unlocked()?;
@@ -404,20 +1174,143 @@ impl PhotoVault {
         }
     }
     
+    /// Group photos that are visually identical or near-identical.
+    ///
+    /// Photos are clustered when the Hamming distance between their stored
+    /// dHashes is within `threshold` (default [`DEFAULT_DHASH_THRESHOLD`]).
+    /// Transitively-similar photos are merged through union-find, and each
+    /// cluster reports the bytes reclaimable by keeping a single copy (the sum
+    /// of every member's encrypted size bar the largest).
+    pub fn find_duplicates(&self, threshold: Option<u32>) -> VaultResult<Vec<DuplicateCluster>> {
+        let threshold = threshold.unwrap_or(DEFAULT_DHASH_THRESHOLD);
+
+        // Only photos that actually carry a dHash can be compared.
+        let hashed: Vec<PhotoMeta> = self
+            .list_photos()?
+            .into_iter()
+            .filter(|p| p.dhash.is_some())
+            .collect();
+
+        let mut parent: Vec<usize> = (0..hashed.len()).collect();
+        fn find(parent: &mut [usize], mut x: usize) -> usize {
+            while parent[x] != x {
+                parent[x] = parent[parent[x]];
+                x = parent[x];
+            }
+            x
+        }
+
+        for i in 0..hashed.len() {
+            for j in (i + 1)..hashed.len() {
+                let a = hashed[i].dhash.unwrap();
+                let b = hashed[j].dhash.unwrap();
+                if (a ^ b).count_ones() <= threshold {
+                    let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                    if ri != rj {
+                        parent[ri] = rj;
+                    }
+                }
+            }
+        }
+
+        let mut groups: std::collections::HashMap<usize, Vec<PhotoMeta>> =
+            std::collections::HashMap::new();
+        for i in 0..hashed.len() {
+            let root = find(&mut parent, i);
+            groups.entry(root).or_default().push(hashed[i].clone());
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = groups
+            .into_values()
+            .filter(|members| members.len() > 1)
+            .map(|mut members| {
+                members.sort_by(|a, b| a.id.cmp(&b.id));
+                let largest = members.iter().map(|m| m.encrypted_size).max().unwrap_or(0);
+                let total: u64 = members.iter().map(|m| m.encrypted_size).sum();
+                DuplicateCluster {
+                    reclaimable_bytes: total - largest,
+                    photos: members,
+                }
+            })
+            .collect();
+        clusters.sort_by(|a, b| b.reclaimable_bytes.cmp(&a.reclaimable_bytes));
+
+        Ok(clusters)
+    }
+
+    /// Mint a capability token granting read access to `photo_ids` for
+    /// `ttl_secs`, re-encrypting each photo under the token's HKDF-derived
+    /// share key. The returned [`ShareBundle`] is self-contained and can be
+    /// handed to a recipient.
+    pub fn create_share(&self, photo_ids: &[String], ttl_secs: i64) -> VaultResult<crate::sharing::ShareBundle> {
+        self.ensure_unlocked()?;
+
+        let master = self.master_key()?;
+        let expiry = Utc::now().timestamp() + ttl_secs;
+        let token = crate::sharing::ShareToken::mint(&master, photo_ids.to_vec(), expiry)?;
+        let share_key = crate::sharing::share_key(&master, &token.token_id)?;
+
+        let method = self.config.read().encryption_method;
+        let mut blobs = Vec::with_capacity(photo_ids.len());
+        for id in photo_ids {
+            let plaintext = self.get_photo(id)?;
+            let ciphertext = crate::crypto::encrypt(method, &share_key, &plaintext)?;
+            blobs.push((id.clone(), ciphertext));
+        }
+
+        Ok(crate::sharing::ShareBundle { token, blobs })
+    }
+
+    /// Open a [`ShareBundle`], verifying the token's MAC and expiry against the
+    /// vault master before decrypting its blobs. Returns `(photo_id, bytes)`
+    /// pairs for the authorized photos.
+    pub fn open_share(&self, bundle: &crate::sharing::ShareBundle) -> VaultResult<Vec<(String, Vec<u8>)>> {
+        self.ensure_unlocked()?;
+
+        let master = self.master_key()?;
+        let share_key = bundle.token.verify(&master, Utc::now().timestamp())?;
+
+        let mut out = Vec::with_capacity(bundle.blobs.len());
+        for (id, ciphertext) in &bundle.blobs {
+            if !bundle.token.photo_ids.contains(id) {
+                return Err(VaultError::InvalidShareParameters(format!(
+                    "blob {} is not covered by the token",
+                    id
+                )));
+            }
+            let plaintext = crate::crypto::decrypt(&share_key, ciphertext)?;
+            out.push((id.clone(), plaintext));
+        }
+        Ok(out)
+    }
+
     /// Delete photo
     pub fn delete_photo(&self, id: &str) -> VaultResult<()> {
         self.ensure_unlocked()?;
-        
+
+        // Release any chunk references before dropping the metadata.
+        let meta = self.get_photo_meta(id)?;
+        if !meta.chunks.is_empty() {
+            let keys = self.keys.read();
+            let keys = keys.as_ref().unwrap();
+            if let Some(store) = self.chunks.write().as_mut() {
+                store.release(keys, &meta.chunks)?;
+            }
+        }
+
         // Remove from index
         if let Some(ref mut index) = *self.index.write() {
             index.remove_photo(id)?;
         }
-        
+
+        // Record the deletion (tombstone) in the operation log.
+        self.record_op(OpPayload::DeletePhoto { id: id.to_string() })?;
+
         // Delete files
         let photo_path = format!("photos/{}.enc", id);
         let thumb_path = format!("thumbs/{}.enc", id);
-        
-        self.fs.delete_file(&photo_path)?;
+
+        let _ = self.fs.delete_file(&photo_path); // Chunked photos have no blob
         let _ = self.fs.delete_file(&thumb_path); // Thumb might not exist
         
         // Notify AI
@@ -448,7 +1341,7 @@ impl PhotoVault {
         report.integrity_issues = self.verify_all_files()?;
         
         // 4. Run AI self-healing
-        if let Some(ref mut ai) = *self.ai.write() {
+        if let Some(ref ai) = *self.ai.read() {
             report.ai_fixes = ai.heal()?;
         }
         
@@ -482,8 +1375,10 @@ impl PhotoVault {
         let mut errors = 0;
         
         if photos_dir.exists() {
-            let mut new_index = PhotoIndex::create(&self.root, keys)?;
-            
+            let new_index = PhotoIndex::create(&self.root, keys)?;
+            // Refresh the derived tag/flag tables from the encrypted blobs.
+            new_index.rebuild_derived()?;
+
             for entry in std::fs::read_dir(&photos_dir)? {
                 if let Ok(entry) = entry {
                     let filename = entry.file_name().to_string_lossy().to_string();
@@ -519,28 +1414,250 @@ impl PhotoVault {
         Ok(issues)
     }
     
+    // ═══════════════════════════════════════════════════════════════════════
+    // NAMED KEY MANAGEMENT
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Register a new named key, wrapping its AEAD key under the master key.
+    ///
+    /// Returns the generated key id. The newly registered key is left mounted.
+    pub fn register_key(&self, name: &str, secret: &[u8]) -> VaultResult<String> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+
+        let id = Uuid::new_v4().to_string();
+        // Derive the album AEAD key from the caller-supplied secret.
+        let album_key = crate::crypto::derive_key(secret, id.as_bytes(), b"ALFA:ALBUMKEY:v1")?;
+
+        // Wrap the album key under the master (index) key.
+        let wrapped = encrypt_aes_gcm(keys.index_key(), album_key.expose(), &[])?;
+
+        let entry = VaultKeyEntry {
+            id: id.clone(),
+            name: name.to_string(),
+            automount: false,
+            photo_count: 0,
+            wrapped_key: wrapped.to_bytes(),
+        };
+
+        {
+            let mut registry = self.key_registry.write();
+            registry.entries.push(entry);
+            if registry.default_key.is_none() {
+                registry.default_key = Some(id.clone());
+            }
+            let snapshot = registry.clone();
+            drop(registry);
+            self.persist_registry(keys, &snapshot)?;
+        }
+
+        self.mounted_keys.write().insert(id.clone(), album_key);
+        Ok(id)
+    }
+
+    /// Mount a named key so photos encrypted under it can be accessed.
+    pub fn mount_key(&self, id: &str) -> VaultResult<()> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+
+        let wrapped = {
+            let registry = self.key_registry.read();
+            registry
+                .entries
+                .iter()
+                .find(|e| e.id == id)
+                .map(|e| e.wrapped_key.clone())
+                .ok_or_else(|| VaultError::KeyNotFound(id.to_string()))?
+        };
+
+        let encrypted = EncryptedData::parse(&wrapped)?;
+        let album_bytes = decrypt_aes_gcm(keys.index_key(), &encrypted, &[])?;
+        if album_bytes.len() != crate::crypto::KEY_LEN {
+            return Err(VaultError::KeyDerivationFailed("wrapped key length".into()));
+        }
+        let mut key = [0u8; crate::crypto::KEY_LEN];
+        key.copy_from_slice(&album_bytes);
+        self.mounted_keys
+            .write()
+            .insert(id.to_string(), VaultKey::new(key));
+        Ok(())
+    }
+
+    /// Mount a named key only if `secret` re-derives the same album key that
+    /// was registered, so an unlocked vault still can't mount an album without
+    /// its own PIN. Returns [`VaultError::InvalidPin`] on mismatch.
+    pub fn mount_key_with(&self, id: &str, secret: &[u8]) -> VaultResult<()> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+
+        let wrapped = {
+            let registry = self.key_registry.read();
+            registry
+                .entries
+                .iter()
+                .find(|e| e.id == id)
+                .map(|e| e.wrapped_key.clone())
+                .ok_or_else(|| VaultError::KeyNotFound(id.to_string()))?
+        };
+
+        let encrypted = EncryptedData::parse(&wrapped)?;
+        let album_bytes = decrypt_aes_gcm(keys.index_key(), &encrypted, &[])?;
+        let expected = crate::crypto::derive_key(secret, id.as_bytes(), b"ALFA:ALBUMKEY:v1")?;
+        if album_bytes.len() != crate::crypto::KEY_LEN
+            || !constant_time_eq(&album_bytes, expected.expose())
+        {
+            return Err(VaultError::InvalidPin);
+        }
+        let mut key = [0u8; crate::crypto::KEY_LEN];
+        key.copy_from_slice(&album_bytes);
+        self.mounted_keys
+            .write()
+            .insert(id.to_string(), VaultKey::new(key));
+        Ok(())
+    }
+
+    /// Unmount a named key, zeroizing its material.
+    pub fn unmount_key(&self, id: &str) {
+        self.mounted_keys.write().remove(id);
+    }
+
+    /// Unmount all named keys.
+    pub fn unmount_all(&self) {
+        self.mounted_keys.write().clear();
+    }
+
+    /// Set the default key used by `import_photo` when none is given.
+    pub fn set_default_key(&self, id: &str) -> VaultResult<()> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+
+        let mut registry = self.key_registry.write();
+        if !registry.entries.iter().any(|e| e.id == id) {
+            return Err(VaultError::KeyNotFound(id.to_string()));
+        }
+        registry.default_key = Some(id.to_string());
+        let snapshot = registry.clone();
+        drop(registry);
+        self.persist_registry(keys, &snapshot)
+    }
+
+    /// Toggle the automount flag for a named key.
+    pub fn set_key_automount(&self, id: &str, automount: bool) -> VaultResult<()> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().unwrap();
+
+        let mut registry = self.key_registry.write();
+        let entry = registry
+            .entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| VaultError::KeyNotFound(id.to_string()))?;
+        entry.automount = automount;
+        let snapshot = registry.clone();
+        drop(registry);
+        self.persist_registry(keys, &snapshot)
+    }
+
+    /// List registered keys with their mount state and photo counts.
+    pub fn list_keys(&self) -> Vec<KeyInfo> {
+        let mounted = self.mounted_keys.read();
+        let registry = self.key_registry.read();
+        registry
+            .entries
+            .iter()
+            .map(|e| KeyInfo {
+                id: e.id.clone(),
+                name: e.name.clone(),
+                mounted: mounted.contains_key(&e.id),
+                automount: e.automount,
+                photo_count: e.photo_count,
+            })
+            .collect()
+    }
+
+    /// The default key id if one is set and currently mounted.
+    fn default_mounted_key(&self) -> Option<String> {
+        let id = self.key_registry.read().default_key.clone()?;
+        if self.mounted_keys.read().contains_key(&id) {
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    /// Mount every key flagged for automount (best-effort).
+    fn automount_keys(&self) {
+        let ids: Vec<String> = {
+            let registry = self.key_registry.read();
+            registry
+                .entries
+                .iter()
+                .filter(|e| e.automount)
+                .map(|e| e.id.clone())
+                .collect()
+        };
+        for id in ids {
+            let _ = self.mount_key(&id);
+        }
+    }
+
+    /// Load the named-key registry from disk (requires keys to be present).
+    fn load_registry(&self) -> VaultResult<()> {
+        let keys = self.keys.read();
+        let keys = keys.as_ref().ok_or(VaultError::VaultLocked)?;
+        let registry = match self.fs.read_file("keys.enc") {
+            Ok(bytes) => {
+                let encrypted = EncryptedData::parse(&bytes)?;
+                let data = decrypt_aes_gcm(keys.index_key(), &encrypted, &[])?;
+                serde_json::from_slice(&data)?
+            }
+            // Older vaults predate the registry; start empty.
+            Err(_) => KeyRegistry::default(),
+        };
+        *self.key_registry.write() = registry;
+        Ok(())
+    }
+
+    /// Persist the named-key registry, encrypted under the master key.
+    fn persist_registry(&self, keys: &KeyManager, registry: &KeyRegistry) -> VaultResult<()> {
+        let bytes = serde_json::to_vec(registry)?;
+        let encrypted = encrypt_aes_gcm(keys.index_key(), &bytes, &[])?;
+        self.fs.write_file("keys.enc", &encrypted.to_bytes())
+    }
+
     // ═══════════════════════════════════════════════════════════════════════
     // HELPERS
     // ═══════════════════════════════════════════════════════════════════════
-    
-    /// Derive master seed from PIN using Argon2id
-    fn derive_seed_from_pin(pin: &str) -> VaultResult<[u8; 64]> {
+
+    /// Derive the PIN-wrapping key (KEK) using Argon2id over the vault salt.
+    fn derive_pin_kek(pin: &str, salt: &[u8], mem_kib: u32, time: u32, par: u32) -> VaultResult<VaultKey> {
         use argon2::{Argon2, Params, Version, Algorithm};
-        
-        // Fixed salt (should be stored with vault in production)
-        let salt = b"ALFA_PHOTOS_VAULT_SALT_v1";
-        
-        let params = Params::new(65536, 3, 4, Some(64))
+
+        let params = Params::new(mem_kib, time, par, Some(32))
             .map_err(|e| VaultError::KeyDerivationFailed(e.to_string()))?;
-        
         let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
-        
-        let mut seed = [0u8; 64];
+
+        let mut kek = [0u8; 32];
         argon2
-            .hash_password_into(pin.as_bytes(), salt, &mut seed)
+            .hash_password_into(pin.as_bytes(), salt, &mut kek)
             .map_err(|e| VaultError::KeyDerivationFailed(e.to_string()))?;
-        
-        Ok(seed)
+        Ok(VaultKey::new(kek))
+    }
+
+    /// Derive the recovery-phrase-wrapping key via PBKDF2-HMAC-SHA512.
+    ///
+    /// Follows the BIP39 seed scheme: salt = `"mnemonic"` + passphrase (empty
+    /// here), 2048 rounds, 64-byte output; the first 32 bytes form the KEK.
+    fn derive_phrase_kek(words: &str) -> VaultResult<VaultKey> {
+        let seed = pbkdf2_hmac_sha512(words.as_bytes(), b"mnemonic", 2048);
+        let mut kek = [0u8; 32];
+        kek.copy_from_slice(&seed[..32]);
+        Ok(VaultKey::new(kek))
     }
     
     /// Ensure vault is unlocked
@@ -587,28 +1704,396 @@ impl PhotoVault {
         }
     }
     
+    // ═══════════════════════════════════════════════════════════════════════
+    // OPERATION LOG & MULTI-DEVICE SYNC
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Load the persisted operation log (empty when none exists yet).
+    fn load_ops(&self, key: &VaultKey) -> VaultResult<Vec<Op>> {
+        match self.fs.read_file("oplog.enc") {
+            Ok(bytes) => {
+                let plain = crate::crypto::decrypt(key, &bytes)?;
+                Ok(serde_json::from_slice(&plain)?)
+            }
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Persist the full ordered op set as a single encrypted blob.
+    fn persist_ops(&self, keys: &KeyManager, ops: &[Op]) -> VaultResult<()> {
+        let method = self.config.read().encryption_method;
+        let bytes = serde_json::to_vec(ops)?;
+        let blob = crate::crypto::encrypt(method, keys.index_key(), &bytes)?;
+        self.fs.write_file("oplog.enc", &blob)
+    }
+
+    /// Append an op to the log and persist it, reusing an already-held key
+    /// reference (the caller must ensure the vault is unlocked).
+    fn append_op(&self, keys: &KeyManager, payload: OpPayload) -> VaultResult<()> {
+        let wall = Utc::now().timestamp_millis().max(0) as u64;
+        let ops = {
+            let mut guard = self.oplog.write();
+            let log = guard.as_mut().ok_or(VaultError::VaultLocked)?;
+            log.record(wall, payload);
+            log.ordered()
+        };
+        self.persist_ops(keys, &ops)
+    }
+
+    /// Record a mutating operation in the local log and persist it.
+    ///
+    /// Mutating API calls funnel through here so two devices sharing a vault
+    /// can converge by replaying ops; the op is stamped with a hybrid logical
+    /// timestamp for deterministic total ordering.
+    pub fn record_op(&self, payload: OpPayload) -> VaultResult<()> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().ok_or(VaultError::VaultLocked)?;
+        self.append_op(keys, payload)
+    }
+
+    /// Fetch and decrypt the newest checkpoint from a remote, if any.
+    fn fetch_latest_checkpoint(
+        &self,
+        remote: &dyn BlobStore,
+        key: &VaultKey,
+    ) -> VaultResult<Option<crate::oplog::Checkpoint>> {
+        let mut keys = remote.list("cp_")?;
+        keys.sort();
+        match keys.last() {
+            Some(k) => {
+                let bytes = remote.fetch(k)?;
+                let plain = crate::crypto::decrypt(key, &bytes)?;
+                Ok(serde_json::from_slice(&plain).ok())
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Synchronize with another device through a [`BlobStore`].
+    ///
+    /// Pushes local ops, merges remote ops, resolves the combined stream on top
+    /// of the newest checkpoint (last-writer-wins per field, deletes tombstone
+    /// the id), applies the result to the local index, and periodically writes
+    /// a fresh checkpoint. Returns the number of new remote ops merged.
+    pub fn sync(&self, remote: &dyn BlobStore) -> VaultResult<usize> {
+        self.ensure_unlocked()?;
+        let keys = self.keys.read();
+        let keys = keys.as_ref().ok_or(VaultError::VaultLocked)?;
+        let method = self.config.read().encryption_method;
+        let index_key = keys.index_key();
+
+        // 1. Push local ops, each encrypted individually under the data key.
+        {
+            let guard = self.oplog.read();
+            let log = guard.as_ref().ok_or(VaultError::VaultLocked)?;
+            for op in log.ordered() {
+                let blob = crate::crypto::encrypt(method, index_key, &serde_json::to_vec(&op)?)?;
+                remote.put(&op_key(&op), &blob)?;
+            }
+        }
+
+        // 2. Fetch and merge remote ops.
+        let mut incoming = Vec::new();
+        for key in remote.list("op_")? {
+            let bytes = remote.fetch(&key)?;
+            let plain = crate::crypto::decrypt(index_key, &bytes)?;
+            if let Ok(op) = serde_json::from_slice::<Op>(&plain) {
+                incoming.push(op);
+            }
+        }
+        let added = {
+            let mut guard = self.oplog.write();
+            let log = guard.as_mut().ok_or(VaultError::VaultLocked)?;
+            log.merge(incoming)
+        };
+
+        // 3. Resolve: newest checkpoint, then replay everything after it.
+        let checkpoint = self.fetch_latest_checkpoint(remote, index_key)?;
+        let after = checkpoint.as_ref().and_then(|c| c.ts.clone());
+        let ordered = self.oplog.read().as_ref().map(|l| l.ordered()).unwrap_or_default();
+        let to_replay: Vec<&Op> = ordered
+            .iter()
+            .filter(|op| after.as_ref().map(|t| &op.ts > t).unwrap_or(true))
+            .collect();
+        let mut state = ResolvedState::from_checkpoint(checkpoint.as_ref());
+        state.apply_all(to_replay);
+
+        // 4. Apply the resolved state to the local index.
+        if let Some(ref index) = *self.index.read() {
+            for meta in state.photos() {
+                index.update_photo(meta)?;
+            }
+            for id in state.tombstones() {
+                let _ = index.remove_photo(id);
+            }
+        }
+
+        // 5. Persist local ops; write a checkpoint every CHECKPOINT_INTERVAL ops.
+        self.persist_ops(keys, &ordered)?;
+        if checkpoint.is_none() || ordered.len() as u64 % CHECKPOINT_INTERVAL == 0 {
+            let last_ts = ordered.last().map(|o| o.ts.clone());
+            let cp = state.to_checkpoint(last_ts.clone());
+            let marker = last_ts.map(|t| t.wall_ms).unwrap_or(0);
+            let cp_key = format!("cp_{marker:020}");
+            let blob = crate::crypto::encrypt(method, index_key, &serde_json::to_vec(&cp)?)?;
+            remote.put(&cp_key, &blob)?;
+        }
+
+        Ok(added)
+    }
+
     /// Compute perceptual hash for duplicate detection
+    ///
+    /// Decoding and hashing run inside `catch_unwind` because the `image`
+    /// decoder is untrusted input that can panic on malformed files; a panic
+    /// here simply yields `None` so import proceeds without a perceptual hash.
     fn compute_phash(&self, data: &[u8]) -> Option<String> {
         use image::io::Reader as ImageReader;
         use std::io::Cursor;
-        
-        // Try to decode image
-        let img = ImageReader::new(Cursor::new(data))
-            .with_guessed_format()
-            .ok()?
-            .decode()
-            .ok()?;
-        
-        // Compute perceptual hash
-        let hasher = img_hash::HasherConfig::new()
-            .hash_size(16, 16)
-            .to_hasher();
-        
-        let hash = hasher.hash_image(&img);
-        Some(hash.to_base64())
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            // Try to decode image
+            let img = ImageReader::new(Cursor::new(data))
+                .with_guessed_format()
+                .ok()?
+                .decode()
+                .ok()?;
+
+            // Compute perceptual hash
+            let hasher = img_hash::HasherConfig::new()
+                .hash_size(16, 16)
+                .to_hasher();
+
+            let hash = hasher.hash_image(&img);
+            Some(hash.to_base64())
+        }));
+
+        result.unwrap_or(None)
+    }
+
+    /// Compute a BlurHash placeholder from raw image bytes.
+    ///
+    /// Runs under `catch_unwind` for the same reason as [`compute_phash`](Self::compute_phash):
+    /// the decoder is untrusted input. Returns `None` when the bytes do not
+    /// decode or the encoder rejects the grid.
+    fn compute_blurhash(data: &[u8]) -> Option<String> {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let img = image::load_from_memory(data).ok()?;
+            // A 4x3 grid keeps the string short while still hinting orientation.
+            crate::blurhash::encode(&img, 4, 3)
+        }));
+        result.unwrap_or(None)
+    }
+
+    /// Best-effort EXIF extraction from raw image bytes.
+    ///
+    /// Never fails: missing, truncated, or garbage EXIF yields an all-`None`
+    /// [`ExifData`] so a bad camera blob cannot block an import. Runs under
+    /// `catch_unwind` for the same untrusted-decoder reason as
+    /// [`compute_phash`](Self::compute_phash).
+    fn extract_exif(data: &[u8]) -> ExifData {
+        let parsed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            exif::Reader::new()
+                .read_from_container(&mut std::io::Cursor::new(data))
+                .ok()
+        }))
+        .ok()
+        .flatten();
+
+        let exif = match parsed {
+            Some(e) => e,
+            None => return ExifData::default(),
+        };
+
+        use exif::{In, Tag};
+
+        let ascii = |tag: Tag| {
+            exif.get_field(tag, In::PRIMARY).and_then(|f| match &f.value {
+                exif::Value::Ascii(v) => v
+                    .first()
+                    .map(|s| String::from_utf8_lossy(s).trim().to_string())
+                    .filter(|s| !s.is_empty()),
+                _ => None,
+            })
+        };
+
+        let created_at = ascii(Tag::DateTimeOriginal)
+            .or_else(|| ascii(Tag::DateTime))
+            .and_then(|s| parse_exif_datetime(&s));
+
+        let orientation = exif
+            .get_field(Tag::Orientation, In::PRIMARY)
+            .and_then(|f| f.value.get_uint(0))
+            .map(|v| v as u16)
+            .filter(|&v| (1..=8).contains(&v));
+
+        let gps_latitude = gps_coordinate(&exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, b'S');
+        let gps_longitude = gps_coordinate(&exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, b'W');
+
+        ExifData {
+            created_at,
+            camera_make: ascii(Tag::Make),
+            camera_model: ascii(Tag::Model),
+            gps_latitude,
+            gps_longitude,
+            orientation,
+        }
+    }
+
+    /// Get the BlurHash placeholder for a photo, if one was computed on import.
+    pub fn get_blurhash(&self, id: &str) -> VaultResult<Option<String>> {
+        self.ensure_unlocked()?;
+        Ok(self.get_photo_meta(id)?.blurhash)
+    }
+
+    /// Scan stored photos for ones whose bytes no longer decode as an image.
+    ///
+    /// Mirrors [`verify_all_files`](Self::verify_all_files) but focuses on
+    /// decode health: every stored photo is fetched and fed to the decoder
+    /// under `catch_unwind`, and the ids that fail (either a decode error or a
+    /// panic on crafted/corrupt data) are returned so users can find broken
+    /// imports. The decoder is treated as untrusted throughout.
+    pub fn scan_broken_images(&self) -> VaultResult<Vec<String>> {
+        self.ensure_unlocked()?;
+
+        let mut broken = Vec::new();
+        for meta in self.list_photos()? {
+            // Only raster images are expected to decode; skip anything else.
+            if !meta.mime_type.starts_with("image/") {
+                continue;
+            }
+            let data = match self.get_photo(&meta.id) {
+                Ok(data) => data,
+                Err(_) => {
+                    broken.push(meta.id);
+                    continue;
+                }
+            };
+            let ok = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                image::load_from_memory(&data).is_ok()
+            }))
+            .unwrap_or(false);
+            if !ok {
+                broken.push(meta.id);
+            }
+        }
+        Ok(broken)
     }
 }
 
+/// Blob-store key for an op, ordered lexicographically by its timestamp.
+fn op_key(op: &Op) -> String {
+    format!(
+        "op_{:020}_{:020}_{}",
+        op.ts.wall_ms, op.ts.counter, op.ts.actor
+    )
+}
+
+/// Length-independent constant-time byte comparison for key material.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+/// Capture metadata parsed (best-effort) from a photo's EXIF block.
+#[derive(Debug, Clone, Default)]
+struct ExifData {
+    created_at: Option<DateTime<Utc>>,
+    camera_make: Option<String>,
+    camera_model: Option<String>,
+    gps_latitude: Option<f64>,
+    gps_longitude: Option<f64>,
+    orientation: Option<u16>,
+}
+
+/// Parse an EXIF `DateTimeOriginal`/`DateTime` string ("YYYY:MM:DD HH:MM:SS").
+///
+/// EXIF carries no timezone, so the value is interpreted as UTC — good enough
+/// for sorting and coarse date filtering.
+fn parse_exif_datetime(s: &str) -> Option<DateTime<Utc>> {
+    use chrono::{NaiveDateTime, TimeZone};
+    let naive = NaiveDateTime::parse_from_str(s.trim(), "%Y:%m:%d %H:%M:%S").ok()?;
+    Some(Utc.from_utc_datetime(&naive))
+}
+
+/// Resolve a GPS coordinate to signed decimal degrees, applying the S/W ref.
+fn gps_coordinate(
+    exif: &exif::Exif,
+    coord: exif::Tag,
+    reference: exif::Tag,
+    negative_ref: u8,
+) -> Option<f64> {
+    use exif::{In, Value};
+
+    let field = exif.get_field(coord, In::PRIMARY)?;
+    let dms = match &field.value {
+        Value::Rational(r) if r.len() >= 3 => [r[0].to_f64(), r[1].to_f64(), r[2].to_f64()],
+        _ => return None,
+    };
+    let mut degrees = dms[0] + dms[1] / 60.0 + dms[2] / 3600.0;
+
+    let is_negative = exif
+        .get_field(reference, In::PRIMARY)
+        .and_then(|f| match &f.value {
+            Value::Ascii(v) => v.first().and_then(|s| s.first().copied()),
+            _ => None,
+        })
+        .map(|c| c.eq_ignore_ascii_case(&negative_ref))
+        .unwrap_or(false);
+    if is_negative {
+        degrees = -degrees;
+    }
+    Some(degrees)
+}
+
+/// An in-progress chunked import, held across `import_begin`/`import_chunk`/
+/// `import_end` calls (e.g. behind a JNI handle). Plaintext is sealed and
+/// written out a chunk at a time by [`PhotoVault::import_chunk`] as it
+/// arrives, so this session never buffers the whole photo — only the
+/// in-flight [`StreamEncryptor`](crate::crypto::StreamEncryptor) chunk and a
+/// short sniff prefix used for MIME detection. Dropping a session before
+/// [`import_end`](PhotoVault::import_end) discards the partial `.tmp` file via
+/// [`StreamWriteHandle`](crate::secure_fs::StreamWriteHandle)'s own `Drop`.
+pub struct ImportSession {
+    id: String,
+    original_name: String,
+    key_id: Option<String>,
+    encryptor: Option<crate::crypto::StreamEncryptor>,
+    handle: Option<crate::secure_fs::StreamWriteHandle>,
+    total_len: u64,
+    sniff: Vec<u8>,
+}
+
+/// A group of near-identical photos found by [`PhotoVault::find_duplicates`].
+#[derive(Debug, Clone)]
+pub struct DuplicateCluster {
+    /// Members of the cluster, sorted by id.
+    pub photos: Vec<PhotoMeta>,
+    /// Bytes reclaimable by keeping a single copy (total encrypted size of the
+    /// cluster minus its largest member).
+    pub reclaimable_bytes: u64,
+}
+
+/// Summary of a key-rotation pass.
+#[derive(Debug, Default)]
+pub struct RotateReport {
+    /// Epoch the vault now encrypts under.
+    pub new_epoch: u64,
+    /// Photos re-encrypted to the new epoch.
+    pub photos_reencrypted: usize,
+    /// Thumbnails re-encrypted to the new epoch.
+    pub thumbs_reencrypted: usize,
+    /// Total ciphertext bytes written during the pass.
+    pub bytes_rewritten: u64,
+}
+
 /// Report from reset operation
 #[derive(Debug, Default)]
 pub struct ResetReport {